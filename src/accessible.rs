@@ -0,0 +1,193 @@
+//! `--accessible`: the lock as plain lines of text a screen reader can follow,
+//! instead of a screen that is redrawn in place.
+
+use std::{collections::HashMap, time::Duration};
+
+use crossterm::{execute, style::Print};
+
+use crate::{
+    commands::Command,
+    controller::{DrawContext, EntityId, EventContext, UpdateContext, UpdateResult},
+    count_down_entity::Trigger,
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, markup, session,
+};
+
+/// Prints what happens during the lock as new lines: the intro once, then the
+/// minutes left each time that number drops, errors and other messages.
+/// Set the `message` property to announce an error and `info` for anything
+/// else, like `MessageAreaEntity`.
+pub struct AnnouncerEntity {
+    id: String,
+    prompt: Option<String>,
+    pending: Vec<String>,
+    lines: Vec<String>,
+    minutes_left: Option<u64>,
+    hide_time_left: bool,
+    properties: HashMap<String, String>,
+}
+
+impl AnnouncerEntity {
+    pub fn new(id: &str, intro: Vec<String>) -> Self {
+        AnnouncerEntity {
+            id: format!("AnnouncerEntity-{id}"),
+            prompt: None,
+            pending: intro,
+            lines: Vec::new(),
+            minutes_left: None,
+            hide_time_left: false,
+            properties: HashMap::new(),
+        }
+    }
+
+    /// Repeat `prompt` after the intro and after every error.
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = Some(prompt.to_string());
+    }
+
+    /// Don't announce the minutes left, for `--hide-timer`.
+    pub fn hide_time_left(&mut self) {
+        self.hide_time_left = true;
+    }
+
+    fn announce_prompt(&mut self) {
+        if let Some(prompt) = &self.prompt {
+            self.pending.push(prompt.trim_end().to_string());
+        }
+    }
+
+    fn announce_minutes(&mut self, remaining: Duration) {
+        let minutes = remaining.as_secs().div_ceil(60);
+        if self.minutes_left.replace(minutes) == Some(minutes) || minutes == 0 {
+            return;
+        }
+        if self.hide_time_left {
+            return;
+        }
+        self.pending.push(if minutes == 1 {
+            i18n::t("accessible.minute_left").to_string()
+        } else {
+            i18n::tr("accessible.minutes_left", &[("minutes", &minutes)])
+        });
+    }
+}
+
+/// `text` without markup and the emoji and symbols it starts with, which
+/// screen readers either skip or spell out.
+fn spoken(text: &str) -> String {
+    markup::plain_text(text)
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_string()
+}
+
+impl Named for AnnouncerEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for AnnouncerEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(|s| s.as_str())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "message" => {
+                self.pending
+                    .push(i18n::tr("accessible.error", &[("message", &spoken(value))]));
+                self.announce_prompt();
+            }
+            "info" => self.pending.push(spoken(value)),
+            _ => {
+                self.properties.insert(key.to_string(), value.to_string());
+            }
+        }
+        true
+    }
+}
+
+impl FullEntity for AnnouncerEntity {}
+
+impl Entity for AnnouncerEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        for line in &self.lines {
+            // Raw mode, so no implicit carriage return
+            execute!(draw_context.out, Print(format!("{line}\r\n")))?;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        let stats = session::stats();
+        if stats.locked {
+            let first = self.minutes_left.is_none();
+            self.announce_minutes(stats.remaining);
+            if first {
+                self.announce_prompt();
+            }
+        }
+        self.lines = std::mem::take(&mut self.pending);
+        UpdateResult::focus()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(Duration::from_secs(1))
+    }
+}
+
+/// Keeps an entity working, but without drawing anything, for the parts of
+/// the lock the announcer speaks for.
+pub struct Muted<T: FullEntity> {
+    delegate_entity: T,
+}
+
+impl<T: FullEntity> Muted<T> {
+    pub fn new(delegate_entity: T) -> Self {
+        Muted { delegate_entity }
+    }
+}
+
+impl<T: FullEntity> Named for Muted<T> {
+    fn get_name(&self) -> &str {
+        self.delegate_entity.get_name()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.delegate_entity.entity_id()
+    }
+}
+
+impl<T: FullEntity> HasProperties for Muted<T> {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        self.delegate_entity.get_property(key)
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        self.delegate_entity.set_property(key, value)
+    }
+}
+
+impl<T: FullEntity> Entity for Muted<T> {
+    fn draw(&self, _: &mut DrawContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        self.delegate_entity.update(context)
+    }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        self.delegate_entity.handle_event(event)
+    }
+
+    fn handle_command(&mut self, command: Command, trigger: Trigger) -> bool {
+        self.delegate_entity.handle_command(command, trigger)
+    }
+
+    fn cadence(&self) -> Cadence {
+        self.delegate_entity.cadence()
+    }
+}
+
+impl<T: FullEntity> FullEntity for Muted<T> {}
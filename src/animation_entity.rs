@@ -0,0 +1,230 @@
+use std::{collections::VecDeque, io::Write, time::Duration};
+
+use clap::ValueEnum;
+use crossterm::{
+    cursor::MoveTo,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    QueueableCommand,
+};
+use rand::Rng;
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    layout, theme,
+};
+
+const MATRIX_GLYPHS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ#$%&*+=<>";
+const MATRIX_TRAIL: u16 = 8;
+
+fn matrix_glyph(rng: &mut impl Rng) -> String {
+    let idx = rng.gen_range(0..MATRIX_GLYPHS.len());
+    (MATRIX_GLYPHS[idx] as char).to_string()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ScreensaverStyle {
+    /// Falling green glyphs
+    Matrix,
+    /// A lock bouncing off the edges
+    Bounce,
+    /// Twinkling stars
+    Stars,
+}
+
+/// The part of the screen below the lock UI that is free to animate in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Region {
+    top: u16,
+    width: u16,
+    height: u16,
+}
+
+enum State {
+    Matrix { heads: Vec<Option<u16>> },
+    Bounce { x: u16, y: u16, dx: i16, dy: i16 },
+    Stars { stars: VecDeque<(u16, u16)> },
+}
+
+/// Steps made at most per update, so a stalled loop catches up a little
+/// instead of flooding the terminal.
+const MAX_STEPS: u32 = 4;
+
+/// Fills the otherwise unused bottom of the screen with a cheap animation.
+/// Every update produces only the cells that changed, so frames stay small.
+pub struct AnimationEntity {
+    id: String,
+    style: ScreensaverStyle,
+    region: Region,
+    state: State,
+    cells: Vec<(u16, u16, String, Color)>,
+    /// Time not yet turned into steps
+    pending: Duration,
+}
+
+impl AnimationEntity {
+    pub fn new(id: &str, style: ScreensaverStyle) -> Self {
+        AnimationEntity {
+            id: format!("AnimationEntity-{id}"),
+            style,
+            region: Region::default(),
+            state: Self::initial_state(style, Region::default()),
+            cells: Vec::new(),
+            pending: Duration::ZERO,
+        }
+    }
+
+    /// How long one step of the animation takes.
+    fn step_interval(&self) -> Duration {
+        match self.style {
+            ScreensaverStyle::Matrix => Duration::from_millis(80),
+            ScreensaverStyle::Bounce => Duration::from_millis(120),
+            ScreensaverStyle::Stars => Duration::from_millis(250),
+        }
+    }
+
+    fn initial_state(style: ScreensaverStyle, region: Region) -> State {
+        match style {
+            ScreensaverStyle::Matrix => State::Matrix {
+                heads: vec![None; region.width as usize],
+            },
+            ScreensaverStyle::Bounce => State::Bounce {
+                x: 0,
+                y: 0,
+                dx: 1,
+                dy: 1,
+            },
+            ScreensaverStyle::Stars => State::Stars {
+                stars: VecDeque::new(),
+            },
+        }
+    }
+
+    fn current_region() -> Region {
+        let (width, _) = layout::terminal_size();
+        let top = layout::hint_y() + 2;
+        Region {
+            top,
+            width,
+            height: layout::footer_y().saturating_sub(top),
+        }
+    }
+
+    fn step(&mut self) {
+        let mut rng = rand::thread_rng();
+        let region = self.region;
+        let cells = &mut self.cells;
+        match &mut self.state {
+            State::Matrix { heads } => {
+                for (x, head) in heads.iter_mut().enumerate() {
+                    let x = x as u16;
+                    match *head {
+                        None => {
+                            if rng.gen_bool(0.02) {
+                                *head = Some(0);
+                                cells.push((x, 0, matrix_glyph(&mut rng), Color::White));
+                            }
+                        }
+                        Some(y) => {
+                            if y < region.height {
+                                cells.push((x, y, matrix_glyph(&mut rng), Color::Green));
+                            }
+                            if y + 1 < region.height {
+                                cells.push((x, y + 1, matrix_glyph(&mut rng), Color::White));
+                            }
+                            if y >= MATRIX_TRAIL && y - MATRIX_TRAIL < region.height {
+                                cells.push((x, y - MATRIX_TRAIL, " ".to_string(), Color::Reset));
+                            }
+                            *head = if y > region.height + MATRIX_TRAIL {
+                                None
+                            } else {
+                                Some(y + 1)
+                            };
+                        }
+                    }
+                }
+            }
+            State::Bounce { x, y, dx, dy } => {
+                // The lock glyph is two columns wide
+                let max_x = region.width.saturating_sub(2) as i16;
+                let max_y = region.height.saturating_sub(1) as i16;
+                cells.push((*x, *y, "  ".to_string(), Color::Reset));
+                let mut nx = *x as i16 + *dx;
+                let mut ny = *y as i16 + *dy;
+                if nx < 0 || nx > max_x {
+                    *dx = -*dx;
+                    nx = (*x as i16 + *dx).clamp(0, max_x);
+                }
+                if ny < 0 || ny > max_y {
+                    *dy = -*dy;
+                    ny = (*y as i16 + *dy).clamp(0, max_y);
+                }
+                *x = nx as u16;
+                *y = ny as u16;
+                cells.push((*x, *y, theme::current().lock.to_string(), Color::Reset));
+            }
+            State::Stars { stars } => {
+                let capacity = (region.width as usize * region.height as usize) / 40;
+                while stars.len() >= capacity.max(1) {
+                    let Some((x, y)) = stars.pop_front() else {
+                        break;
+                    };
+                    cells.push((x, y, " ".to_string(), Color::Reset));
+                }
+                let x = rng.gen_range(0..region.width);
+                let y = rng.gen_range(0..region.height);
+                let glyph = theme::current().stars[rng.gen_range(0..4)];
+                let color = [Color::White, Color::Yellow, Color::Cyan][rng.gen_range(0..3)];
+                stars.push_back((x, y));
+                cells.push((x, y, glyph.to_string(), color));
+            }
+        }
+    }
+}
+
+impl Named for AnimationEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for AnimationEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        for (x, y, glyph, color) in &self.cells {
+            draw_context
+                .out
+                .queue(MoveTo(*x, self.region.top + *y))?
+                .queue(SetForegroundColor(*color))?
+                .queue(Print(glyph))?;
+        }
+        draw_context.out.queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        self.cells.clear();
+        let region = Self::current_region();
+        if region != self.region {
+            // Whatever was drawn in the old region is cleared by the layout change
+            self.region = region;
+            self.state = Self::initial_state(self.style, region);
+        }
+        // Step as often as the time that passed calls for, however late the
+        // update came
+        let interval = self.step_interval();
+        self.pending += context.dt;
+        let steps = (self.pending.as_nanos() / interval.as_nanos()) as u32;
+        self.pending -= interval * steps;
+        if region.width > 2 && region.height > 0 {
+            for _ in 0..steps.min(MAX_STEPS) {
+                self.step();
+            }
+        }
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(self.step_interval().saturating_sub(self.pending))
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::Write;
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::DrawContext,
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout, theme,
+};
+
+/// Shows on a footer row how many unlock attempts failed, in the warning
+/// color from halfway to `threshold` on and in the error color from there.
+/// The count arrives in the `failed_attempts` property.
+pub struct AttemptCounterEntity {
+    id: String,
+    row: u16,
+    threshold: Option<u32>,
+    failed_attempts: u32,
+    text: String,
+}
+
+impl AttemptCounterEntity {
+    /// `row` is the line within the footer.
+    pub fn new(id: &str, row: u16, failed_attempts: u32, threshold: Option<u32>) -> Self {
+        let mut entity = AttemptCounterEntity {
+            id: format!("AttemptCounterEntity-{id}"),
+            row,
+            threshold,
+            failed_attempts: 0,
+            text: String::new(),
+        };
+        entity.set_failed_attempts(failed_attempts);
+        entity
+    }
+
+    fn set_failed_attempts(&mut self, failed_attempts: u32) {
+        self.failed_attempts = failed_attempts;
+        self.text = failed_attempts.to_string();
+    }
+}
+
+impl Named for AttemptCounterEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for AttemptCounterEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        (key == "failed_attempts").then_some(self.text.as_str())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        let Some(failed_attempts) = value.parse().ok().filter(|_| key == "failed_attempts") else {
+            return false;
+        };
+        self.set_failed_attempts(failed_attempts);
+        true
+    }
+}
+
+impl FullEntity for AttemptCounterEntity {}
+
+impl Entity for AttemptCounterEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let theme = theme::current();
+        let color = match self.threshold {
+            Some(threshold) if self.failed_attempts >= threshold => theme.error,
+            Some(threshold) if self.failed_attempts * 2 >= threshold => theme.warning,
+            Some(_) => theme.muted,
+            None => theme.warning,
+        };
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?;
+        // Nothing to count before the first wrong guess
+        if self.failed_attempts > 0 {
+            let text = i18n::tr("attempts.count", &[("count", &self.failed_attempts)]);
+            draw_context
+                .out
+                .queue(SetForegroundColor(color))?
+                .queue(Print(text))?
+                .queue(ResetColor)?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
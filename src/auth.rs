@@ -0,0 +1,10 @@
+//! Ways to unlock besides typing a password. Each backend waits for its
+//! device on a thread of its own, alongside the keyboard, and shows a hint
+//! below the prompt while it does.
+
+#[cfg(feature = "fido2")]
+pub(crate) mod fido2;
+#[cfg(all(target_os = "linux", feature = "fprintd"))]
+pub(crate) mod fprintd;
+#[cfg(feature = "presence")]
+pub(crate) mod presence;
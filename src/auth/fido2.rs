@@ -0,0 +1,231 @@
+//! Unlocking by touching a FIDO2 security key registered with
+//! `screenlock register-key`. The key signs a fresh challenge on every touch,
+//! which is checked against the public key saved at registration.
+
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::Context;
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use ctap_hid_fido2::{
+    fidokey::{GetAssertionArgsBuilder, MakeCredentialArgsBuilder},
+    public_key::{PublicKey, PublicKeyType},
+    verifier, Cfg, FidoKeyHidFactory,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::{
+    bus,
+    controller::{DrawContext, EntityId, Recipient, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout, session, theme,
+};
+
+/// The relying party the credentials are made for.
+const RP_ID: &str = "screenlock";
+
+/// How long to wait before looking for a key again when none is plugged in.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RegisteredKey {
+    credential_id: Vec<u8>,
+    /// DER encoded ES256 public key
+    public_key: Vec<u8>,
+}
+
+fn keys_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("screenlock").join("security_keys.json"))
+}
+
+fn load_keys() -> anyhow::Result<Vec<RegisteredKey>> {
+    let Some(path) = keys_path() else {
+        return Ok(Vec::new());
+    };
+    match fs::read(&path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Whether any key was registered, i.e. whether to wait for one at all.
+pub fn has_keys() -> bool {
+    match load_keys() {
+        Ok(keys) => !keys.is_empty(),
+        Err(error) => {
+            warn!(?error, "could not read the registered security keys");
+            false
+        }
+    }
+}
+
+/// `screenlock register-key`: make a credential on the key plugged in and
+/// save it, so touching that key unlocks from now on.
+pub fn register() -> anyhow::Result<()> {
+    let path = keys_path().context("no config directory to store the key in")?;
+    let device = FidoKeyHidFactory::create(&Cfg::init()).context("no security key found")?;
+    println!("Touch your security key…");
+    let challenge = verifier::create_challenge();
+    let args = MakeCredentialArgsBuilder::new(RP_ID, &challenge)
+        .without_pin_and_uv()
+        .build();
+    let attestation = device.make_credential_with_args(&args)?;
+    let verified = verifier::verify_attestation(RP_ID, &challenge, &attestation);
+    if !verified.is_success {
+        anyhow::bail!("the key's attestation didn't verify");
+    }
+    let mut keys = load_keys()?;
+    keys.push(RegisteredKey {
+        credential_id: verified.credential_id,
+        public_key: verified.credential_public_key.der,
+    });
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(&keys)?)?;
+    println!("Security key registered in {}.", path.display());
+    Ok(())
+}
+
+/// Wait for one touch of a registered key, true if it signed our challenge.
+fn wait_for_touch(keys: &[RegisteredKey]) -> anyhow::Result<bool> {
+    let device = FidoKeyHidFactory::create(&Cfg::init())?;
+    let challenge = verifier::create_challenge();
+    let mut args = GetAssertionArgsBuilder::new(RP_ID, &challenge).without_pin_and_uv();
+    for key in keys {
+        args = args.add_credential_id(&key.credential_id);
+    }
+    let assertions = device.get_assertion_with_args(&args.build())?;
+    Ok(assertions.iter().any(|assertion| {
+        keys.iter().any(|key| {
+            let public_key = PublicKey::with_der(&key.public_key, PublicKeyType::Ecdsa256);
+            verifier::verify_assertion(RP_ID, &public_key, &challenge, assertion)
+        })
+    }))
+}
+
+/// The hint below the prompt, and the unlock once a registered key was
+/// touched.
+pub struct SecurityKeyEntity {
+    id: String,
+    entity_id: EntityId,
+    row: u16,
+    touched: bool,
+    /// Tells the waiting thread to stop, so a touch after this lock ended
+    /// can't unlock the next one
+    stop: Arc<AtomicBool>,
+}
+
+impl SecurityKeyEntity {
+    /// Start waiting for a touch of any registered key. `row` is the line
+    /// among the unlock hints.
+    pub fn spawn(id: &str, row: u16) -> anyhow::Result<Self> {
+        let id = format!("SecurityKeyEntity-{id}");
+        let keys = load_keys()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let entity_id = EntityId::next();
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                match wait_for_touch(&keys) {
+                    Ok(true) if !stopped.load(Ordering::Relaxed) => {
+                        bus::publish_event(Recipient::Entity(entity_id), "touched", "true");
+                        return;
+                    }
+                    Ok(true) => return,
+                    Ok(false) => warn!("security key touched but its signature didn't verify"),
+                    Err(error) => {
+                        debug!(%error, "no security key touch");
+                        thread::sleep(RETRY_INTERVAL);
+                    }
+                }
+            }
+        });
+        bus::enable();
+        Ok(SecurityKeyEntity {
+            id,
+            entity_id,
+            row,
+            touched: false,
+            stop,
+        })
+    }
+}
+
+impl Drop for SecurityKeyEntity {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Named for SecurityKeyEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for SecurityKeyEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        match key {
+            "touched" => Some(if self.touched { "true" } else { "false" }),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != "touched" {
+            return false;
+        }
+        self.touched = value == "true";
+        true
+    }
+}
+
+impl FullEntity for SecurityKeyEntity {}
+
+impl Entity for SecurityKeyEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::auth_hint_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(theme::current().muted))?
+            .queue(Print(theme::glyphs(i18n::t("security_key.hint"))))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        if self.touched {
+            info!("unlocked with security key");
+            session::set_exit_reason("security_key");
+            return UpdateResult::kill();
+        }
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
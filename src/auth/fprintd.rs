@@ -0,0 +1,253 @@
+//! Unlocking with a fingerprint enrolled in fprintd, talked to over the
+//! system D-Bus. Linux only.
+
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use tracing::{debug, info, warn};
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::OwnedObjectPath,
+};
+
+use crate::{
+    bus,
+    controller::{DrawContext, EntityId, Recipient, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout, session, theme,
+};
+
+const SERVICE: &str = "net.reactivated.Fprint";
+
+/// How long to wait before trying again when the reader is gone or busy.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Waiting,
+    NoMatch,
+    Retry,
+    Matched,
+    Unavailable,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Waiting => "waiting",
+            Status::NoMatch => "no_match",
+            Status::Retry => "retry",
+            Status::Matched => "matched",
+            Status::Unavailable => "unavailable",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "waiting" => Status::Waiting,
+            "no_match" => Status::NoMatch,
+            "retry" => Status::Retry,
+            "matched" => Status::Matched,
+            "unavailable" => Status::Unavailable,
+            _ => return None,
+        })
+    }
+
+    /// The status for fprintd's `VerifyStatus` result, see its D-Bus API.
+    fn from_result(result: &str) -> Self {
+        match result {
+            "verify-match" => Status::Matched,
+            "verify-no-match" => Status::NoMatch,
+            "verify-disconnected" | "verify-unknown-error" => Status::Unavailable,
+            // Too short a swipe, an off-center finger and the like
+            _ => Status::Retry,
+        }
+    }
+}
+
+/// Verify fingers on the claimed `device` until one matches or `stop` is set.
+/// Ok(true) on a match.
+fn verify_claimed(
+    device: &Proxy,
+    stop: &AtomicBool,
+    publish: &dyn Fn(Status),
+) -> anyhow::Result<bool> {
+    let mut statuses = device.receive_signal("VerifyStatus")?;
+    loop {
+        device.call::<_, _, ()>("VerifyStart", &("any",))?;
+        publish(Status::Waiting);
+        loop {
+            let Some(message) = statuses.next() else {
+                anyhow::bail!("fprintd went away");
+            };
+            let (result, done): (String, bool) = message.body().deserialize()?;
+            debug!(%result, done, "fingerprint verify status");
+            if stop.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+            match Status::from_result(&result) {
+                Status::Matched => return Ok(true),
+                Status::Unavailable => anyhow::bail!("fingerprint reader failed: {result}"),
+                status => publish(status),
+            }
+            if done {
+                break;
+            }
+        }
+        // A finished verification has to be stopped before the next one
+        device.call::<_, _, ()>("VerifyStop", &())?;
+    }
+}
+
+/// Claim the default reader and verify with it, releasing it afterwards.
+fn verify(stop: &AtomicBool, publish: &dyn Fn(Status)) -> anyhow::Result<bool> {
+    let connection = Connection::system()?;
+    let manager = Proxy::new(
+        &connection,
+        SERVICE,
+        "/net/reactivated/Fprint/Manager",
+        "net.reactivated.Fprint.Manager",
+    )?;
+    let path: OwnedObjectPath = manager.call("GetDefaultDevice", &())?;
+    let device = Proxy::new(&connection, SERVICE, path, "net.reactivated.Fprint.Device")?;
+    // An empty user name claims the reader for whoever we run as
+    device.call::<_, _, ()>("Claim", &("",))?;
+    let result = verify_claimed(&device, stop, publish);
+    let _ = device.call::<_, _, ()>("VerifyStop", &());
+    let _ = device.call::<_, _, ()>("Release", &());
+    result
+}
+
+/// Shows what the fingerprint reader wants, e.g. "place finger on reader",
+/// and unlocks once an enrolled finger matched.
+pub struct FingerprintEntity {
+    id: String,
+    entity_id: EntityId,
+    row: u16,
+    status: Status,
+    /// Tells the verifying thread to stop, so a finger after this lock ended
+    /// can't unlock the next one
+    stop: Arc<AtomicBool>,
+}
+
+impl FingerprintEntity {
+    /// Start verifying on the default reader. `row` is the line among the
+    /// unlock hints.
+    pub fn spawn(id: &str, row: u16) -> Self {
+        let id = format!("FingerprintEntity-{id}");
+        let stop = Arc::new(AtomicBool::new(false));
+        let entity_id = EntityId::next();
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            let publish = |status: Status| {
+                if !stopped.load(Ordering::Relaxed) {
+                    bus::publish_event(Recipient::Entity(entity_id), "status", status.as_str());
+                }
+            };
+            while !stopped.load(Ordering::Relaxed) {
+                match verify(&stopped, &publish) {
+                    Ok(true) => {
+                        publish(Status::Matched);
+                        return;
+                    }
+                    Ok(false) => return,
+                    Err(error) => {
+                        warn!(%error, "fingerprint verification unavailable");
+                        publish(Status::Unavailable);
+                        thread::sleep(RETRY_INTERVAL);
+                    }
+                }
+            }
+        });
+        bus::enable();
+        FingerprintEntity {
+            id,
+            entity_id,
+            row,
+            status: Status::Waiting,
+            stop,
+        }
+    }
+}
+
+impl Drop for FingerprintEntity {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Named for FingerprintEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for FingerprintEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        match key {
+            "status" => Some(self.status.as_str()),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        let Some(status) = Status::parse(value).filter(|_| key == "status") else {
+            return false;
+        };
+        self.status = status;
+        true
+    }
+}
+
+impl FullEntity for FingerprintEntity {}
+
+impl Entity for FingerprintEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let theme = theme::current();
+        let (color, text) = match self.status {
+            Status::Waiting | Status::Matched => (theme.muted, "fingerprint.waiting"),
+            Status::NoMatch => (theme.warning, "fingerprint.no_match"),
+            Status::Retry => (theme.warning, "fingerprint.retry"),
+            Status::Unavailable => (theme.muted, "fingerprint.unavailable"),
+        };
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::auth_hint_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(color))?
+            .queue(Print(theme::glyphs(i18n::t(text))))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        if self.status == Status::Matched {
+            info!("unlocked with fingerprint");
+            session::set_exit_reason("fingerprint");
+            return UpdateResult::kill();
+        }
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -0,0 +1,250 @@
+//! Telling whether the owner is near the machine, from a phone paired over
+//! Bluetooth or a command of their choosing, e.g. a webcam face detector that
+//! exits 0 when it sees them. Depending on the policy the lock ends early
+//! once they are back, or refuses to unlock while they are away.
+
+use std::{
+    io::Write,
+    process::Stdio,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use tracing::{info, warn};
+
+use crate::{
+    controller::{DrawContext, EntityId, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    hooks, i18n, layout, session,
+    tasks::{self, TaskHandle},
+    theme,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Whether the last check found the owner nearby. Checks that fail count as
+/// present, so a missing tool can't keep the owner out.
+static PRESENT: AtomicBool = AtomicBool::new(true);
+
+/// What being near the machine or not does to the lock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PresencePolicy {
+    /// End the lock once the owner is back after being away
+    Unlock,
+    /// Refuse to unlock, even with the right password, while they are away
+    Guard,
+}
+
+/// How presence is checked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PresenceSource {
+    /// A paired phone that is connected, by its address
+    Bluetooth(String),
+    /// A shell command that exits 0 when the owner is there and 1 when not
+    Command(String),
+}
+
+impl FromStr for PresenceSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("bluetooth", address)) if !address.is_empty() => {
+                Ok(PresenceSource::Bluetooth(address.to_string()))
+            }
+            Some(("command", command)) if !command.is_empty() => {
+                Ok(PresenceSource::Command(command.to_string()))
+            }
+            _ => Err("expected bluetooth:<address> or command:<command>".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn bluetooth_connected(address: &str) -> Result<bool, String> {
+    let output = std::process::Command::new("bluetoothctl")
+        .args(["info", address])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|error| format!("could not run bluetoothctl: {error}"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).contains("Connected: yes"))
+}
+
+#[cfg(target_os = "macos")]
+fn bluetooth_connected(address: &str) -> Result<bool, String> {
+    let output = std::process::Command::new("blueutil")
+        .args(["--is-connected", address])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|error| format!("could not run blueutil: {error}"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn bluetooth_connected(_: &str) -> Result<bool, String> {
+    Err("Bluetooth presence is not supported on this platform".to_string())
+}
+
+impl PresenceSource {
+    fn check(&self) -> Result<bool, String> {
+        match self {
+            PresenceSource::Bluetooth(address) => bluetooth_connected(address),
+            PresenceSource::Command(command) => {
+                let status = hooks::shell_command(command)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .map_err(|error| format!("could not run '{command}': {error}"))?;
+                match status.code() {
+                    Some(0) => Ok(true),
+                    Some(1) => Ok(false),
+                    _ => Err(format!("'{command}' failed with {status}")),
+                }
+            }
+        }
+    }
+}
+
+/// Whether unlocking is fine as far as presence goes, for `Guard`.
+pub fn is_present() -> bool {
+    PRESENT.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Checking,
+    Present,
+    Away,
+    Unavailable,
+}
+
+/// Checks presence every few seconds and shows the result among the unlock
+/// hints. With `Unlock` it ends the lock once the owner was away and is back.
+pub struct PresenceEntity {
+    id: String,
+    entity_id: EntityId,
+    row: u16,
+    policy: PresencePolicy,
+    status: Status,
+    /// The owner was seen away during this lock
+    was_away: bool,
+    _task: TaskHandle,
+}
+
+impl PresenceEntity {
+    /// Start checking `source`. `row` is the line among the unlock hints.
+    pub fn spawn(id: &str, source: PresenceSource, policy: PresencePolicy, row: u16) -> Self {
+        let id = format!("PresenceEntity-{id}");
+        let entity_id = EntityId::next();
+        PRESENT.store(true, Ordering::Relaxed);
+        let task = tasks::every(entity_id, "present", CHECK_INTERVAL, move || {
+            source.check().map(|present| present.to_string())
+        });
+        PresenceEntity {
+            id,
+            entity_id,
+            row,
+            policy,
+            status: Status::Checking,
+            was_away: false,
+            _task: task,
+        }
+    }
+}
+
+impl Drop for PresenceEntity {
+    fn drop(&mut self) {
+        // Nothing is checking any more
+        PRESENT.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Named for PresenceEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for PresenceEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        match (key, self.status) {
+            ("present", Status::Present) => Some("true"),
+            ("present", Status::Away) => Some("false"),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        let status = match (key, value) {
+            ("present", "true") => Status::Present,
+            ("present", _) => Status::Away,
+            ("error", error) => {
+                if self.status != Status::Unavailable {
+                    warn!(%error, "presence check unavailable");
+                }
+                Status::Unavailable
+            }
+            _ => return false,
+        };
+        if status != self.status && status != Status::Unavailable {
+            info!(present = status == Status::Present, "presence changed");
+        }
+        self.was_away |= status == Status::Away;
+        self.status = status;
+        PRESENT.store(status != Status::Away, Ordering::Relaxed);
+        true
+    }
+}
+
+impl FullEntity for PresenceEntity {}
+
+impl Entity for PresenceEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let theme = theme::current();
+        let (color, text) = match (self.status, self.policy) {
+            (Status::Checking, _) => (theme.muted, ""),
+            (Status::Present, _) => (theme.muted, i18n::t("presence.present")),
+            (Status::Away, PresencePolicy::Unlock) => (theme.muted, i18n::t("presence.away")),
+            (Status::Away, PresencePolicy::Guard) => (theme.warning, i18n::t("presence.guard")),
+            (Status::Unavailable, _) => (theme.muted, i18n::t("presence.unavailable")),
+        };
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::auth_hint_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(color))?
+            .queue(Print(theme::glyphs(text)))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        if self.policy == PresencePolicy::Unlock && self.was_away && self.status == Status::Present
+        {
+            info!("unlocked on the owner's return");
+            session::set_exit_reason("presence");
+            return UpdateResult::kill();
+        }
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
 use crate::{
-    controller::{DrawContext, EventContext, UpdateResult},
-    entity::{Entity, FullEntity, HasProperties, Named},
+    commands::Command,
+    controller::{DrawContext, EntityId, EventContext, UpdateContext, UpdateResult},
+    count_down_entity::Trigger,
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
 };
 
 pub struct BaseEntity<T: Entity> {
@@ -11,10 +13,14 @@ pub struct BaseEntity<T: Entity> {
     delegate_entity: T,
 }
 
-impl<T: Entity> Named for BaseEntity<T> {
+impl<T: Entity + Named> Named for BaseEntity<T> {
     fn get_name(&self) -> &str {
         self.name.as_str()
     }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        self.delegate_entity.entity_id()
+    }
 }
 
 impl<T: Entity> HasProperties for BaseEntity<T> {
@@ -33,13 +39,21 @@ impl<T: Entity> Entity for BaseEntity<T> {
         self.delegate_entity.draw(draw_context)
     }
 
-    fn update(&mut self) -> UpdateResult {
-        self.delegate_entity.update()
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        self.delegate_entity.update(context)
     }
 
     fn handle_event(&mut self, event: EventContext) -> bool {
         self.delegate_entity.handle_event(event)
     }
+
+    fn handle_command(&mut self, command: Command, trigger: Trigger) -> bool {
+        self.delegate_entity.handle_command(command, trigger)
+    }
+
+    fn cadence(&self) -> Cadence {
+        self.delegate_entity.cadence()
+    }
 }
 
 impl<T: Entity + Named> BaseEntity<T> {
@@ -52,4 +66,4 @@ impl<T: Entity + Named> BaseEntity<T> {
     }
 }
 
-impl<T: Entity> FullEntity for BaseEntity<T> {}
+impl<T: Entity + Named> FullEntity for BaseEntity<T> {}
@@ -0,0 +1,66 @@
+use crossterm::{execute, style::Print};
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+};
+
+/// Rings the terminal bell each time its `ring` property is set. Draws
+/// nothing else, so it works in accessible mode too.
+pub struct BellEntity {
+    id: String,
+    /// Rung since the last update
+    pending: bool,
+    /// Rung in this update, so a redraw of everything doesn't ring again
+    ringing: bool,
+}
+
+impl BellEntity {
+    pub fn new(id: &str) -> Self {
+        BellEntity {
+            id: format!("BellEntity-{id}"),
+            pending: false,
+            ringing: false,
+        }
+    }
+}
+
+impl Named for BellEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for BellEntity {
+    fn get_property(&self, _: &str) -> Option<&str> {
+        None
+    }
+
+    fn set_property(&mut self, key: &str, _: &str) -> bool {
+        if key != "ring" {
+            return false;
+        }
+        self.pending = true;
+        true
+    }
+}
+
+impl FullEntity for BellEntity {}
+
+impl Entity for BellEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        if self.ringing {
+            execute!(draw_context.out, Print('\x07'))?;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        self.ringing = std::mem::take(&mut self.pending);
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
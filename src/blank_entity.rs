@@ -0,0 +1,215 @@
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use crossterm::event::Event;
+use tracing::{info, warn};
+
+use crate::{
+    controller::{DrawContext, EventContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+};
+
+/// What happens to the display once the lock sat untouched for a while.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BlankMode {
+    /// Turn it off (DPMS on X11, the backlight elsewhere on Linux)
+    Off,
+    /// Turn the backlight down to a tenth
+    Dim,
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{
+        fs,
+        path::PathBuf,
+        process::{Command, Stdio},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+    };
+
+    use super::BlankMode;
+
+    const BACKLIGHTS: &str = "/sys/class/backlight";
+
+    /// Brightness of each backlight before we changed it.
+    static SAVED: Mutex<Vec<(PathBuf, String)>> = Mutex::new(Vec::new());
+    /// Set while X11 has the display off on our behalf.
+    static DPMS_OFF: AtomicBool = AtomicBool::new(false);
+
+    fn xset(state: &str) -> bool {
+        std::env::var_os("DISPLAY").is_some()
+            && Command::new("xset")
+                .args(["dpms", "force", state])
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+    }
+
+    /// Set every backlight to `level(max)`, remembering what it was.
+    fn set_backlights(level: impl Fn(u64) -> u64) -> anyhow::Result<()> {
+        let mut saved = SAVED.lock().unwrap_or_else(|e| e.into_inner());
+        for entry in fs::read_dir(BACKLIGHTS)? {
+            let dir = entry?.path();
+            let max: u64 = fs::read_to_string(dir.join("max_brightness"))?
+                .trim()
+                .parse()?;
+            let path = dir.join("brightness");
+            let current = fs::read_to_string(&path)?;
+            // Writing it usually needs root or a udev rule
+            fs::write(&path, level(max).to_string())?;
+            saved.push((path, current));
+        }
+        if saved.is_empty() {
+            anyhow::bail!("no backlight in {BACKLIGHTS}");
+        }
+        Ok(())
+    }
+
+    pub fn blank(mode: BlankMode) -> anyhow::Result<()> {
+        match mode {
+            BlankMode::Off if xset("off") => {
+                DPMS_OFF.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            BlankMode::Off => set_backlights(|_| 0),
+            BlankMode::Dim => set_backlights(|max| (max / 10).max(1)),
+        }
+    }
+
+    pub fn wake() -> anyhow::Result<()> {
+        if DPMS_OFF.swap(false, Ordering::Relaxed) && !xset("on") {
+            anyhow::bail!("xset could not turn the display back on");
+        }
+        for (path, brightness) in SAVED.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+            fs::write(&path, brightness)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    use super::BlankMode;
+
+    fn run(program: &str, args: &[&str]) -> anyhow::Result<()> {
+        let status = Command::new(program).args(args).status()?;
+        anyhow::ensure!(status.success(), "{program} failed with {status}");
+        Ok(())
+    }
+
+    pub fn blank(mode: BlankMode) -> anyhow::Result<()> {
+        anyhow::ensure!(mode == BlankMode::Off, "dimming is not supported on macOS");
+        run("pmset", &["displaysleepnow"])
+    }
+
+    pub fn wake() -> anyhow::Result<()> {
+        // Declaring user activity wakes the display
+        run("caffeinate", &["-u", "-t", "1"])
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod platform {
+    use super::BlankMode;
+
+    pub fn blank(_: BlankMode) -> anyhow::Result<()> {
+        anyhow::bail!("blanking the display is not supported on this platform")
+    }
+
+    pub fn wake() -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Blanks or dims the display after the lock sat `after` without a key
+/// press, to save power during long locks. Any key wakes it again, and so
+/// does the end of the lock.
+pub struct BlankEntity {
+    id: String,
+    mode: BlankMode,
+    after: Duration,
+    /// When a key was last pressed, or the lock started
+    last_key: Option<Instant>,
+    blanked: bool,
+}
+
+impl BlankEntity {
+    pub fn new(id: &str, mode: BlankMode, after: Duration) -> Self {
+        BlankEntity {
+            id: format!("BlankEntity-{id}"),
+            mode,
+            after,
+            last_key: None,
+            blanked: false,
+        }
+    }
+
+    fn wake(&mut self) {
+        if !self.blanked {
+            return;
+        }
+        self.blanked = false;
+        match platform::wake() {
+            Ok(()) => info!("display woken"),
+            Err(error) => warn!(%error, "could not wake the display"),
+        }
+    }
+}
+
+impl Drop for BlankEntity {
+    fn drop(&mut self) {
+        self.wake();
+    }
+}
+
+impl Named for BlankEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for BlankEntity {
+    fn draw(&self, _: &mut DrawContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        if self.blanked {
+            return UpdateResult::nop();
+        }
+        let last_key = *self.last_key.get_or_insert(context.now);
+        if context.now.saturating_duration_since(last_key) >= self.after {
+            // Even if that fails, so it isn't retried on every update
+            self.blanked = true;
+            match platform::blank(self.mode) {
+                Ok(()) => info!(mode = ?self.mode, "display blanked"),
+                Err(error) => warn!(%error, mode = ?self.mode, "could not blank the display"),
+            }
+        }
+        UpdateResult::nop()
+    }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        if matches!(event.event, Event::Key(_)) {
+            self.last_key = Some(Instant::now());
+            self.wake();
+        }
+        // The key still goes to whoever wants it
+        false
+    }
+
+    fn cadence(&self) -> Cadence {
+        if self.blanked {
+            Cadence::OnChange
+        } else {
+            let idle = self.last_key.map_or(Duration::ZERO, |at| at.elapsed());
+            Cadence::Every(self.after.saturating_sub(idle))
+        }
+    }
+}
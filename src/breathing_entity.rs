@@ -0,0 +1,155 @@
+use std::{io::Write, str::FromStr, time::Duration};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    i18n, layout, text, theme,
+};
+
+const MAX_BAR_WIDTH: u16 = 40;
+
+/// Seconds to breathe in, hold, breathe out and hold again, parsed from
+/// `in:hold:out[:hold]`, e.g. `4:7:8` or `4:4:4:4` for box breathing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BreathingPattern {
+    inhale: Duration,
+    hold: Duration,
+    exhale: Duration,
+    rest: Duration,
+}
+
+impl FromStr for BreathingPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let secs = s
+            .split(':')
+            .map(|part| {
+                part.trim()
+                    .parse::<u64>()
+                    .map(Duration::from_secs)
+                    .map_err(|_| format!("Invalid number of seconds '{part}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (inhale, hold, exhale, rest) = match secs[..] {
+            [inhale, hold, exhale] => (inhale, hold, exhale, Duration::ZERO),
+            [inhale, hold, exhale, rest] => (inhale, hold, exhale, rest),
+            _ => return Err(format!("Expected in:hold:out[:hold], got '{s}'")),
+        };
+        if inhale.is_zero() || exhale.is_zero() {
+            return Err("Breathing in and out must take at least a second".to_string());
+        }
+        Ok(BreathingPattern {
+            inhale,
+            hold,
+            exhale,
+            rest,
+        })
+    }
+}
+
+impl BreathingPattern {
+    fn cycle(&self) -> Duration {
+        self.inhale + self.hold + self.exhale + self.rest
+    }
+
+    /// The instruction and how full the bar is (0.0 to 1.0) at `elapsed`.
+    fn phase(&self, elapsed: Duration) -> (&'static str, f64) {
+        let mut t = Duration::from_nanos((elapsed.as_nanos() % self.cycle().as_nanos()) as u64);
+        if t < self.inhale {
+            return (
+                i18n::t("breathing.in"),
+                t.as_secs_f64() / self.inhale.as_secs_f64(),
+            );
+        }
+        t -= self.inhale;
+        if t < self.hold {
+            return (i18n::t("breathing.hold"), 1.0);
+        }
+        t -= self.hold;
+        if t < self.exhale {
+            return (
+                i18n::t("breathing.out"),
+                1.0 - t.as_secs_f64() / self.exhale.as_secs_f64(),
+            );
+        }
+        (i18n::t("breathing.hold"), 0.0)
+    }
+}
+
+/// Guides a breathing exercise below the prompt with a bar that grows while
+/// breathing in and shrinks while breathing out.
+pub struct BreathingEntity {
+    id: String,
+    pattern: BreathingPattern,
+    /// Time spent breathing so far
+    elapsed: Duration,
+    text: &'static str,
+    fill: f64,
+}
+
+impl BreathingEntity {
+    pub fn new(id: &str, pattern: BreathingPattern) -> Self {
+        BreathingEntity {
+            id: format!("BreathingEntity-{id}"),
+            pattern,
+            elapsed: Duration::ZERO,
+            text: "",
+            fill: 0.0,
+        }
+    }
+}
+
+impl Named for BreathingEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for BreathingEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, _) = layout::terminal_size();
+        let bar_width = MAX_BAR_WIDTH.min(width.saturating_sub(2));
+        let filled = (self.fill * bar_width as f64).round() as usize;
+        let top = layout::hint_y() + 2;
+        let theme = theme::current();
+        let bar = format!(
+            "{}{}",
+            theme.bar_full.repeat(filled),
+            theme.bar_empty.repeat(bar_width as usize - filled)
+        );
+        let text_x = (width as usize).saturating_sub(text::width(self.text)) as u16 / 2;
+        let bar_x = (width.saturating_sub(bar_width)) / 2;
+        draw_context
+            .out
+            .queue(MoveTo(0, top))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(MoveTo(text_x, top))?
+            .queue(Print(self.text))?
+            .queue(MoveTo(0, top + 1))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(MoveTo(bar_x, top + 1))?
+            .queue(SetForegroundColor(theme::current().accent))?
+            .queue(Print(bar))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        self.elapsed += context.dt;
+        (self.text, self.fill) = self.pattern.phase(self.elapsed);
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(Duration::from_millis(100))
+    }
+}
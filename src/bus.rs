@@ -0,0 +1,80 @@
+//! The controller's inbox. The input grab, the IPC server, MQTT, the
+//! scheduler, background tasks and the entities all publish here, from any
+//! thread, and the controller handles whatever is queued once per pass of its
+//! loop.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, Sender},
+    Mutex, OnceLock,
+};
+
+use crate::{
+    commands::Command,
+    controller::{ControlEvent, Recipient},
+    count_down_entity::Trigger,
+};
+
+pub enum Message {
+    /// Set a property on an entity, or run the controller's listeners
+    Event(ControlEvent),
+    /// A command from outside the UI, e.g. `unlock` over the control socket
+    Command(Command, Trigger),
+    /// End the lock right away, e.g. on SIGTERM
+    Quit,
+    /// Draw everything anew, e.g. after the theme changed
+    Redraw,
+}
+
+struct Bus {
+    sender: Sender<Message>,
+    receiver: Mutex<Receiver<Message>>,
+}
+
+static BUS: OnceLock<Bus> = OnceLock::new();
+
+/// Set once something publishes from another thread, so the controller knows
+/// to check the bus regularly.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+fn bus() -> &'static Bus {
+    BUS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        Bus {
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    })
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn publish(message: Message) {
+    // The receiver lives as long as the process, sending can't fail
+    let _ = bus().sender.send(message);
+}
+
+/// Set `property_key` to `property_value` on `recipient`.
+pub fn publish_event(recipient: Recipient, property_key: &str, property_value: &str) {
+    publish(Message::Event(ControlEvent {
+        recipient,
+        property_key: property_key.to_string(),
+        property_value: property_value.to_string(),
+    }));
+}
+
+/// Messages published since the last call, oldest first.
+pub fn drain() -> Vec<Message> {
+    bus()
+        .receiver
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .try_iter()
+        .collect()
+}
@@ -0,0 +1,208 @@
+use std::{io::Write, path::PathBuf, time::Duration};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use tracing::warn;
+
+use crate::{
+    controller::{DrawContext, EntityId},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    hooks, i18n, layout,
+    tasks::{self, TaskHandle},
+    theme,
+};
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where upcoming events come from.
+pub enum CalendarSource {
+    /// A local iCalendar file, e.g. an export or a vdirsyncer calendar
+    Ics(PathBuf),
+    /// A command printing upcoming events one per line, soonest first, e.g.
+    /// `khal list now 1d` or `gcalcli agenda`
+    Command(String),
+}
+
+impl CalendarSource {
+    fn next_event(&self, now: DateTime<Local>) -> anyhow::Result<Option<String>> {
+        match self {
+            CalendarSource::Ics(path) => {
+                let ics = std::fs::read_to_string(path)?;
+                Ok(next_ics_event(&ics, now)
+                    .map(|(start, all_day, title)| describe(&title, start, all_day, now)))
+            }
+            CalendarSource::Command(cmd) => {
+                let output = hooks::output_with_timeout(cmd, COMMAND_TIMEOUT)?;
+                Ok(output
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(|line| i18n::tr("calendar.next", &[("title", &line)])))
+            }
+        }
+    }
+}
+
+fn describe(title: &str, start: DateTime<Local>, all_day: bool, now: DateTime<Local>) -> String {
+    let time = start.format("%H:%M").to_string();
+    let date = start.format("%a %d %b").to_string();
+    match (all_day, start.date_naive() == now.date_naive()) {
+        (true, true) => i18n::tr("calendar.next", &[("title", &title)]),
+        (true, false) => i18n::tr("calendar.next_on", &[("title", &title), ("date", &date)]),
+        (false, true) => i18n::tr("calendar.next_at", &[("title", &title), ("time", &time)]),
+        (false, false) => i18n::tr(
+            "calendar.next_on_at",
+            &[("title", &title), ("date", &date), ("time", &time)],
+        ),
+    }
+}
+
+/// Parse a `DTSTART` value: UTC (`...Z`), floating or TZID local time (taken
+/// as local, we don't ship time zone data) or a date for all-day events.
+fn parse_start(value: &str) -> Option<(DateTime<Local>, bool)> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&naive).with_timezone(&Local), false));
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some((Local.from_local_datetime(&naive).earliest()?, false));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    Some((
+        Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?,
+        true,
+    ))
+}
+
+/// The earliest event in `ics` that has not started yet (all-day events of
+/// today count as upcoming). Recurrence rules are not expanded.
+fn next_ics_event(ics: &str, now: DateTime<Local>) -> Option<(DateTime<Local>, bool, String)> {
+    // Long lines are folded onto continuation lines starting with a space
+    let unfolded = ics
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut next: Option<(DateTime<Local>, bool, String)> = None;
+    let mut start = None;
+    let mut title = None;
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters such as ;TZID=... or ;VALUE=DATE follow the name
+        let name = name.split(';').next().unwrap_or(name);
+        match name {
+            "BEGIN" if value == "VEVENT" => {
+                start = None;
+                title = None;
+            }
+            "DTSTART" => start = parse_start(value),
+            "SUMMARY" => title = Some(value.replace("\\,", ",").replace("\\;", ";")),
+            "END" if value == "VEVENT" => {
+                let (Some((at, all_day)), Some(title)) = (start.take(), title.take()) else {
+                    continue;
+                };
+                let upcoming = if all_day {
+                    at.date_naive() >= now.date_naive()
+                } else {
+                    at >= now
+                };
+                if upcoming && next.as_ref().is_none_or(|(soonest, ..)| at < *soonest) {
+                    next = Some((at, all_day, title));
+                }
+            }
+            _ => {}
+        }
+    }
+    next
+}
+
+/// Shows the next calendar event on a footer row, e.g. "Next: Standup at
+/// 11:00", reloaded every `refresh` in the background.
+pub struct CalendarEntity {
+    id: String,
+    entity_id: EntityId,
+    row: u16,
+    text: String,
+    _task: TaskHandle,
+}
+
+impl CalendarEntity {
+    /// Start reading `source`. `row` is the line within the footer.
+    pub fn spawn(id: &str, source: CalendarSource, refresh: Duration, row: u16) -> Self {
+        let id = format!("CalendarEntity-{id}");
+        let entity_id = EntityId::next();
+        let task = tasks::every(entity_id, "event", refresh, move || {
+            Ok(match source.next_event(Local::now()) {
+                Ok(event) => event.unwrap_or_default(),
+                Err(error) => {
+                    warn!(%error, "could not read calendar");
+                    String::new()
+                }
+            })
+        });
+        CalendarEntity {
+            id,
+            entity_id,
+            row,
+            text: String::new(),
+            _task: task,
+        }
+    }
+}
+
+impl Named for CalendarEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for CalendarEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        (key == "event").then_some(self.text.as_str())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != "event" {
+            return false;
+        }
+        self.text = value.to_string();
+        true
+    }
+}
+
+impl FullEntity for CalendarEntity {}
+
+impl Entity for CalendarEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, _) = layout::terminal_size();
+        let text: String = theme::glyphs(&self.text)
+            .chars()
+            .take(width as usize)
+            .collect();
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(theme::current().accent))?
+            .queue(Print(text))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -0,0 +1,511 @@
+//! The texts of the lock screen in each language. Each catalog maps a
+//! message key to its text; keys missing from a catalog fall back to
+//! English.
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+const EN: &[(&str, &str)] = &[
+    (
+        "title.unattended",
+        "💻 This laptop has been intentionally left unattended.",
+    ),
+    (
+        "title.tampering",
+        "🙅 Tampering with it is not appreciated.",
+    ),
+    (
+        "title.active",
+        "🔒 Screen lock is active: Unlocks on timeout or correct password.",
+    ),
+    (
+        "title.love",
+        "💖 Send love to: https://github.com/fa993/screenlock",
+    ),
+    ("title.reason", "🔒 Locked: {reason}"),
+    ("title.locked", "🔒 Locked"),
+    ("prompt.password", "Enter password: "),
+    ("prompt.partner", "Enter your partner's unlock code: "),
+    ("prompt.second_factor", "Enter authenticator code: "),
+    ("feedback.wrong_password", "❌ Wrong password, try again."),
+    (
+        "feedback.wrong_code",
+        "❌ Wrong authenticator code, start over with the password.",
+    ),
+    (
+        "feedback.wrong_answer",
+        "❌ Wrong answer, try this one instead.",
+    ),
+    (
+        "feedback.expired_code",
+        "❌ That authenticator code expired, start over with the password.",
+    ),
+    ("feedback.too_soon", "⏳ Too many attempts, wait {secs}s."),
+    (
+        "feedback.refused",
+        "You seem to be away, unlocking is off until you're back.",
+    ),
+    ("challenge.problem", "Problem {n}/{count}: "),
+    ("attempts.count", "Attempts: {count}"),
+    ("countdown.paused", " (paused)"),
+    ("countdown.left", "{time} left"),
+    ("countdown.expired", "🔒 Time's up, still locked"),
+    ("greeting.morning", "Good morning, {name}"),
+    ("greeting.afternoon", "Good afternoon, {name}"),
+    ("greeting.evening", "Good evening, {name}"),
+    ("greeting.night", "Good night, {name}"),
+    ("countdown.hidden", "🔒 Locked"),
+    (
+        "countdown.grace",
+        "Break over — press any key to unlock ({secs}s)",
+    ),
+    ("milestone.percent", "⏳ {percent}% of the lock left"),
+    ("milestone.left", "⏳ {left} left"),
+    ("breathing.in", "Breathe in…"),
+    ("breathing.hold", "Hold…"),
+    ("breathing.out", "Breathe out…"),
+    ("accessible.minute_left", "1 minute left"),
+    ("accessible.minutes_left", "{minutes} minutes left"),
+    ("accessible.error", "Error: {message}"),
+    ("keyboard.layout", "⌨ Keyboard layout: {layout}"),
+    ("network.online", "● Online"),
+    ("network.offline", "⚠ Offline — remote unlock unavailable"),
+    ("calendar.next", "📅 Next: {title}"),
+    ("calendar.next_at", "📅 Next: {title} at {time}"),
+    ("calendar.next_on", "📅 Next: {title} on {date}"),
+    (
+        "calendar.next_on_at",
+        "📅 Next: {title} on {date} at {time}",
+    ),
+    (
+        "grab.failed",
+        "⚠ Could not grab the keyboard, shortcuts may get through",
+    ),
+    (
+        "grab.lost",
+        "⚠ Keyboard and mouse are no longer grabbed, only this terminal is locked",
+    ),
+    ("input.blocked", "🔒 Input blocked, this machine is locked"),
+    (
+        "input.blocked_count",
+        "⌨ {count} key presses and clicks blocked",
+    ),
+    ("security_key.hint", "or touch your security key"),
+    ("fingerprint.waiting", "place finger on the reader"),
+    ("fingerprint.no_match", "no match, try another finger"),
+    ("fingerprint.retry", "finger not read, try again"),
+    ("fingerprint.unavailable", "fingerprint reader unavailable"),
+    ("presence.present", "you're here"),
+    ("presence.away", "away, unlocks when you're back"),
+    ("presence.guard", "away, unlocking is off"),
+    ("presence.unavailable", "presence check unavailable"),
+    ("qr.remote_unlock", "Scan to unlock"),
+];
+
+const DE: &[(&str, &str)] = &[
+    (
+        "title.unattended",
+        "💻 Dieser Laptop wurde absichtlich unbeaufsichtigt gelassen.",
+    ),
+    ("title.tampering", "🙅 Bitte nicht daran herumspielen."),
+    (
+        "title.active",
+        "🔒 Bildschirmsperre aktiv: entsperrt nach Ablauf der Zeit oder mit dem Passwort.",
+    ),
+    (
+        "title.love",
+        "💖 Unterstütze das Projekt: https://github.com/fa993/screenlock",
+    ),
+    ("title.reason", "🔒 Gesperrt: {reason}"),
+    ("title.locked", "🔒 Gesperrt"),
+    ("prompt.password", "Passwort eingeben: "),
+    (
+        "prompt.partner",
+        "Freischaltcode deines Partners eingeben: ",
+    ),
+    ("prompt.second_factor", "Authenticator-Code eingeben: "),
+    (
+        "feedback.wrong_password",
+        "❌ Falsches Passwort, bitte erneut versuchen.",
+    ),
+    (
+        "feedback.wrong_code",
+        "❌ Falscher Authenticator-Code, beginne erneut mit dem Passwort.",
+    ),
+    (
+        "feedback.wrong_answer",
+        "❌ Falsche Antwort, versuch es mit dieser.",
+    ),
+    (
+        "feedback.expired_code",
+        "❌ Der Code ist abgelaufen, fang mit dem Passwort neu an.",
+    ),
+    ("feedback.too_soon", "⏳ Zu viele Versuche, warte {secs} s."),
+    (
+        "feedback.refused",
+        "Du scheinst weg zu sein, Entsperren geht erst, wenn du zurück bist.",
+    ),
+    ("challenge.problem", "Aufgabe {n}/{count}: "),
+    ("attempts.count", "Versuche: {count}"),
+    ("countdown.paused", " (pausiert)"),
+    ("countdown.left", "noch {time}"),
+    ("countdown.expired", "🔒 Zeit um, bleibt gesperrt"),
+    ("greeting.morning", "Guten Morgen, {name}"),
+    ("greeting.afternoon", "Guten Tag, {name}"),
+    ("greeting.evening", "Guten Abend, {name}"),
+    ("greeting.night", "Gute Nacht, {name}"),
+    ("countdown.hidden", "🔒 Gesperrt"),
+    (
+        "countdown.grace",
+        "Pause vorbei — beliebige Taste zum Entsperren ({secs}s)",
+    ),
+    ("milestone.percent", "⏳ Noch {percent}% der Sperre"),
+    ("milestone.left", "⏳ Noch {left}"),
+    ("breathing.in", "Einatmen…"),
+    ("breathing.hold", "Halten…"),
+    ("breathing.out", "Ausatmen…"),
+    ("accessible.minute_left", "Noch 1 Minute"),
+    ("accessible.minutes_left", "Noch {minutes} Minuten"),
+    ("accessible.error", "Fehler: {message}"),
+    ("keyboard.layout", "⌨ Tastaturlayout: {layout}"),
+    ("network.online", "● Online"),
+    (
+        "network.offline",
+        "⚠ Offline — Entsperren aus der Ferne nicht möglich",
+    ),
+    ("calendar.next", "📅 Als Nächstes: {title}"),
+    ("calendar.next_at", "📅 Als Nächstes: {title} um {time}"),
+    ("calendar.next_on", "📅 Als Nächstes: {title} am {date}"),
+    (
+        "calendar.next_on_at",
+        "📅 Als Nächstes: {title} am {date} um {time}",
+    ),
+    (
+        "grab.failed",
+        "⚠ Tastatur konnte nicht gesperrt werden, Tastenkürzel kommen eventuell durch",
+    ),
+    (
+        "grab.lost",
+        "⚠ Tastatur und Maus sind nicht mehr gesperrt, nur dieses Terminal ist gesperrt",
+    ),
+    (
+        "input.blocked",
+        "🔒 Eingabe blockiert, dieser Rechner ist gesperrt",
+    ),
+    (
+        "input.blocked_count",
+        "⌨ {count} Tastendrücke und Klicks blockiert",
+    ),
+    (
+        "security_key.hint",
+        "oder berühre deinen Sicherheitsschlüssel",
+    ),
+    ("fingerprint.waiting", "Finger auf den Leser legen"),
+    (
+        "fingerprint.no_match",
+        "keine Übereinstimmung, anderen Finger versuchen",
+    ),
+    (
+        "fingerprint.retry",
+        "Finger nicht erkannt, erneut versuchen",
+    ),
+    (
+        "fingerprint.unavailable",
+        "Fingerabdruckleser nicht verfügbar",
+    ),
+    ("presence.present", "du bist da"),
+    ("presence.away", "weg, entsperrt bei Rückkehr"),
+    ("presence.guard", "weg, Entsperren gesperrt"),
+    (
+        "presence.unavailable",
+        "Anwesenheitsprüfung nicht verfügbar",
+    ),
+    ("qr.remote_unlock", "Zum Entsperren scannen"),
+];
+
+const FR: &[(&str, &str)] = &[
+    (
+        "title.unattended",
+        "💻 Cet ordinateur a été laissé sans surveillance volontairement.",
+    ),
+    ("title.tampering", "🙅 Merci de ne pas y toucher."),
+    (
+        "title.active",
+        "🔒 Verrouillage actif : se déverrouille à la fin du minuteur ou avec le bon mot de passe.",
+    ),
+    (
+        "title.love",
+        "💖 Soutenez le projet : https://github.com/fa993/screenlock",
+    ),
+    ("title.reason", "🔒 Verrouillé : {reason}"),
+    ("title.locked", "🔒 Verrouillé"),
+    ("prompt.password", "Mot de passe : "),
+    (
+        "prompt.partner",
+        "Code de déverrouillage de votre partenaire : ",
+    ),
+    ("prompt.second_factor", "Code d'authentification : "),
+    (
+        "feedback.wrong_password",
+        "❌ Mot de passe incorrect, réessayez.",
+    ),
+    (
+        "feedback.wrong_code",
+        "❌ Code d'authentification incorrect, recommencez avec le mot de passe.",
+    ),
+    (
+        "feedback.wrong_answer",
+        "❌ Mauvaise réponse, essayez celle-ci.",
+    ),
+    (
+        "feedback.expired_code",
+        "❌ Ce code a expiré, recommencez avec le mot de passe.",
+    ),
+    (
+        "feedback.too_soon",
+        "⏳ Trop de tentatives, attendez {secs} s.",
+    ),
+    (
+        "feedback.refused",
+        "Vous semblez absent, déverrouillage impossible jusqu'à votre retour.",
+    ),
+    ("challenge.problem", "Problème {n}/{count} : "),
+    ("attempts.count", "Tentatives : {count}"),
+    ("countdown.paused", " (en pause)"),
+    ("countdown.left", "encore {time}"),
+    ("countdown.expired", "🔒 Temps écoulé, toujours verrouillé"),
+    ("greeting.morning", "Bonjour, {name}"),
+    ("greeting.afternoon", "Bon après-midi, {name}"),
+    ("greeting.evening", "Bonsoir, {name}"),
+    ("greeting.night", "Bonne nuit, {name}"),
+    ("countdown.hidden", "🔒 Verrouillé"),
+    (
+        "countdown.grace",
+        "Pause terminée — appuyez sur une touche pour déverrouiller ({secs}s)",
+    ),
+    ("milestone.percent", "⏳ Encore {percent}% du verrouillage"),
+    ("milestone.left", "⏳ Encore {left}"),
+    ("breathing.in", "Inspirez…"),
+    ("breathing.hold", "Retenez…"),
+    ("breathing.out", "Expirez…"),
+    ("accessible.minute_left", "Encore 1 minute"),
+    ("accessible.minutes_left", "Encore {minutes} minutes"),
+    ("accessible.error", "Erreur : {message}"),
+    ("keyboard.layout", "⌨ Disposition du clavier : {layout}"),
+    ("network.online", "● En ligne"),
+    (
+        "network.offline",
+        "⚠ Hors ligne — déverrouillage à distance indisponible",
+    ),
+    ("calendar.next", "📅 À suivre : {title}"),
+    ("calendar.next_at", "📅 À suivre : {title} à {time}"),
+    ("calendar.next_on", "📅 À suivre : {title} le {date}"),
+    (
+        "calendar.next_on_at",
+        "📅 À suivre : {title} le {date} à {time}",
+    ),
+    (
+        "grab.failed",
+        "⚠ Impossible de bloquer le clavier, des raccourcis peuvent passer",
+    ),
+    (
+        "grab.lost",
+        "⚠ Le clavier et la souris ne sont plus bloqués, seul ce terminal est verrouillé",
+    ),
+    (
+        "input.blocked",
+        "🔒 Saisie bloquée, cette machine est verrouillée",
+    ),
+    ("input.blocked_count", "⌨ {count} frappes et clics bloqués"),
+    ("security_key.hint", "ou touchez votre clé de sécurité"),
+    ("fingerprint.waiting", "posez le doigt sur le lecteur"),
+    (
+        "fingerprint.no_match",
+        "aucune correspondance, essayez un autre doigt",
+    ),
+    ("fingerprint.retry", "doigt mal lu, réessayez"),
+    (
+        "fingerprint.unavailable",
+        "lecteur d'empreintes indisponible",
+    ),
+    ("presence.present", "vous êtes là"),
+    ("presence.away", "absent, déverrouillage à votre retour"),
+    ("presence.guard", "absent, déverrouillage bloqué"),
+    ("presence.unavailable", "détection de présence indisponible"),
+    ("qr.remote_unlock", "Scannez pour déverrouiller"),
+];
+
+const ES: &[(&str, &str)] = &[
+    (
+        "title.unattended",
+        "💻 Este portátil se ha dejado desatendido a propósito.",
+    ),
+    ("title.tampering", "🙅 Por favor, no lo toques."),
+    (
+        "title.active",
+        "🔒 Bloqueo activo: se desbloquea al acabar el tiempo o con la contraseña correcta.",
+    ),
+    (
+        "title.love",
+        "💖 Apoya el proyecto: https://github.com/fa993/screenlock",
+    ),
+    ("title.reason", "🔒 Bloqueado: {reason}"),
+    ("title.locked", "🔒 Bloqueado"),
+    ("prompt.password", "Introduce la contraseña: "),
+    (
+        "prompt.partner",
+        "Introduce el código de desbloqueo de tu compañero: ",
+    ),
+    (
+        "prompt.second_factor",
+        "Introduce el código del autenticador: ",
+    ),
+    (
+        "feedback.wrong_password",
+        "❌ Contraseña incorrecta, inténtalo de nuevo.",
+    ),
+    (
+        "feedback.wrong_code",
+        "❌ Código del autenticador incorrecto, empieza de nuevo con la contraseña.",
+    ),
+    (
+        "feedback.wrong_answer",
+        "❌ Respuesta incorrecta, prueba con esta.",
+    ),
+    (
+        "feedback.expired_code",
+        "❌ Ese código ha caducado, empieza de nuevo con la contraseña.",
+    ),
+    (
+        "feedback.too_soon",
+        "⏳ Demasiados intentos, espera {secs} s.",
+    ),
+    (
+        "feedback.refused",
+        "Parece que no estás, no se puede desbloquear hasta que vuelvas.",
+    ),
+    ("challenge.problem", "Problema {n}/{count}: "),
+    ("attempts.count", "Intentos: {count}"),
+    ("countdown.paused", " (en pausa)"),
+    ("countdown.left", "quedan {time}"),
+    (
+        "countdown.expired",
+        "🔒 Se acabó el tiempo, sigue bloqueado",
+    ),
+    ("greeting.morning", "Buenos días, {name}"),
+    ("greeting.afternoon", "Buenas tardes, {name}"),
+    ("greeting.evening", "Buenas tardes, {name}"),
+    ("greeting.night", "Buenas noches, {name}"),
+    ("countdown.hidden", "🔒 Bloqueado"),
+    (
+        "countdown.grace",
+        "Descanso terminado — pulsa cualquier tecla para desbloquear ({secs}s)",
+    ),
+    ("milestone.percent", "⏳ Queda un {percent}% del bloqueo"),
+    ("milestone.left", "⏳ Quedan {left}"),
+    ("breathing.in", "Inspira…"),
+    ("breathing.hold", "Mantén…"),
+    ("breathing.out", "Espira…"),
+    ("accessible.minute_left", "Queda 1 minuto"),
+    ("accessible.minutes_left", "Quedan {minutes} minutos"),
+    ("accessible.error", "Error: {message}"),
+    ("keyboard.layout", "⌨ Distribución del teclado: {layout}"),
+    ("network.online", "● En línea"),
+    (
+        "network.offline",
+        "⚠ Sin conexión — desbloqueo remoto no disponible",
+    ),
+    ("calendar.next", "📅 Próximo: {title}"),
+    ("calendar.next_at", "📅 Próximo: {title} a las {time}"),
+    ("calendar.next_on", "📅 Próximo: {title} el {date}"),
+    (
+        "calendar.next_on_at",
+        "📅 Próximo: {title} el {date} a las {time}",
+    ),
+    (
+        "grab.failed",
+        "⚠ No se pudo bloquear el teclado, algunos atajos pueden pasar",
+    ),
+    (
+        "grab.lost",
+        "⚠ El teclado y el ratón ya no están bloqueados, solo este terminal está bloqueado",
+    ),
+    (
+        "input.blocked",
+        "🔒 Entrada bloqueada, este equipo está bloqueado",
+    ),
+    (
+        "input.blocked_count",
+        "⌨ {count} pulsaciones y clics bloqueados",
+    ),
+    ("security_key.hint", "o toca tu llave de seguridad"),
+    ("fingerprint.waiting", "coloca el dedo en el lector"),
+    ("fingerprint.no_match", "no coincide, prueba otro dedo"),
+    (
+        "fingerprint.retry",
+        "no se leyó el dedo, inténtalo de nuevo",
+    ),
+    ("fingerprint.unavailable", "lector de huellas no disponible"),
+    ("presence.present", "estás aquí"),
+    ("presence.away", "ausente, se desbloquea al volver"),
+    ("presence.guard", "ausente, desbloqueo bloqueado"),
+    (
+        "presence.unavailable",
+        "detección de presencia no disponible",
+    ),
+    ("qr.remote_unlock", "Escanea para desbloquear"),
+];
+
+impl Lang {
+    /// Pick the language from LC_ALL, LC_MESSAGES or LANG (e.g. `de_DE.UTF-8`),
+    /// English if none of them names one we have.
+    pub fn detect() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|locale| !locale.is_empty())
+            .and_then(|locale| Lang::from_locale(&locale))
+            .unwrap_or(Lang::En)
+    }
+
+    fn from_locale(locale: &str) -> Option<Lang> {
+        match locale.get(..2)? {
+            "en" => Some(Lang::En),
+            "de" => Some(Lang::De),
+            "fr" => Some(Lang::Fr),
+            "es" => Some(Lang::Es),
+            _ => None,
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::En => EN,
+            Lang::De => DE,
+            Lang::Fr => FR,
+            Lang::Es => ES,
+        }
+    }
+
+    fn lookup(self, key: &str) -> Option<&'static str> {
+        self.catalog()
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, text)| *text)
+    }
+
+    /// The text for `key`, in English if this catalog misses it and the key
+    /// itself if all do.
+    pub fn text(self, key: &'static str) -> &'static str {
+        self.lookup(key)
+            .or_else(|| Lang::En.lookup(key))
+            .unwrap_or(key)
+    }
+}
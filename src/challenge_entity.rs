@@ -0,0 +1,278 @@
+use std::{fmt, str::FromStr};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+use rand::Rng;
+use tracing::{info, warn};
+
+use crate::{
+    commands::Command,
+    controller::{
+        ControlEvent, DrawContext, EntityId, EventContext, Recipient, UpdateContext, UpdateResult,
+    },
+    count_down_entity::Trigger,
+    entity::{Cadence, Entity, Named},
+    i18n, layout,
+    password_prompt_entity::{is_shortcut, main_key},
+    session, text,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// How many problems have to be solved to unlock early, parsed from
+/// `math[:count[:easy|medium|hard]]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    pub count: u32,
+    pub difficulty: Difficulty,
+}
+
+impl FromStr for Challenge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        if parts.next() != Some("math") {
+            return Err(format!(
+                "Unknown challenge '{s}', expected math[:count[:difficulty]]"
+            ));
+        }
+        let count = match parts.next() {
+            Some(count) => count
+                .parse::<u32>()
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or_else(|| format!("Invalid problem count '{count}'"))?,
+            None => 3,
+        };
+        let difficulty = match parts.next() {
+            Some("easy") => Difficulty::Easy,
+            Some("medium") | None => Difficulty::Medium,
+            Some("hard") => Difficulty::Hard,
+            Some(other) => {
+                return Err(format!(
+                    "Unknown difficulty '{other}', expected easy, medium or hard"
+                ))
+            }
+        };
+        if parts.next().is_some() {
+            return Err(format!("Trailing input in challenge '{s}'"));
+        }
+        Ok(Challenge { count, difficulty })
+    }
+}
+
+struct Problem {
+    question: String,
+    answer: i64,
+}
+
+impl Problem {
+    fn random(difficulty: Difficulty) -> Self {
+        let mut rng = rand::thread_rng();
+        let (question, answer) = match difficulty {
+            Difficulty::Easy => {
+                let (a, b): (i64, i64) = (rng.gen_range(1..=10), rng.gen_range(1..=10));
+                if rng.gen_bool(0.5) {
+                    (format!("{a} + {b}"), a + b)
+                } else {
+                    (format!("{} - {}", a.max(b), a.min(b)), a.max(b) - a.min(b))
+                }
+            }
+            Difficulty::Medium => {
+                let (a, b): (i64, i64) = (rng.gen_range(10..=99), rng.gen_range(10..=99));
+                match rng.gen_range(0..3) {
+                    0 => (format!("{a} + {b}"), a + b),
+                    1 => (format!("{a} - {b}"), a - b),
+                    _ => {
+                        let (a, b): (i64, i64) = (rng.gen_range(2..=12), rng.gen_range(2..=12));
+                        (format!("{a} × {b}"), a * b)
+                    }
+                }
+            }
+            Difficulty::Hard => {
+                let (a, b): (i64, i64) = (rng.gen_range(12..=99), rng.gen_range(3..=19));
+                let c: i64 = rng.gen_range(10..=999);
+                if rng.gen_bool(0.5) {
+                    (format!("{a} × {b} + {c}"), a * b + c)
+                } else {
+                    (format!("{a} × {b} - {c}"), a * b - c)
+                }
+            }
+        };
+        Problem { question, answer }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = ", self.question)
+    }
+}
+
+/// Unlocks early only after solving a few arithmetic problems, friction
+/// instead of a secret.
+pub struct ChallengeEntity {
+    id: String,
+    challenge: Challenge,
+    problem: Problem,
+    solved: u32,
+    answer: String,
+    dirty: bool,
+    unlocked: bool,
+    /// Where wrong answers are reported
+    feedback: EntityId,
+    failed_attempts: u32,
+}
+
+impl ChallengeEntity {
+    pub fn new(id: &str, challenge: Challenge, feedback: EntityId) -> Self {
+        ChallengeEntity {
+            id: format!("ChallengeEntity-{id}"),
+            challenge,
+            problem: Problem::random(challenge.difficulty),
+            solved: 0,
+            answer: String::new(),
+            dirty: true,
+            unlocked: false,
+            feedback,
+            failed_attempts: 0,
+        }
+    }
+
+    /// Count on from earlier failures, e.g. of a lock restored after a crash.
+    pub fn set_failed_attempts(&mut self, failed_attempts: u32) {
+        self.failed_attempts = failed_attempts;
+    }
+
+    fn prompt(&self) -> String {
+        let heading = i18n::tr(
+            "challenge.problem",
+            &[("n", &(self.solved + 1)), ("count", &self.challenge.count)],
+        );
+        format!("{heading}{}", self.problem)
+    }
+
+    fn submit(&mut self) {
+        let correct = self.answer.parse::<i64>().ok() == Some(self.problem.answer);
+        self.answer.clear();
+        self.problem = Problem::random(self.challenge.difficulty);
+        if !correct {
+            self.failed_attempts += 1;
+            session::update_stats(|stats| stats.failed_attempts = self.failed_attempts);
+            warn!(attempt = self.failed_attempts, "wrong challenge answer");
+            self.dirty = false;
+            return;
+        }
+        self.solved += 1;
+        if self.solved >= self.challenge.count {
+            info!(
+                failed_attempts = self.failed_attempts,
+                "unlocked by solving the challenge"
+            );
+            session::set_exit_reason("challenge");
+            self.unlocked = true;
+        }
+    }
+}
+
+impl Named for ChallengeEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for ChallengeEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let line = format!("{}{}", self.prompt(), self.answer);
+        let line_width = text::width(&line);
+        let (width, _) = layout::terminal_size();
+        let x = layout::prompt_x(width, line_width, text::is_rtl(&line));
+        let prompt_y = layout::prompt_y();
+        execute!(
+            draw_context.out,
+            MoveTo(0, prompt_y),
+            Clear(ClearType::CurrentLine),
+            MoveTo(x, prompt_y),
+            Print(&line),
+            MoveTo(x + line_width as u16, prompt_y)
+        )?;
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        if self.unlocked {
+            return UpdateResult::kill();
+        }
+        if !self.dirty {
+            self.dirty = true;
+            context.emit(ControlEvent {
+                recipient: Recipient::Entity(self.feedback),
+                property_key: "message".to_string(),
+                property_value: i18n::t("feedback.wrong_answer").to_string(),
+            });
+            context.emit(ControlEvent {
+                recipient: Recipient::Controller,
+                property_key: "failed_attempt".to_string(),
+                property_value: self.failed_attempts.to_string(),
+            });
+        }
+        UpdateResult::focus()
+    }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind,
+            ..
+        }) = event.event
+        else {
+            return false;
+        };
+        match main_key(*code) {
+            KeyCode::Char('u') if *modifiers == KeyModifiers::CONTROL => {
+                self.answer.clear();
+                true
+            }
+            KeyCode::Char(_) if is_shortcut(*modifiers) => false,
+            KeyCode::Char(c @ ('0'..='9' | '-')) => {
+                self.answer.push(c);
+                true
+            }
+            KeyCode::Backspace => {
+                self.answer.pop();
+                true
+            }
+            KeyCode::Enter if *kind == KeyEventKind::Press => {
+                self.submit();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_command(&mut self, command: Command, trigger: Trigger) -> bool {
+        if command != Command::Unlock {
+            return false;
+        }
+        info!(%trigger, failed_attempts = self.failed_attempts, "unlocked remotely");
+        session::set_exit_reason(&format!("remote_{trigger}"));
+        self.unlocked = true;
+        true
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -0,0 +1,187 @@
+//! `[clipboard] clear`: emptying the clipboard when the lock engages and
+//! whenever something lands in it until the lock ends, so neither whatever
+//! was copied last nor anything a background process copies meanwhile can be
+//! pasted or read off it. With `restore` the text it held comes back on
+//! unlock. Uses wl-clipboard (wlr-data-control) on Wayland, xclip on X11 and
+//! pbcopy/pbpaste on macOS.
+
+use std::{
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use tracing::{info, warn};
+
+/// How often the clipboard is checked for something new.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run `program` and return what it printed, `None` if it failed.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    use std::process::{Command, Stdio};
+
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run `program` with `input` on its stdin. The copy tools fork to keep
+/// serving the selection, so nothing of theirs may stay on our terminal.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn feed(program: &str, args: &[&str], input: &str) -> bool {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let Ok(mut child) = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    let written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(input.as_bytes()).is_ok());
+    child.wait().is_ok_and(|status| status.success()) && written
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{feed, output};
+
+    fn wayland() -> bool {
+        std::env::var_os("WAYLAND_DISPLAY").is_some()
+    }
+
+    pub fn read() -> Option<String> {
+        if wayland() {
+            // Fails when there is nothing to paste
+            output("wl-paste", &["--no-newline"])
+        } else {
+            output("xclip", &["-selection", "clipboard", "-o"])
+        }
+    }
+
+    pub fn write(text: &str) -> bool {
+        if wayland() {
+            feed("wl-copy", &[], text)
+        } else {
+            feed("xclip", &["-selection", "clipboard", "-i"], text)
+        }
+    }
+
+    /// Empty the clipboard and the primary selection, which middle click
+    /// pastes.
+    pub fn clear() -> bool {
+        if wayland() {
+            output("wl-copy", &["--clear"]).is_some()
+                && output("wl-copy", &["--primary", "--clear"]).is_some()
+        } else {
+            feed("xclip", &["-selection", "clipboard", "-i"], "")
+                && feed("xclip", &["-selection", "primary", "-i"], "")
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{feed, output};
+
+    pub fn read() -> Option<String> {
+        output("pbpaste", &[])
+    }
+
+    pub fn write(text: &str) -> bool {
+        feed("pbcopy", &[], text)
+    }
+
+    pub fn clear() -> bool {
+        write("")
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod platform {
+    pub fn read() -> Option<String> {
+        None
+    }
+
+    pub fn write(_: &str) -> bool {
+        false
+    }
+
+    pub fn clear() -> bool {
+        tracing::warn!("clearing the clipboard is not supported on this platform");
+        false
+    }
+}
+
+/// Keeps the clipboard empty until dropped, then puts back what `lock` saved.
+pub struct ClipboardLock {
+    saved: Option<String>,
+    /// Dropped to stop the watcher right away
+    stop: Option<Sender<()>>,
+    watcher: Option<JoinHandle<()>>,
+}
+
+/// Empty the clipboard now and whenever something is copied until the
+/// returned lock is dropped. Only text is saved for `restore`.
+pub fn lock(restore: bool) -> ClipboardLock {
+    let saved = restore
+        .then(platform::read)
+        .flatten()
+        .filter(|text| !text.is_empty());
+    if !platform::clear() {
+        warn!("could not clear the clipboard");
+        return ClipboardLock {
+            saved: None,
+            stop: None,
+            watcher: None,
+        };
+    }
+    info!(saved = saved.is_some(), "cleared the clipboard");
+    let (stop, stopped) = mpsc::channel::<()>();
+    let watcher = thread::spawn(move || {
+        while stopped.recv_timeout(CHECK_INTERVAL) == Err(RecvTimeoutError::Timeout) {
+            if platform::read().is_some_and(|text| !text.is_empty()) {
+                info!("something was copied during the lock, clearing it");
+                platform::clear();
+            }
+        }
+    });
+    ClipboardLock {
+        saved,
+        stop: Some(stop),
+        watcher: Some(watcher),
+    }
+}
+
+impl Drop for ClipboardLock {
+    fn drop(&mut self) {
+        // The watcher must be done before restoring, or it clears that too
+        drop(self.stop.take());
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+        if let Some(text) = self.saved.take() {
+            if platform::write(&text) {
+                info!("restored the clipboard");
+            } else {
+                warn!("could not restore the clipboard");
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{io::Write, time::Duration};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use tracing::warn;
+
+use crate::{
+    controller::{DrawContext, EntityId},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    hooks, layout,
+    tasks::{self, TaskHandle},
+    theme,
+};
+
+/// Shows the output of a shell command on a footer row, e.g. `acpi -b` or
+/// `playerctl metadata --format '{{ artist }} - {{ title }}'`. The command
+/// runs every `refresh` on the background pool, so a slow one never holds up
+/// the lock.
+pub struct CommandOutputEntity {
+    id: String,
+    entity_id: EntityId,
+    row: u16,
+    text: String,
+    failed: bool,
+    _task: TaskHandle,
+}
+
+impl CommandOutputEntity {
+    /// Start running `command`. `row` is the widget's line within the footer.
+    pub fn spawn(id: &str, command: &str, refresh: Duration, timeout: Duration, row: u16) -> Self {
+        let id = format!("CommandOutputEntity-{id}");
+        let entity_id = EntityId::next();
+        let command = command.to_string();
+        let task = tasks::every(entity_id, "output", refresh, move || {
+            hooks::output_with_timeout(&command, timeout)
+                .map(|output| one_line(&output))
+                .map_err(|error| {
+                    warn!(%command, %error, "widget command failed");
+                    format!("⚠ {command}: {error}")
+                })
+        });
+        CommandOutputEntity {
+            id,
+            entity_id,
+            row,
+            text: String::new(),
+            failed: false,
+            _task: task,
+        }
+    }
+}
+
+/// The trimmed lines of `output` on a single row.
+fn one_line(output: &str) -> String {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+impl Named for CommandOutputEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for CommandOutputEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        match key {
+            "output" if !self.failed => Some(&self.text),
+            "error" if self.failed => Some(&self.text),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "output" => self.failed = false,
+            "error" => self.failed = true,
+            _ => return false,
+        }
+        self.text = value.to_string();
+        true
+    }
+}
+
+impl FullEntity for CommandOutputEntity {}
+
+impl Entity for CommandOutputEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, _) = layout::terminal_size();
+        let text: String = theme::glyphs(&self.text)
+            .chars()
+            .take(width as usize)
+            .collect();
+        let theme = theme::current();
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(if self.failed {
+                theme.error
+            } else {
+                theme.muted
+            }))?
+            .queue(Print(text))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
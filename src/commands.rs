@@ -0,0 +1,82 @@
+//! Commands from outside the lock UI, e.g. MQTT. Every source publishes them
+//! on the controller's message bus.
+
+use std::{str::FromStr, thread, time::Duration};
+
+use crate::{
+    bus::{self, Message},
+    count_down_entity::Trigger,
+    schedule,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Start a lock, optionally for a given time instead of `--for`
+    Lock(Option<Duration>),
+    Unlock,
+    Pause,
+    Resume,
+    Extend(Duration),
+    /// Postpone the next scheduled lock
+    Delay(Duration),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    /// Parses `lock [duration]`, `unlock`, `pause`, `resume`,
+    /// `extend [duration]` and `delay [duration]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let command = match words.next() {
+            Some("lock") => Command::Lock(words.next().map(crate::parse_duration).transpose()?),
+            Some("unlock") => Command::Unlock,
+            Some("pause") => Command::Pause,
+            Some("resume") => Command::Resume,
+            Some("extend") => Command::Extend(match words.next() {
+                Some(duration) => crate::parse_duration(duration)?,
+                None => crate::count_down_entity::EXTEND_STEP,
+            }),
+            Some("delay") => Command::Delay(match words.next() {
+                Some(duration) => crate::parse_duration(duration)?,
+                None => schedule::DELAY_STEP,
+            }),
+            Some(other) => return Err(format!("Unknown command '{other}'")),
+            None => return Err("Empty command".to_string()),
+        };
+        if words.next().is_some() {
+            return Err(format!("Trailing arguments in '{s}'"));
+        }
+        Ok(command)
+    }
+}
+
+pub fn send(command: Command, trigger: Trigger) {
+    bus::publish(Message::Command(command, trigger));
+}
+
+/// Consume queued messages, returning the first `Lock` among them with its
+/// trigger. Delays go to the scheduler, anything else makes no sense while
+/// unlocked and is dropped.
+pub fn take_lock() -> Option<(Option<Duration>, Trigger)> {
+    let mut lock = None;
+    for message in bus::drain() {
+        match message {
+            Message::Command(Command::Lock(duration), trigger) if lock.is_none() => {
+                lock = Some((duration, trigger))
+            }
+            Message::Command(Command::Delay(by), trigger) => schedule::postpone(by, trigger),
+            _ => {}
+        }
+    }
+    lock
+}
+
+pub fn wait_for_lock(interval: Duration) -> (Option<Duration>, Trigger) {
+    loop {
+        if let Some(lock) = take_lock() {
+            return lock;
+        }
+        thread::sleep(interval);
+    }
+}
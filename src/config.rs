@@ -0,0 +1,384 @@
+//! Settings that are awkward as flags, read from a TOML file
+//! (`~/.config/screenlock/config.toml` unless `--config` says otherwise).
+//! Edits to `[feedback]`, `[grab]` and `[theme]` apply to a running lock,
+//! the rest to the next one. Text may use the placeholders in `template`.
+//!
+//! ```toml
+//! [contact]
+//! lines = ["This laptop belongs to Jane Doe", "If found, call +1 555 0100"]
+//!
+//! [qr]
+//! data = "WIFI:T:WPA;S:guests;P:welcome123;;"
+//! label = "Guest Wi-Fi"
+//!
+//! [feedback]
+//! wrong_password = "{red}Nope.{/red} Try again."
+//! duration = "5s"
+//!
+//! [grab]
+//! allow = ["CapsLock", "Tab"]
+//!
+//! [clipboard]
+//! clear = true
+//! restore = true
+//!
+//! [theme]
+//! ascii = true
+//!
+//! [greeting]
+//! templates = [
+//!     { from = "06:00", text = "Rise and shine, {name}" },
+//!     { from = "13:00", text = "Back to it after lunch, {name}?" },
+//! ]
+//!
+//! # `--profile break`, the same as `--for 5m --strict --screensaver bounce`
+//! [profile.break]
+//! for = "5m"
+//! strict = true
+//! screensaver = "bounce"
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::NaiveTime;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Deserializer};
+use tracing::{info, warn};
+
+/// How long the events of one save get to die down before the config is
+/// read again.
+const SETTLE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub contact: Contact,
+    pub qr: Qr,
+    pub feedback: Feedback,
+    pub grab: Grab,
+    pub clipboard: Clipboard,
+    pub theme: Theme,
+    pub greeting: Greeting,
+    /// Named sets of flags, see `profile_flags`
+    pub profile: BTreeMap<String, toml::Table>,
+}
+
+impl Config {
+    /// The flags `[profile.<name>]` stands for: `key = "value"` is
+    /// `--key=value`, `key = true` is `--key`, and a list repeats the flag for
+    /// each of its items.
+    pub fn profile_flags(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        let Some(profile) = self.profile.get(name) else {
+            let known: Vec<&String> = self.profile.keys().collect();
+            anyhow::bail!("there is no [profile.{name}] in the config, only {known:?}");
+        };
+        let mut flags = Vec::new();
+        for (key, value) in profile {
+            let flag = format!("--{}", key.replace('_', "-"));
+            let values = match value {
+                toml::Value::Array(items) => items.clone(),
+                value => vec![value.clone()],
+            };
+            for value in values {
+                match value {
+                    toml::Value::Boolean(true) => flags.push(flag.clone()),
+                    toml::Value::Boolean(false) => {}
+                    toml::Value::String(text) => flags.push(format!("{flag}={text}")),
+                    toml::Value::Integer(_) | toml::Value::Float(_) => {
+                        flags.push(format!("{flag}={value}"))
+                    }
+                    _ => anyhow::bail!(
+                        "{key} in [profile.{name}] must be a string, number or boolean"
+                    ),
+                }
+            }
+        }
+        Ok(flags)
+    }
+}
+
+/// Owner details shown at the bottom of the lock screen, for whoever finds
+/// the machine.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Contact {
+    pub lines: Vec<String>,
+}
+
+/// Anything worth scanning off the lock screen, drawn as a QR code.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Qr {
+    pub data: Option<String>,
+    /// Shown below the code
+    pub label: Option<String>,
+}
+
+/// Messages replacing the defaults when input is turned down. Markup such as
+/// `{red}…{/red}` gives them colors of their own.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Feedback {
+    pub wrong_password: Option<String>,
+    pub wrong_code: Option<String>,
+    /// An authenticator code that was valid a few minutes ago
+    pub expired_code: Option<String>,
+    /// Input during the delay after a wrong password, `{secs}` is replaced
+    /// with the seconds left
+    pub too_soon: Option<String>,
+    /// The right password while unlocking isn't allowed, e.g. by the
+    /// presence check
+    pub refused: Option<String>,
+    /// How long messages stay up, e.g. "5s"
+    #[serde(deserialize_with = "duration")]
+    pub duration: Option<Duration>,
+}
+
+/// The input grab.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Grab {
+    /// Keys let through while locked, by name: `CapsLock`, `Tab`, `MetaLeft`,
+    /// `MetaRight`, `ControlLeft`, `ControlRight`, `KeyC`, `Escape`, `Alt`,
+    /// `AltGr` or `MouseLeft`. Volume, brightness and media keys are never
+    /// blocked in the first place.
+    pub allow: Vec<String>,
+}
+
+/// What happens to the clipboard while locked.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Clipboard {
+    /// Empty it when the lock engages, and again whenever something is
+    /// copied until the lock ends
+    pub clear: bool,
+    /// Put the text it held back once unlocked, instead of leaving it empty
+    pub restore: bool,
+}
+
+/// How things look, see `--ascii`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// The high contrast palette and plain ASCII instead of emoji
+    pub ascii: bool,
+}
+
+/// What `--greeting` says, by time of day.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Greeting {
+    /// Each applies from its time until the next one's; the built-in ones
+    /// if empty
+    pub templates: Vec<GreetingTemplate>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GreetingTemplate {
+    /// Local time of day, e.g. "13:00"
+    #[serde(deserialize_with = "time_of_day")]
+    pub from: NaiveTime,
+    /// `{name}` is replaced with the user's first name
+    pub text: String,
+}
+
+fn time_of_day<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&text, "%H:%M").map_err(serde::de::Error::custom)
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    crate::parse_duration(&text)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("screenlock").join("config.toml"))
+}
+
+/// Read the config at `path`, or the default location if `None`. Only a
+/// missing default file is fine, that is an empty config.
+pub fn load(path: Option<&Path>) -> anyhow::Result<Config> {
+    let (path, explicit) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if !explicit && error.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Config::default())
+        }
+        Err(error) => {
+            return Err(error).with_context(|| format!("could not read {}", path.display()))
+        }
+    };
+    toml::from_str(&text).with_context(|| format!("invalid config {}", path.display()))
+}
+
+/// Calls back with the config whenever its file changes, until dropped.
+pub struct Watch {
+    /// Dropped first, which ends the events the thread waits for
+    notifier: Option<RecommendedWatcher>,
+    watcher: Option<JoinHandle<()>>,
+}
+
+/// Look for edits to the config at `path`, or the default location, and hand
+/// each version that loads to `on_change`. One that doesn't is logged and
+/// the last good one stays.
+///
+/// Editors often save by renaming a new file over the old one, which a watch
+/// on the file itself loses track of, so it's the directory that is watched.
+pub fn watch(path: Option<&Path>, mut on_change: impl FnMut(Config) + Send + 'static) -> Watch {
+    let inactive = || Watch {
+        notifier: None,
+        watcher: None,
+    };
+    let Some(path) = path.map(Path::to_path_buf).or_else(default_path) else {
+        return inactive();
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let (events, received) = mpsc::channel::<notify::Result<notify::Event>>();
+    let notifier = notify::recommended_watcher(events).and_then(|mut notifier| {
+        notifier.watch(&dir, RecursiveMode::NonRecursive)?;
+        Ok(notifier)
+    });
+    let notifier = match notifier {
+        Ok(notifier) => notifier,
+        Err(error) => {
+            warn!(?error, dir = %dir.display(), "not watching the config for edits");
+            return inactive();
+        }
+    };
+    let watcher = thread::spawn(move || {
+        while let Ok(event) = received.recv() {
+            match event {
+                Ok(event) if touches(&event, &path) => {}
+                Ok(_) => continue,
+                Err(error) => {
+                    warn!(?error, "watching the config");
+                    continue;
+                }
+            }
+            // One save is often a few events, read the file once they stop
+            loop {
+                match received.recv_timeout(SETTLE) {
+                    Ok(_) => {}
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            match load(Some(&path)) {
+                Ok(config) => {
+                    info!(path = %path.display(), "config changed, applying it");
+                    on_change(config);
+                }
+                Err(error) => warn!(?error, "ignoring the changed config"),
+            }
+        }
+    });
+    Watch {
+        notifier: Some(notifier),
+        watcher: Some(watcher),
+    }
+}
+
+/// Whether `event` in the config's directory is about the config itself.
+fn touches(event: &notify::Event, path: &Path) -> bool {
+    !event.kind.is_access()
+        && event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == path.file_name())
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        drop(self.notifier.take());
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles(text: &str) -> Config {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn profile_flags_turn_values_into_flags() {
+        let config = profiles(
+            r#"
+            [profile.break]
+            for = "5m"
+            strict = true
+            ascii = false
+            max_attempts = 3
+            widget = ["date", "uptime -p"]
+            "#,
+        );
+        assert_eq!(
+            config.profile_flags("break").unwrap(),
+            [
+                "--for=5m",
+                "--max-attempts=3",
+                "--strict",
+                "--widget=date",
+                "--widget=uptime -p",
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_flags_reject_unknown_profiles_and_tables() {
+        let config = profiles(
+            r#"
+            [profile.break]
+            for = "5m"
+
+            [profile.away.nested]
+            for = "1h"
+            "#,
+        );
+        let unknown = config.profile_flags("lunch").unwrap_err().to_string();
+        assert!(unknown.contains("[profile.lunch]"), "{unknown}");
+        assert!(unknown.contains("break"), "{unknown}");
+        assert!(config.profile_flags("away").is_err());
+    }
+
+    #[test]
+    fn a_rename_over_the_config_touches_it() {
+        use notify::event::{AccessKind, EventKind, ModifyKind, RenameMode};
+
+        let path = Path::new("/home/jane/.config/screenlock/config.toml");
+        let renamed = notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(path.with_file_name(".config.toml.swp"))
+            .add_path(path.to_path_buf());
+        assert!(touches(&renamed, path));
+        let other = notify::Event::new(EventKind::Create(notify::event::CreateKind::File))
+            .add_path(path.with_file_name("notes.txt"));
+        assert!(!touches(&other, path));
+        let read = notify::Event::new(EventKind::Access(AccessKind::Read)).add_path(path.into());
+        assert!(!touches(&read, path));
+    }
+}
@@ -0,0 +1,69 @@
+use std::io::Write;
+
+use crossterm::{
+    cursor::MoveTo,
+    style::Print,
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    layout, text, theme,
+};
+
+/// Who the machine belongs to and how to reach them, centered on the last
+/// rows of the footer whatever the terminal height.
+pub struct ContactEntity {
+    id: String,
+    lines: Vec<String>,
+    /// First footer row of the contact lines
+    row: u16,
+}
+
+impl ContactEntity {
+    pub fn new(id: &str, lines: Vec<String>, row: u16) -> Self {
+        ContactEntity {
+            id: format!("ContactEntity-{id}"),
+            lines: lines
+                .iter()
+                .map(|line| theme::glyphs(line).into_owned())
+                .collect(),
+            row,
+        }
+    }
+}
+
+impl Named for ContactEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for ContactEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, _) = layout::terminal_size();
+        let top = layout::footer_y() + self.row;
+        for (idx, line) in self.lines.iter().enumerate() {
+            let y = top + idx as u16;
+            let x = (width as usize).saturating_sub(text::width(line)) as u16 / 2;
+            draw_context
+                .out
+                .queue(MoveTo(0, y))?
+                .queue(Clear(ClearType::CurrentLine))?
+                .queue(MoveTo(x, y))?
+                .queue(Print(line))?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -1,66 +1,238 @@
 use std::{
-    io::{stdout, Stdout},
-    time::Duration,
+    collections::VecDeque,
+    io::{stdout, Write},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant},
 };
 
 use crossterm::terminal::disable_raw_mode;
 
 use crossterm::{
-    cursor::{MoveTo, RestorePosition, SavePosition},
-    event::{self, Event},
+    cursor::{MoveTo, RestorePosition, SavePosition, Show},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyEvent, KeyEventKind},
     execute,
     terminal::{enable_raw_mode, Clear, ClearType},
 };
+use tracing::{info, warn};
 
-use crate::entity::FullEntity;
+use crate::{
+    bus::{self, Message},
+    commands::Command,
+    count_down_entity::Trigger,
+    entity::{Cadence, FullEntity},
+    layout,
+    screen::{ScreenBuffer, SharedBuffer},
+    tasks,
+};
+
+/// Set while we have the terminal in raw mode.
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Leave raw mode and show the cursor again, if we changed them. Returns false
+/// if there was nothing to restore. Safe to call from a panic hook on any
+/// thread.
+pub fn restore_terminal() -> bool {
+    if !RAW_MODE.swap(false, Ordering::SeqCst) {
+        return false;
+    }
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), DisableBracketedPaste);
+    // Whatever was typed at the lock must not end up in the shell
+    discard_typeahead();
+    let _ = execute!(stdout(), Show);
+    true
+}
+
+/// Drop input the terminal has queued but nobody read yet.
+#[cfg(unix)]
+fn discard_typeahead() {
+    // SAFETY: plain syscall on a descriptor we don't own or close
+    unsafe { libc::tcflush(libc::STDIN_FILENO, libc::TCIFLUSH) };
+}
+
+#[cfg(not(unix))]
+fn discard_typeahead() {}
+
+/// Keeps the terminal in raw mode until dropped, unwinding from a panic
+/// included.
+pub struct RawMode(());
+
+impl RawMode {
+    pub fn enable() -> anyhow::Result<Self> {
+        // Keys pressed before we took over would otherwise count as input
+        discard_typeahead();
+        enable_raw_mode()?;
+        RAW_MODE.store(true, Ordering::SeqCst);
+        Ok(RawMode(()))
+    }
+
+    /// Put the terminal back in raw mode if something took it out, e.g. a
+    /// `reset` or `stty sane` from another process. Returns true if it had
+    /// to, the screen is likely garbled then too.
+    pub fn reassert(&self) -> anyhow::Result<bool> {
+        if !raw_mode_lost() {
+            return Ok(false);
+        }
+        warn!("terminal left raw mode, restoring it");
+        // crossterm thinks it is still raw and would do nothing otherwise
+        disable_raw_mode()?;
+        enable_raw_mode()?;
+        Ok(true)
+    }
+}
+
+/// Whether the terminal echoes or line-buffers input again.
+#[cfg(unix)]
+fn raw_mode_lost() -> bool {
+    // SAFETY: termios is plain data and tcgetattr only writes into it
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+        return false;
+    }
+    termios.c_lflag & (libc::ICANON | libc::ECHO) != 0
+}
+
+#[cfg(not(unix))]
+fn raw_mode_lost() -> bool {
+    false
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
 
+/// Where entities draw. Anything implementing `Write` works: the terminal,
+/// stderr, a PTY or a buffer for golden output.
 pub struct DrawContext {
-    pub out: Stdout,
+    pub out: Box<dyn Write>,
+    /// Set if we put the terminal in raw mode and have to restore it
+    raw_mode: Option<RawMode>,
+    /// Output is read line by line, don't move the cursor around behind the
+    /// entities' backs
+    plain: bool,
 }
 
 impl DrawContext {
     pub fn try_new() -> anyhow::Result<Self> {
-        let mut out = stdout();
-        enable_raw_mode()?;
+        Self::on_terminal(stdout())
+    }
+
+    /// Put the controlling terminal in raw mode and draw into `out`.
+    pub fn on_terminal(out: impl Write + 'static) -> anyhow::Result<Self> {
+        let mut out: Box<dyn Write> = Box::new(out);
+        let raw_mode = RawMode::enable()?;
         execute!(out, Clear(ClearType::All), MoveTo(0, 0))?;
-        Ok(DrawContext { out })
+        // Pastes arrive whole then, not as keys. Not every terminal can
+        let _ = execute!(out, EnableBracketedPaste);
+        Ok(DrawContext {
+            out,
+            raw_mode: Some(raw_mode),
+            plain: false,
+        })
+    }
+
+    /// Draw into `out` without touching the terminal, e.g. into a buffer.
+    pub fn with_writer(out: impl Write + 'static) -> Self {
+        DrawContext {
+            out: Box::new(out),
+            raw_mode: None,
+            plain: false,
+        }
     }
 }
 
 impl Drop for DrawContext {
     fn drop(&mut self) {
-        let _ = disable_raw_mode();
-        let _ = execute!(self.out, Clear(ClearType::All), MoveTo(0, 0));
+        // Nothing to clear if a panic hook restored the terminal already, it
+        // would wipe the panic message
+        if self.raw_mode.is_some() && restore_terminal() {
+            let _ = execute!(self.out, Clear(ClearType::All), MoveTo(0, 0));
+        }
     }
 }
 
+/// Where the controller gets its input events from.
+pub trait EventSource {
+    /// Wait up to `timeout` for an event, returns true if one is ready.
+    fn poll(&mut self, timeout: Duration) -> anyhow::Result<bool>;
+    fn read(&mut self) -> anyhow::Result<Event>;
+
+    /// Throw away events that are ready right now, returns how many.
+    fn discard_pending(&mut self) -> anyhow::Result<usize> {
+        let mut discarded = 0;
+        while self.poll(Duration::ZERO)? {
+            self.read()?;
+            discarded += 1;
+        }
+        Ok(discarded)
+    }
+}
+
+/// Events from the real terminal.
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> anyhow::Result<bool> {
+        Ok(event::poll(timeout)?)
+    }
+
+    fn read(&mut self) -> anyhow::Result<Event> {
+        Ok(event::read()?)
+    }
+}
+
+/// Scripts address the listeners registered with `Controller::add_listener` by
+/// this name.
+pub const CONTROLLER_NAME: &str = "Controller";
+
+/// Refers to an entity added to a `Controller`, to address `ControlEvent`s to
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntityId(u64);
+
+impl EntityId {
+    /// A fresh id, unique within the process.
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        EntityId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Who a `ControlEvent` is for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recipient {
+    /// The listeners registered with `Controller::add_listener`
+    Controller,
+    Entity(EntityId),
+    /// The entity with this name, or the listeners for `CONTROLLER_NAME`. Only
+    /// for scripts, which can't hold an `EntityId`
+    Named(String),
+}
+
 pub struct ControlEvent {
-    pub name: String,
+    pub recipient: Recipient,
     pub property_key: String,
     pub property_value: String,
 }
 
+/// What an entity wants after an update. Events go out through
+/// `UpdateContext::emit`.
 pub struct UpdateResult {
+    /// End the whole lock
     pub kill: bool,
     pub focused: bool,
-    pub events: Vec<ControlEvent>,
+    /// Take only this entity away, e.g. a message that was shown long enough
+    pub remove: bool,
 }
 
 impl UpdateResult {
-    #[allow(unused)]
-    pub fn new(kill: bool, focused: bool, events: Vec<ControlEvent>) -> Self {
-        UpdateResult {
-            kill,
-            focused,
-            events,
-        }
-    }
-
     pub fn kill() -> Self {
         UpdateResult {
             kill: true,
             focused: false,
-            events: Vec::new(),
+            remove: false,
         }
     }
 
@@ -68,7 +240,7 @@ impl UpdateResult {
         UpdateResult {
             kill: false,
             focused: true,
-            events: Vec::new(),
+            remove: false,
         }
     }
 
@@ -76,7 +248,15 @@ impl UpdateResult {
         UpdateResult {
             kill: false,
             focused: false,
-            events: Vec::new(),
+            remove: false,
+        }
+    }
+
+    pub fn remove() -> Self {
+        UpdateResult {
+            kill: false,
+            focused: false,
+            remove: true,
         }
     }
 }
@@ -85,82 +265,537 @@ pub struct EventContext<'a> {
     pub event: &'a Event,
 }
 
+/// What an entity gets to know on `Entity::update`: the time that actually
+/// passed, so animations keep their pace however often the loop gets around
+/// to them, the terminal size and whether it has the cursor.
+#[derive(Clone, Copy, Debug)]
+pub struct UpdateContext {
+    /// Time since this entity's previous update, zero on the first
+    pub dt: Duration,
+    pub now: Instant,
+    /// Columns and rows, as the layout sees them
+    pub size: (u16, u16),
+    /// Whether the entity kept the cursor after its previous update
+    pub focused: bool,
+}
+
+impl UpdateContext {
+    /// Send `event` to its recipient through the bus, like events from any
+    /// other thread.
+    pub fn emit(&self, event: ControlEvent) {
+        bus::publish(Message::Event(event));
+    }
+}
+
+type Listener = Box<dyn FnMut(&str) -> Vec<ControlEvent>>;
+
+struct EntitySlot {
+    id: EntityId,
+    entity: Box<dyn FullEntity>,
+    last_update: Option<Instant>,
+    focused: bool,
+    dirty: bool,
+    /// Asked to be removed, it goes on the next pass of the loop
+    removed: bool,
+}
+
+impl EntitySlot {
+    fn new<U: FullEntity + 'static>(entity: U) -> Self {
+        EntitySlot {
+            id: entity.entity_id().unwrap_or_else(EntityId::next),
+            entity: Box::new(entity),
+            last_update: None,
+            focused: false,
+            dirty: true,
+            removed: false,
+        }
+    }
+
+    /// When the entity next wants an update, `None` if it only updates on
+    /// change. New entities start out dirty and are due right away.
+    fn deadline(&self, tick_interval: Duration) -> Option<Instant> {
+        let last_update = self.last_update?;
+        match self.entity.cadence() {
+            Cadence::EveryTick => last_update.checked_add(tick_interval),
+            Cadence::Every(interval) => last_update.checked_add(interval),
+            Cadence::OnChange => None,
+        }
+    }
+
+    fn is_due(&self, now: Instant, tick_interval: Duration) -> bool {
+        !self.removed
+            && (self.dirty
+                || self
+                    .deadline(tick_interval)
+                    .is_some_and(|deadline| deadline <= now))
+    }
+}
+
+/// Upper bound on how long the loop sleeps when no entity has a deadline.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often we check that the terminal is still in raw mode.
+const RAW_MODE_PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How quickly messages from other threads are picked up while there may be
+/// some.
+const BUS_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// One screen of a session, e.g. the lock itself or the summary after it:
+/// its entities and the listeners for their events.
+pub struct Scene {
+    name: &'static str,
+    entities: Vec<EntitySlot>,
+    listeners: Vec<(String, Listener)>,
+}
+
+impl Scene {
+    pub fn new(name: &'static str) -> Self {
+        Scene {
+            name,
+            entities: Vec::new(),
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Like `Controller::add_entity`.
+    pub fn add_entity<U: FullEntity + 'static>(&mut self, entity: U) -> EntityId {
+        let slot = EntitySlot::new(entity);
+        let id = slot.id;
+        self.entities.push(slot);
+        id
+    }
+
+    /// Like `Controller::add_listener`.
+    pub fn add_listener<F>(&mut self, property_key: &str, listener: F)
+    where
+        F: FnMut(&str) -> Vec<ControlEvent> + 'static,
+    {
+        self.listeners
+            .push((property_key.to_string(), Box::new(listener)));
+    }
+}
+
+/// Builds a scene once the one before it ended, so it can show how that went.
+type SceneFactory = Box<dyn FnOnce() -> anyhow::Result<Scene>>;
+
+/// Why the controller stops running the current scene.
+enum Ending {
+    /// An entity ended it, or none are left, the next scene may start
+    Scene,
+    /// Quit altogether, e.g. on SIGTERM
+    All,
+}
+
 pub struct Controller {
-    entities: Vec<Box<dyn FullEntity>>,
-    poll_interval: Duration,
+    scene: Scene,
+    next_scenes: VecDeque<SceneFactory>,
+    tick_interval: Duration,
+    plain: bool,
 }
 
 impl Controller {
     pub fn new() -> Self {
         Controller {
-            entities: Vec::new(),
-            poll_interval: Duration::from_millis(50),
+            scene: Scene::new("lock"),
+            next_scenes: VecDeque::new(),
+            tick_interval: Duration::from_millis(50),
+            plain: false,
         }
     }
 
-    pub fn add_entity<U: FullEntity + 'static>(&mut self, entity: U) {
-        self.entities.push(Box::new(entity));
+    /// Switch to the scene `next` builds once the current one and any queued
+    /// before it ended, instead of returning.
+    pub fn then(&mut self, next: impl FnOnce() -> anyhow::Result<Scene> + 'static) {
+        self.next_scenes.push_back(Box::new(next));
+    }
+
+    /// Move on to the next scene after `ending`, returns false if there is
+    /// none and the controller is done.
+    fn advance(&mut self, ending: Ending, context: &mut DrawContext) -> anyhow::Result<bool> {
+        if let Ending::All = ending {
+            return Ok(false);
+        }
+        let Some(next) = self.next_scenes.pop_front() else {
+            return Ok(false);
+        };
+        let previous = self.scene.name;
+        self.scene = next()?;
+        info!(from = previous, to = self.scene.name, "switching scene");
+        if !context.plain {
+            execute!(context.out, Clear(ClearType::All))?;
+        }
+        Ok(true)
+    }
+
+    /// Only ever append to the output: no saving and restoring the cursor
+    /// around unfocused entities and no clearing on resize.
+    pub fn set_plain(&mut self, plain: bool) {
+        self.plain = plain;
+    }
+
+    /// Add `entity` after the ones already there, returns what to address
+    /// events for it to.
+    pub fn add_entity<U: FullEntity + 'static>(&mut self, entity: U) -> EntityId {
+        self.scene.add_entity(entity)
+    }
+
+    /// Take the entity `id` away, returns false if there is none. The others
+    /// are redrawn, they may have been drawn over.
+    pub fn remove_entity(&mut self, id: EntityId) -> bool {
+        let before = self.scene.entities.len();
+        self.scene.entities.retain(|slot| slot.id != id);
+        self.mark_all_dirty();
+        self.scene.entities.len() != before
+    }
+
+    /// Register a listener for controller events with the given property key.
+    /// The events it returns are published on the bus like any other
+    /// `ControlEvent`.
+    pub fn add_listener<F>(&mut self, property_key: &str, listener: F)
+    where
+        F: FnMut(&str) -> Vec<ControlEvent> + 'static,
+    {
+        self.scene.add_listener(property_key, listener);
     }
 
     fn update_and_draw_entity(
-        entity: &mut Box<dyn FullEntity>,
+        slot: &mut EntitySlot,
         context: &mut DrawContext,
     ) -> anyhow::Result<UpdateResult> {
-        let result = entity.update();
-        if !result.focused {
+        let now = Instant::now();
+        let dt = slot
+            .last_update
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        let result = slot.entity.update(UpdateContext {
+            dt,
+            now,
+            size: layout::terminal_size(),
+            focused: slot.focused,
+        });
+        slot.last_update = Some(now);
+        slot.focused = result.focused;
+        slot.dirty = false;
+        if result.remove {
+            slot.removed = true;
+            return Ok(result);
+        }
+        let keep_cursor = !result.focused && !context.plain;
+        if keep_cursor {
             execute!(context.out, SavePosition)?;
         }
-        entity.draw(context)?;
-        if !result.focused {
+        slot.entity.draw(context)?;
+        if keep_cursor {
             execute!(context.out, RestorePosition)?
         }
         Ok(result)
     }
 
-    fn execute_entity_events(&mut self, events: &mut Vec<ControlEvent>) {
-        for event in events.drain(..) {
-            for entity in self.entities.iter_mut() {
-                if entity.get_name() == event.name {
-                    entity.set_property(&event.property_key, &event.property_value);
-                    break;
+    /// Sleep until the earliest entity deadline rather than a fixed tick.
+    fn poll_timeout(&self) -> Duration {
+        let now = Instant::now();
+        self.scene
+            .entities
+            .iter()
+            .filter(|slot| !slot.removed)
+            .filter_map(|slot| {
+                if slot.dirty {
+                    Some(now)
+                } else {
+                    slot.deadline(self.tick_interval)
+                }
+            })
+            .min()
+            .map_or(MAX_POLL_INTERVAL, |deadline| {
+                deadline.saturating_duration_since(now)
+            })
+            .min(if bus::is_enabled() || tasks::is_active() {
+                BUS_POLL_INTERVAL
+            } else {
+                MAX_POLL_INTERVAL
+            })
+    }
+
+    fn mark_all_dirty(&mut self) {
+        for slot in self.scene.entities.iter_mut() {
+            slot.dirty = true;
+        }
+    }
+
+    /// Remove the entities that asked for it and clear what they drew.
+    fn remove_finished(&mut self, context: &mut DrawContext) -> anyhow::Result<()> {
+        let finished: Vec<EntityId> = self
+            .scene
+            .entities
+            .iter()
+            .filter(|slot| slot.removed)
+            .map(|slot| slot.id)
+            .collect();
+        if finished.is_empty() {
+            return Ok(());
+        }
+        for id in finished {
+            self.remove_entity(id);
+        }
+        if !context.plain {
+            execute!(context.out, Clear(ClearType::All))?;
+        }
+        Ok(())
+    }
+
+    fn dispatch_event(&mut self, event: ControlEvent) {
+        let to_controller = match &event.recipient {
+            Recipient::Controller => true,
+            Recipient::Named(name) => name == CONTROLLER_NAME,
+            Recipient::Entity(_) => false,
+        };
+        if to_controller {
+            for (key, listener) in self.scene.listeners.iter_mut() {
+                if *key == event.property_key {
+                    for follow_up in listener(&event.property_value) {
+                        bus::publish(Message::Event(follow_up));
+                    }
                 }
             }
+            return;
+        }
+        for slot in self.scene.entities.iter_mut() {
+            let addressed = match &event.recipient {
+                Recipient::Entity(id) => slot.id == *id,
+                Recipient::Named(name) => slot.entity.get_name() == name,
+                Recipient::Controller => false,
+            };
+            if addressed {
+                slot.entity
+                    .set_property(&event.property_key, &event.property_value);
+                slot.dirty = true;
+                break;
+            }
         }
     }
 
-    fn work_loop(&mut self, context: &mut DrawContext) -> anyhow::Result<()> {
+    /// Hand a command to every entity, returns true if one of them ended the
+    /// scene.
+    fn dispatch_command(
+        &mut self,
+        command: Command,
+        trigger: Trigger,
+        context: &mut DrawContext,
+    ) -> anyhow::Result<bool> {
+        if let Command::Lock(_) | Command::Delay(_) = command {
+            return Ok(false); // already locked, nothing to start or postpone
+        }
+        for slot in self.scene.entities.iter_mut().filter(|slot| !slot.removed) {
+            if slot.entity.handle_command(command, trigger) {
+                let result = Self::update_and_draw_entity(slot, context)?;
+                if result.kill {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Handle everything on the bus, including what handling it publishes in
+    /// turn, until something ends the scene.
+    fn process_messages(&mut self, context: &mut DrawContext) -> anyhow::Result<Option<Ending>> {
         loop {
-            let mut events_to_process = Vec::new();
-            for entity in self.entities.iter_mut() {
-                let result = Self::update_and_draw_entity(entity, context)?;
+            let messages = bus::drain();
+            if messages.is_empty() {
+                return Ok(None);
+            }
+            for message in messages {
+                match message {
+                    Message::Event(event) => self.dispatch_event(event),
+                    Message::Command(command, trigger) => {
+                        if self.dispatch_command(command, trigger, context)? {
+                            return Ok(Some(Ending::Scene));
+                        }
+                    }
+                    Message::Quit => return Ok(Some(Ending::All)),
+                    Message::Redraw => {
+                        if !self.plain {
+                            execute!(context.out, Clear(ClearType::All))?;
+                        }
+                        self.mark_all_dirty();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run each scene until an entity asks to end it or none are left, reading
+    /// input from `events` and drawing into `context`.
+    pub fn run_with(
+        &mut self,
+        events: &mut dyn EventSource,
+        context: &mut DrawContext,
+    ) -> anyhow::Result<()> {
+        context.plain = self.plain;
+        let mut last_probe = Instant::now();
+        'scene: loop {
+            self.remove_finished(context)?;
+            if self.scene.entities.is_empty() {
+                if self.advance(Ending::Scene, context)? {
+                    continue;
+                }
+                return Ok(());
+            }
+            let now = Instant::now();
+            if let Some(raw_mode) = &context.raw_mode {
+                if now.duration_since(last_probe) >= RAW_MODE_PROBE_INTERVAL {
+                    last_probe = now;
+                    if raw_mode.reassert()? {
+                        execute!(context.out, Clear(ClearType::All))?;
+                        self.mark_all_dirty();
+                    }
+                }
+            }
+            for idx in 0..self.scene.entities.len() {
+                if !self.scene.entities[idx].is_due(now, self.tick_interval) {
+                    continue;
+                }
+                let result = Self::update_and_draw_entity(&mut self.scene.entities[idx], context)?;
                 if result.kill {
+                    if self.advance(Ending::Scene, context)? {
+                        continue 'scene;
+                    }
                     return Ok(());
                 }
-                events_to_process.extend(result.events);
+                if layout::take_changed() {
+                    self.mark_all_dirty();
+                }
             }
-            self.execute_entity_events(&mut events_to_process);
-            if event::poll(self.poll_interval)? {
-                let event = event::read()?;
-                for entity in self.entities.iter_mut() {
-                    let acted = entity.handle_event(EventContext { event: &event });
-                    if acted {
-                        let result = Self::update_and_draw_entity(entity, context)?;
-                        if result.kill {
-                            return Ok(());
-                        }
-                        events_to_process.extend(result.events);
+            if let Some(ending) = self.process_messages(context)? {
+                if self.advance(ending, context)? {
+                    continue;
+                }
+                return Ok(());
+            }
+            let mut timeout = self.poll_timeout();
+            if context.raw_mode.is_some() {
+                timeout = timeout.min(RAW_MODE_PROBE_INTERVAL);
+            }
+            if events.poll(timeout)? {
+                let event = events.read()?;
+                if matches!(event, Event::Resize(..)) && !self.plain {
+                    execute!(context.out, Clear(ClearType::All))?;
+                    self.mark_all_dirty();
+                }
+                // Only some terminals report releases, no entity wants them
+                if let Event::Key(KeyEvent {
+                    kind: KeyEventKind::Release,
+                    ..
+                }) = event
+                {
+                    continue;
+                }
+                let mut ended = false;
+                for slot in self.scene.entities.iter_mut().filter(|slot| !slot.removed) {
+                    let acted = slot.entity.handle_event(EventContext { event: &event });
+                    if acted && Self::update_and_draw_entity(slot, context)?.kill {
+                        ended = true;
+                        break;
                     }
                 }
-                self.execute_entity_events(&mut events_to_process);
+                if ended && !self.advance(Ending::Scene, context)? {
+                    return Ok(());
+                }
             }
         }
     }
 
     pub fn execute(&mut self) -> anyhow::Result<()> {
-        let mut context = DrawContext::try_new()?;
+        let context = DrawContext::try_new()?;
 
-        self.work_loop(&mut context)?;
+        self.run_on_terminal(context)
+    }
 
-        Ok(())
+    /// Run on the real terminal, with no input left over before or after:
+    /// nothing typed before the lock counts, nothing typed at it reaches the
+    /// shell.
+    fn run_on_terminal(&mut self, mut context: DrawContext) -> anyhow::Result<()> {
+        let mut events = TerminalEvents;
+        events.discard_pending()?;
+
+        let result = self.run_with(&mut events, &mut context);
+
+        let leftover = events.discard_pending()?;
+        if leftover > 0 {
+            warn!(leftover, "discarded input left after the lock");
+        }
+        // Leaves raw mode and flushes what the terminal still queued
+        drop(context);
+        result
+    }
+
+    /// Update and draw every entity once as if the terminal was `width` by
+    /// `height`, and return the resulting screen as text.
+    pub fn render_to_string(&mut self, width: u16, height: u16) -> anyhow::Result<String> {
+        layout::set_size_override(Some((width, height)));
+        let buffer = SharedBuffer::default();
+        let mut context = DrawContext::with_writer(buffer.clone());
+        let mut rendered = Ok(());
+        // Rows move as entities report their heights, settle before capturing
+        for _ in 0..3 {
+            layout::take_changed();
+            for slot in self.scene.entities.iter_mut() {
+                if let Err(error) = Self::update_and_draw_entity(slot, &mut context) {
+                    rendered = Err(error);
+                }
+            }
+            if !layout::take_changed() {
+                break;
+            }
+        }
+        layout::set_size_override(None);
+        rendered?;
+
+        let mut screen = ScreenBuffer::new(width, height);
+        screen.feed(&buffer.bytes());
+        Ok(screen.contents())
+    }
+
+    /// Like `execute`, drawing into `out` instead of stdout.
+    pub fn execute_on(&mut self, out: impl Write + 'static) -> anyhow::Result<()> {
+        let context = DrawContext::on_terminal(out)?;
+
+        self.run_on_terminal(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::*;
+    use crate::{
+        lock_screen_builder::{Auth, LockScreenBuilder},
+        password_prompt_entity::{Credential, USER_CREDENTIAL},
+        password_source::Secret,
+        testing::{self, key, FakeDrawContext, ScriptedEvents},
+    };
+
+    #[test]
+    fn keys_typed_after_the_unlock_are_discarded() {
+        let _guard = testing::exclusive();
+        let credentials = vec![Credential::new(USER_CREDENTIAL, Secret::plain("hunter2"))];
+        let mut controller = LockScreenBuilder::new(Duration::from_secs(60))
+            .auth(Auth::Password(credentials))
+            .attempt_delay(Duration::ZERO)
+            .build()
+            .unwrap();
+        let screen = FakeDrawContext::new();
+        // Typed on into the unlock, this would otherwise run in the shell
+        let mut events = ScriptedEvents::typing("hunter2");
+        for code in [KeyCode::Char('l'), KeyCode::Char('s'), KeyCode::Enter] {
+            events.push(key(code));
+        }
+        controller
+            .run_with(&mut events, &mut screen.context())
+            .unwrap();
+        assert_eq!(events.leftover(), 3);
+        assert_eq!(events.discard_pending().unwrap(), 3);
+        assert_eq!(events.leftover(), 0);
     }
 }
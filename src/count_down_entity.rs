@@ -1,34 +1,534 @@
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
+use clap::ValueEnum;
 use crossterm::{
     cursor::MoveTo,
+    event::Event,
     execute,
-    style::{Color, Print, ResetColor, SetForegroundColor},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
     terminal::{Clear, ClearType},
 };
+use tracing::{info, warn};
 
 use crate::{
-    controller::{DrawContext, UpdateResult},
-    entity::{Entity, Named},
-    COUNTDOWN_Y,
+    commands::Command,
+    controller::{ControlEvent, DrawContext, EventContext, Recipient, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n,
+    layout::COUNTDOWN_Y,
+    power, session, theme,
 };
 
+pub const EXTEND_STEP: Duration = Duration::from_secs(5 * 60);
+
+/// Time until the whole seconds of `remaining` tick over.
+fn until_next_second(remaining: Duration) -> Duration {
+    Duration::from_nanos(remaining.subsec_nanos() as u64) + Duration::from_millis(1)
+}
+
+/// Time until the tenths of a second of `remaining` tick over.
+fn until_next_tenth(remaining: Duration) -> Duration {
+    Duration::from_nanos((remaining.subsec_nanos() % 100_000_000) as u64) + Duration::from_millis(1)
+}
+
+/// Below this the countdown shows tenths with `--countdown-tenths final`.
+const FINAL_SECONDS: Duration = Duration::from_secs(10);
+
+/// Who or what asked the countdown to pause, resume or extend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    Ipc,
+    Schedule,
+    Mqtt,
+    /// A one-time unlock link was opened
+    Link,
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Trigger::Ipc => "ipc",
+            Trigger::Schedule => "schedule",
+            Trigger::Mqtt => "mqtt",
+            Trigger::Link => "link",
+        })
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ipc" => Ok(Trigger::Ipc),
+            "schedule" => Ok(Trigger::Schedule),
+            "mqtt" => Ok(Trigger::Mqtt),
+            "link" => Ok(Trigger::Link),
+            other => Err(format!("Unknown trigger '{other}'")),
+        }
+    }
+}
+
+/// A point of the countdown worth announcing, parsed from `50%` (that share
+/// of the lock left) or a duration left such as `1m`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Milestone {
+    Percent(u32),
+    Left(Duration),
+}
+
+impl Milestone {
+    fn threshold(&self, total: Duration) -> Duration {
+        match self {
+            Milestone::Percent(percent) => total * *percent / 100,
+            Milestone::Left(left) => *left,
+        }
+    }
+
+    pub fn announcement(&self) -> String {
+        match self {
+            Milestone::Percent(percent) => i18n::tr("milestone.percent", &[("percent", percent)]),
+            Milestone::Left(_) => i18n::tr("milestone.left", &[("left", self)]),
+        }
+    }
+}
+
+impl Display for Milestone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Milestone::Percent(percent) => write!(f, "{percent}%"),
+            Milestone::Left(left) => {
+                let secs = left.as_secs();
+                if secs >= 3600 && secs % 3600 == 0 {
+                    write!(f, "{}h", secs / 3600)
+                } else if secs >= 60 && secs % 60 == 0 {
+                    write!(f, "{}m", secs / 60)
+                } else {
+                    write!(f, "{secs}s")
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Milestone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().strip_suffix('%') {
+            Some(percent) => percent
+                .parse::<u32>()
+                .ok()
+                .filter(|percent| (1..100).contains(percent))
+                .map(Milestone::Percent)
+                .ok_or_else(|| format!("Invalid percentage '{s}'")),
+            None => crate::parse_duration(s.trim()).map(Milestone::Left),
+        }
+    }
+}
+
+/// What happens when the countdown runs out, parsed from `unlock`, `lock`,
+/// `suspend`, `hibernate`, `shutdown` or `command:<command>`. The machine is
+/// suspended or powered off with the lock still on, so it wakes up locked,
+/// see `power`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OnExpire {
+    /// Unlock, after the grace period if there is one
+    #[default]
+    Unlock,
+    /// Stay locked until unlocked some other way, e.g. with the password
+    Lock,
+    /// Suspend and stay locked
+    Suspend,
+    /// Hibernate and stay locked
+    Hibernate,
+    /// Power the machine off, staying locked in case that fails
+    Shutdown,
+    /// Unlock and run this shell command
+    Command(String),
+}
+
+impl OnExpire {
+    /// Whether the lock stays on once the countdown ran out.
+    pub fn keeps_lock(&self) -> bool {
+        matches!(
+            self,
+            OnExpire::Lock | OnExpire::Suspend | OnExpire::Hibernate | OnExpire::Shutdown
+        )
+    }
+}
+
+impl Display for OnExpire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnExpire::Unlock => write!(f, "unlock"),
+            OnExpire::Lock => write!(f, "lock"),
+            OnExpire::Suspend => write!(f, "suspend"),
+            OnExpire::Hibernate => write!(f, "hibernate"),
+            OnExpire::Shutdown => write!(f, "shutdown"),
+            OnExpire::Command(command) => write!(f, "command:{command}"),
+        }
+    }
+}
+
+impl FromStr for OnExpire {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "unlock" => OnExpire::Unlock,
+            "lock" => OnExpire::Lock,
+            "suspend" => OnExpire::Suspend,
+            "hibernate" => OnExpire::Hibernate,
+            "shutdown" => OnExpire::Shutdown,
+            _ => match s
+                .strip_prefix("command:")
+                .filter(|command| !command.is_empty())
+            {
+                Some(command) => OnExpire::Command(command.to_string()),
+                None => return Err(format!("Invalid action '{s}'")),
+            },
+        })
+    }
+}
+
+/// How the time left is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum CountdownFormat {
+    /// `MM:SS`, `HH:MM:SS` from an hour on
+    #[default]
+    Clock,
+    /// Words, e.g. "1 h 23 m left"
+    Human,
+    /// Seconds only, e.g. "5400 s"
+    Seconds,
+}
+
+impl CountdownFormat {
+    /// `remaining` in this format, with a tenth of a second after the seconds
+    /// if `tenths`.
+    fn format(self, remaining: Duration, tenths: bool) -> String {
+        let secs = remaining.as_secs();
+        let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+        let tenth = if tenths {
+            format!(".{}", remaining.subsec_millis() / 100)
+        } else {
+            String::new()
+        };
+        match self {
+            CountdownFormat::Clock if hours > 0 => {
+                format!("{hours:02}:{minutes:02}:{seconds:02}{tenth}")
+            }
+            CountdownFormat::Clock => format!("{minutes:02}:{seconds:02}{tenth}"),
+            CountdownFormat::Human => {
+                let time = if hours > 0 {
+                    format!("{hours} h {minutes} m")
+                } else if minutes > 0 {
+                    format!("{minutes} m {seconds}{tenth} s")
+                } else {
+                    format!("{seconds}{tenth} s")
+                };
+                i18n::tr("countdown.left", &[("time", &time)])
+            }
+            CountdownFormat::Seconds => format!("{secs}{tenth} s"),
+        }
+    }
+}
+
+/// When the countdown shows tenths of a second, redrawing ten times a
+/// second while it does. Meant for short locks, e.g. demos.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum CountdownTenths {
+    #[default]
+    Never,
+    /// During the last ten seconds
+    Final,
+    Always,
+}
+
+impl CountdownTenths {
+    fn shown(self, remaining: Duration) -> bool {
+        match self {
+            CountdownTenths::Never => false,
+            CountdownTenths::Final => remaining < FINAL_SECONDS,
+            CountdownTenths::Always => true,
+        }
+    }
+}
+
+/// Where the countdown turns from green to yellow, to red and where it starts
+/// blinking, parsed from three comma separated milestones like `50%,20%,1m`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorThresholds {
+    yellow: Milestone,
+    red: Milestone,
+    blink: Milestone,
+}
+
+impl Default for ColorThresholds {
+    fn default() -> Self {
+        ColorThresholds {
+            yellow: Milestone::Percent(50),
+            red: Milestone::Percent(20),
+            blink: Milestone::Left(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl FromStr for ColorThresholds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let thresholds = s
+            .split(',')
+            .map(str::parse::<Milestone>)
+            .collect::<Result<Vec<_>, _>>()?;
+        let [yellow, red, blink] = thresholds[..] else {
+            return Err(format!("Expected yellow,red,blink thresholds, got '{s}'"));
+        };
+        Ok(ColorThresholds { yellow, red, blink })
+    }
+}
+
+impl ColorThresholds {
+    /// The countdown color and whether it blinks with `remaining` of `total`.
+    fn style(&self, remaining: Duration, total: Duration) -> (Color, bool) {
+        let theme = theme::current();
+        let color = if remaining <= self.red.threshold(total) {
+            theme.error
+        } else if remaining <= self.yellow.threshold(total) {
+            theme.warning
+        } else {
+            theme.ok
+        };
+        (color, remaining <= self.blink.threshold(total))
+    }
+}
+
 pub struct CountDownEntity {
     id: String,
+    initial: Duration,
     total: Duration,
     start: Instant,
+    grace: Option<Duration>,
+    relock_after_grace: bool,
+    grace_started: Option<Instant>,
+    acknowledged: bool,
+    on_expire: OnExpire,
+    /// Ran out while `on_expire` keeps the lock on
+    expired: bool,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+    milestones: Vec<Milestone>,
+    /// Which milestones the remaining time is already past, `None` until the
+    /// first update so those passed at start are not announced
+    passed: Option<Vec<bool>>,
+    colors: ColorThresholds,
+    format: CountdownFormat,
+    tenths: CountdownTenths,
+    /// Show that the lock is on but not how long it has left
+    hidden: bool,
+    color: Color,
+    blink: bool,
     print_text: String,
+    next_tick: Duration,
+    properties: HashMap<String, String>,
 }
 
 impl CountDownEntity {
     pub fn new(id: &str, total: Duration) -> Self {
         CountDownEntity {
             id: format!("CountDownEntity-{id}"),
+            initial: total,
             total,
             start: std::time::Instant::now(),
+            grace: None,
+            relock_after_grace: false,
+            grace_started: None,
+            acknowledged: false,
+            on_expire: OnExpire::Unlock,
+            expired: false,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            milestones: Vec::new(),
+            passed: None,
+            colors: ColorThresholds::default(),
+            format: CountdownFormat::default(),
+            tenths: CountdownTenths::default(),
+            hidden: false,
+            color: theme::current().ok,
+            blink: false,
             print_text: String::new(),
+            next_tick: Duration::ZERO,
+            properties: HashMap::new(),
         }
     }
+
+    /// Instead of unlocking as soon as the countdown expires, wait up to `grace`
+    /// for a key press. If nobody shows up the lock either ends or, with
+    /// `relock`, starts over.
+    pub fn set_grace(&mut self, grace: Duration, relock: bool) {
+        self.grace = Some(grace);
+        self.relock_after_grace = relock;
+    }
+
+    /// What to do once the time is up. Anything but `Unlock` skips the grace
+    /// period.
+    pub fn set_on_expire(&mut self, on_expire: OnExpire) {
+        self.on_expire = on_expire;
+    }
+
+    pub fn set_color_thresholds(&mut self, colors: ColorThresholds) {
+        self.colors = colors;
+    }
+
+    pub fn set_format(&mut self, format: CountdownFormat) {
+        self.format = format;
+    }
+
+    pub fn set_tenths(&mut self, tenths: CountdownTenths) {
+        self.tenths = tenths;
+    }
+
+    /// Draw a static "locked" indicator instead of the time left. The lock
+    /// expires all the same.
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    /// Report reaching each of `milestones` to the controller as a
+    /// `milestone` event.
+    pub fn set_milestones(&mut self, milestones: Vec<Milestone>) {
+        self.milestones = milestones;
+        self.passed = None;
+    }
+
+    /// Events for the milestones `remaining` just got past. Extending the
+    /// lock re-arms those it moves back ahead of.
+    fn milestone_events(&mut self, remaining: Duration) -> Vec<ControlEvent> {
+        let passed: Vec<bool> = self
+            .milestones
+            .iter()
+            .map(|milestone| remaining <= milestone.threshold(self.total))
+            .collect();
+        let previous = self.passed.replace(passed.clone());
+        let Some(previous) = previous else {
+            return Vec::new();
+        };
+        self.milestones
+            .iter()
+            .zip(passed.iter().zip(previous))
+            .filter(|(_, (now, before))| **now && !before)
+            .map(|(milestone, _)| {
+                info!(%milestone, "countdown milestone");
+                ControlEvent {
+                    recipient: Recipient::Controller,
+                    property_key: "milestone".to_string(),
+                    property_value: milestone.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn restart(&mut self) {
+        self.total = self.initial;
+        self.start = Instant::now();
+        self.paused_at = None;
+        self.paused_total = Duration::ZERO;
+        self.grace_started = None;
+        self.acknowledged = false;
+        self.passed = None;
+    }
+
+    fn update_grace(&mut self, grace: Duration, started: Instant) -> UpdateResult {
+        if self.acknowledged {
+            info!("grace period acknowledged");
+            session::set_exit_reason("grace_acknowledged");
+            return UpdateResult::kill();
+        }
+        let left = grace.saturating_sub(started.elapsed());
+        if left.is_zero() {
+            if self.relock_after_grace {
+                info!("grace period expired untouched, restarting lock");
+                self.restart();
+                return UpdateResult::nop();
+            }
+            info!("grace period expired");
+            session::set_exit_reason("grace_expired");
+            return UpdateResult::kill();
+        }
+        self.print_text = i18n::tr("countdown.grace", &[("secs", &(left.as_secs() + 1))]);
+        self.next_tick = until_next_second(left);
+        UpdateResult::nop()
+    }
+
+    /// The time is up: stay locked, or end the lock right away or after the
+    /// grace period. Suspending and the like start here, with the lock on.
+    fn expire(&mut self, context: UpdateContext) -> UpdateResult {
+        match self.on_expire {
+            OnExpire::Unlock if self.grace.is_some() => {
+                info!("countdown expired");
+                self.grace_started = Some(Instant::now());
+                self.update(context)
+            }
+            _ if self.on_expire.keeps_lock() => {
+                if !self.expired {
+                    info!(on_expire = %self.on_expire, "countdown expired, staying locked");
+                    self.expired = true;
+                    power::on_expired(&self.on_expire);
+                }
+                self.print_text = i18n::t("countdown.expired").to_string();
+                (self.color, self.blink) = (theme::current().error, false);
+                self.next_tick = Duration::from_secs(1);
+                UpdateResult::nop()
+            }
+            _ => {
+                info!(on_expire = %self.on_expire, "countdown expired");
+                power::on_expired(&self.on_expire);
+                session::set_exit_reason("countdown");
+                UpdateResult::kill()
+            }
+        }
+    }
+
+    pub fn pause(&mut self, trigger: Trigger) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+            info!(%trigger, "countdown paused");
+        }
+    }
+
+    pub fn resume(&mut self, trigger: Trigger) {
+        if let Some(paused_at) = self.paused_at.take() {
+            let paused = paused_at.elapsed();
+            self.paused_total += paused;
+            session::update_stats(|stats| stats.paused += paused);
+            info!(%trigger, ?paused, "countdown resumed");
+        }
+    }
+
+    pub fn extend(&mut self, by: Duration, trigger: Trigger) {
+        self.total += by;
+        session::update_stats(|stats| stats.extended += by);
+        info!(%trigger, extended_by = ?by, "countdown extended");
+    }
+
+    /// Continue a lock that already ran for `elapsed`, e.g. one restored
+    /// after a crash.
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.start = Instant::now().checked_sub(elapsed).unwrap_or(self.start);
+    }
+
+    fn remaining(&self) -> Duration {
+        let paused = self.paused_total + self.paused_at.map(|t| t.elapsed()).unwrap_or_default();
+        let elapsed = self.start.elapsed().saturating_sub(paused);
+        self.total.saturating_sub(elapsed)
+    }
 }
 
 impl Named for CountDownEntity {
@@ -37,45 +537,206 @@ impl Named for CountDownEntity {
     }
 }
 
+impl HasProperties for CountDownEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(|s| s.as_str())
+    }
+
+    /// Besides plain properties this accepts the commands `pause` and `resume`
+    /// (value: trigger) and `extend` (value: `<seconds>:<trigger>`), and
+    /// `format` (value: `clock`, `human` or `seconds`).
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "format" => {
+                let Ok(format) = <CountdownFormat as ValueEnum>::from_str(value, true) else {
+                    warn!(value, "ignoring unknown countdown format");
+                    return false;
+                };
+                self.set_format(format);
+                true
+            }
+            "pause" | "resume" => {
+                let Ok(trigger) = value.parse::<Trigger>() else {
+                    warn!(value, "ignoring {key} with unknown trigger");
+                    return false;
+                };
+                if key == "pause" {
+                    self.pause(trigger);
+                } else {
+                    self.resume(trigger);
+                }
+                true
+            }
+            "extend" => {
+                let parsed = value.split_once(':').and_then(|(secs, trigger)| {
+                    Some((secs.parse::<u64>().ok()?, trigger.parse::<Trigger>().ok()?))
+                });
+                let Some((secs, trigger)) = parsed else {
+                    warn!(value, "ignoring malformed extend");
+                    return false;
+                };
+                self.extend(Duration::from_secs(secs), trigger);
+                true
+            }
+            _ => {
+                self.properties.insert(key.to_string(), value.to_string());
+                true
+            }
+        }
+    }
+}
+
+impl FullEntity for CountDownEntity {}
+
 impl Entity for CountDownEntity {
     fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (color, blink) = if self.grace_started.is_some() {
+            (theme::current().ok, false)
+        } else {
+            (self.color, self.blink)
+        };
         execute!(
             draw_context.out,
             MoveTo(0, COUNTDOWN_Y),
             Clear(ClearType::CurrentLine),
-            SetForegroundColor(Color::Red),
+            SetForegroundColor(color),
+            SetAttribute(if blink {
+                Attribute::SlowBlink
+            } else {
+                Attribute::NoBlink
+            }),
             Print(&self.print_text),
+            SetAttribute(Attribute::NoBlink),
             ResetColor,
         )?;
         Ok(())
     }
 
-    fn update(&mut self) -> UpdateResult {
-        let elapsed = self.start.elapsed();
-        let remaining = if elapsed >= self.total {
-            Duration::from_secs(0)
-        } else {
-            self.total - elapsed
-        };
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        if let (Some(grace), Some(started)) = (self.grace, self.grace_started) {
+            return self.update_grace(grace, started);
+        }
+
+        let remaining = self.remaining();
+        session::update_stats(|stats| {
+            stats.total = self.total;
+            stats.remaining = remaining;
+        });
 
-        let secs = remaining.as_secs();
         let over = remaining.as_secs() <= 0;
 
-        if secs >= 3600 {
-            let hours = secs / 3600;
-            let minutes = (secs % 3600) / 60;
-            let seconds = secs % 60;
-            self.print_text = format!("{:02}:{:02}:{:02}", hours, minutes, seconds);
+        let tenths = self.tenths.shown(remaining) && !self.hidden;
+        self.print_text = if self.hidden {
+            i18n::t("countdown.hidden").to_string()
+        } else {
+            self.format.format(remaining, tenths)
+        };
+        if self.paused_at.is_some() {
+            self.print_text.push_str(i18n::t("countdown.paused"));
+        }
+        (self.color, self.blink) = if self.hidden {
+            // Colors and blinking would give the time away
+            (theme::current().muted, false)
+        } else {
+            self.colors.style(remaining, self.total)
+        };
+        self.next_tick = if tenths {
+            until_next_tenth(remaining)
         } else {
-            let minutes = secs / 60;
-            let seconds = secs % 60;
-            self.print_text = format!("{:02}:{:02}", minutes, seconds);
+            until_next_second(remaining)
+        };
+        for event in self.milestone_events(remaining) {
+            context.emit(event);
         }
 
         if over {
-            UpdateResult::kill()
+            self.expire(context)
         } else {
+            self.expired = false;
             UpdateResult::nop()
         }
     }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        if self.grace_started.is_some() {
+            if matches!(event.event, Event::Key(_)) {
+                self.acknowledged = true;
+            }
+            return self.acknowledged;
+        }
+        false
+    }
+
+    fn handle_command(&mut self, command: Command, trigger: Trigger) -> bool {
+        match command {
+            Command::Pause => self.pause(trigger),
+            Command::Resume => self.resume(trigger),
+            Command::Extend(by) => self.extend(by, trigger),
+            Command::Lock(_) | Command::Unlock | Command::Delay(_) => return false,
+        }
+        true
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(self.next_tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestones_parse_as_percentages_or_time_left() {
+        assert_eq!("25%".parse(), Ok(Milestone::Percent(25)));
+        assert_eq!(" 99% ".parse(), Ok(Milestone::Percent(99)));
+        assert_eq!(
+            "5m".parse(),
+            Ok(Milestone::Left(Duration::from_secs(5 * 60)))
+        );
+        assert_eq!("30s".parse(), Ok(Milestone::Left(Duration::from_secs(30))));
+        for milestone in ["0%", "100%", "-5%", "half%", "%", "5", "soon", ""] {
+            assert!(milestone.parse::<Milestone>().is_err(), "{milestone:?}");
+        }
+    }
+
+    #[test]
+    fn color_thresholds_are_three_milestones() {
+        assert_eq!("50%,20%,1m".parse(), Ok(ColorThresholds::default()));
+        assert_eq!(
+            "75%, 10m, 10s".parse(),
+            Ok(ColorThresholds {
+                yellow: Milestone::Percent(75),
+                red: Milestone::Left(Duration::from_secs(10 * 60)),
+                blink: Milestone::Left(Duration::from_secs(10)),
+            })
+        );
+        for thresholds in ["50%,20%", "50%,20%,1m,10s", "50%,twenty,1m", ""] {
+            assert!(
+                thresholds.parse::<ColorThresholds>().is_err(),
+                "{thresholds:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn expiry_actions_parse() {
+        for action in [
+            OnExpire::Unlock,
+            OnExpire::Lock,
+            OnExpire::Suspend,
+            OnExpire::Hibernate,
+            OnExpire::Shutdown,
+            OnExpire::Command("notify-send 'Break over'".to_string()),
+        ] {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+        assert_eq!(
+            "command:systemctl suspend".parse(),
+            Ok(OnExpire::Command("systemctl suspend".to_string()))
+        );
+        for action in ["", "Suspend", " lock", "reboot", "command:", "command"] {
+            assert!(action.parse::<OnExpire>().is_err(), "{action:?}");
+        }
+    }
 }
@@ -1,7 +1,28 @@
-use crate::controller::{DrawContext, EventContext, UpdateResult};
+use std::time::Duration;
+
+use crate::{
+    commands::Command,
+    controller::{DrawContext, EntityId, EventContext, UpdateContext, UpdateResult},
+    count_down_entity::Trigger,
+};
+
+/// How often an entity wants the controller to update and redraw it. Entities
+/// are always updated after handling an event or a property change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cadence {
+    EveryTick,
+    Every(Duration),
+    OnChange,
+}
 
 pub trait Named {
     fn get_name(&self) -> &str;
+
+    /// The id the entity picked for itself, for entities whose threads publish
+    /// to it before it is added. The controller assigns one otherwise.
+    fn entity_id(&self) -> Option<EntityId> {
+        None
+    }
 }
 
 pub trait HasProperties {
@@ -23,12 +44,19 @@ pub trait Visible: HasProperties {
 
 pub trait Entity {
     fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()>;
-    fn update(&mut self) -> UpdateResult {
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
         UpdateResult::nop()
     }
     fn handle_event(&mut self, _: EventContext) -> bool {
         false
     }
+    /// React to a command from outside the UI. Returns true if it was acted on.
+    fn handle_command(&mut self, _: Command, _: Trigger) -> bool {
+        false
+    }
+    fn cadence(&self) -> Cadence {
+        Cadence::EveryTick
+    }
 }
 
 pub trait FullEntity: Entity + Named + HasProperties {}
@@ -0,0 +1,424 @@
+//! Input devices under /dev/input, grabbed directly with `EVIOCGRAB` so
+//! nothing else sees their events. Needs root or the `input` group, like any
+//! grab on Linux. Linux only.
+//!
+//! `--grab-backend evdev` grabs every keyboard and mouse this way while
+//! locked and passes what it lets through on to a uinput device of its own,
+//! which works the same under X11, Wayland and on the console.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    mem,
+    os::{
+        fd::AsRawFd,
+        unix::fs::{MetadataExt, OpenOptionsExt},
+    },
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use tracing::{info, warn};
+
+const INPUT_DIR: &str = "/dev/input";
+const UINPUT: &str = "/dev/uinput";
+/// What our own uinput device is called, so it isn't grabbed in turn
+const VIRTUAL_NAME: &str = "screenlock virtual input";
+/// How often new devices are looked for
+const HOTPLUG_INTERVAL: Duration = Duration::from_millis(250);
+/// How long a device may keep keys held down before it is grabbed anyway
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// From linux/input.h and linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_MSC: u16 = 0x04;
+/// Mouse, joystick and other buttons, as opposed to keys
+const BUTTONS: std::ops::Range<u16> = 0x100..0x160;
+const KEY_MAX: u16 = 0x2ff;
+const REL_MAX: u16 = 0x0f;
+const MSC_SCAN: u16 = 0x04;
+const BUS_VIRTUAL: u16 = 0x06;
+const EVIOCGRAB: libc::c_ulong = 0x4004_4590;
+
+// From linux/uinput.h
+const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+const UI_DEV_SETUP: libc::c_ulong = 0x405c_5503;
+const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565;
+const UI_SET_RELBIT: libc::c_ulong = 0x4004_5566;
+const UI_SET_MSCBIT: libc::c_ulong = 0x4004_5568;
+
+/// `_IOC(_IOC_READ, 'E', nr, len)`
+fn eviocg(nr: libc::c_ulong, len: usize) -> libc::c_ulong {
+    0x8000_0000 | ((len as libc::c_ulong) << 16) | 0x4500 | nr
+}
+
+fn ioctl(file: &File, request: libc::c_ulong, arg: libc::c_ulong) -> io::Result<()> {
+    // SAFETY: every request used here takes an int by value
+    if unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Ask for `buf.len()` bytes of the device state `nr`.
+fn ioctl_read(file: &File, nr: libc::c_ulong, buf: &mut [u8]) -> io::Result<()> {
+    // SAFETY: the kernel writes at most `buf.len()` bytes, which the
+    // request encodes
+    let result = unsafe {
+        libc::ioctl(
+            file.as_raw_fd(),
+            eviocg(nr, buf.len()) as _,
+            buf.as_mut_ptr(),
+        )
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// An event device and the inode of its node, which a replugged device gets
+/// a new one of even if it reuses the name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Device {
+    pub path: PathBuf,
+    ino: u64,
+}
+
+/// The event devices there are right now.
+pub fn devices() -> HashSet<Device> {
+    let Ok(entries) = fs::read_dir(INPUT_DIR) else {
+        return HashSet::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("event"))
+        .filter_map(|entry| {
+            let ino = entry.metadata().ok()?.ino();
+            Some(Device {
+                path: entry.path(),
+                ino,
+            })
+        })
+        .collect()
+}
+
+/// A device only we get events from, until dropped.
+pub struct Grabbed {
+    pub device: Device,
+    file: File,
+}
+
+impl Drop for Grabbed {
+    fn drop(&mut self) {
+        // Closing would release it too, this just makes it explicit
+        let _ = ioctl(&self.file, EVIOCGRAB, 0);
+    }
+}
+
+fn open(device: &Device) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(&device.path)
+}
+
+pub fn grab(device: &Device) -> io::Result<Grabbed> {
+    let file = open(device)?;
+    ioctl(&file, EVIOCGRAB, 1)?;
+    Ok(Grabbed {
+        device: device.clone(),
+        file,
+    })
+}
+
+/// Grab devices plugged in after `known` was taken for as long as `active`
+/// says so, so a second keyboard can't get around a grab that only covered
+/// the devices there were when it started. Their input is dropped.
+pub fn guard_new_devices(known: HashSet<Device>, active: fn() -> bool) {
+    thread::spawn(move || {
+        let mut grabbed: Vec<Grabbed> = Vec::new();
+        // Not warned about again on every round
+        let mut failed: HashSet<Device> = HashSet::new();
+        loop {
+            thread::sleep(HOTPLUG_INTERVAL);
+            if !active() {
+                grabbed.clear();
+                failed.clear();
+                continue;
+            }
+            let present = devices();
+            grabbed.retain(|grab| present.contains(&grab.device));
+            for device in present.difference(&known) {
+                if failed.contains(device) || grabbed.iter().any(|grab| grab.device == *device) {
+                    continue;
+                }
+                match grab(device) {
+                    Ok(grab) => {
+                        info!(device = %device.path.display(), "blocked new input device");
+                        grabbed.push(grab);
+                    }
+                    Err(error) => {
+                        warn!(
+                            device = %device.path.display(),
+                            %error,
+                            "could not block new input device"
+                        );
+                        failed.insert(device.clone());
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// One event as read from a device, without its timestamp.
+#[derive(Clone, Copy, Debug)]
+pub struct InputEvent {
+    pub kind: u16,
+    pub code: u16,
+    /// For keys 1 is a press, 2 a repeat and 0 a release
+    pub value: i32,
+}
+
+impl InputEvent {
+    /// The key `code` if this is a key being pressed or repeated.
+    pub fn pressed(&self) -> Option<u16> {
+        (self.kind == EV_KEY && self.value != 0).then_some(self.code)
+    }
+
+    pub fn released(&self) -> Option<u16> {
+        (self.kind == EV_KEY && self.value == 0).then_some(self.code)
+    }
+
+    /// Whether this is a button being pressed, not repeats.
+    pub fn clicked(&self) -> bool {
+        self.kind == EV_KEY && self.value == 1 && BUTTONS.contains(&self.code)
+    }
+
+    /// Whether this is a key being pressed, not repeats.
+    pub fn typed(&self) -> bool {
+        self.kind == EV_KEY && self.value == 1 && !BUTTONS.contains(&self.code)
+    }
+
+    /// Whether this is the pointer or a wheel moving.
+    pub fn moved(&self) -> bool {
+        self.kind == EV_REL
+    }
+
+    /// Read off a `struct input_event`, whose timestamp in front differs in
+    /// size between 32 and 64 bit.
+    fn parse(raw: &[u8]) -> Self {
+        let tail = &raw[raw.len() - 8..];
+        InputEvent {
+            kind: u16::from_ne_bytes([tail[0], tail[1]]),
+            code: u16::from_ne_bytes([tail[2], tail[3]]),
+            value: i32::from_ne_bytes([tail[4], tail[5], tail[6], tail[7]]),
+        }
+    }
+}
+
+/// `struct uinput_setup`
+#[repr(C)]
+struct UinputSetup {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+/// The uinput device what gets through is replayed on.
+struct VirtualDevice(File);
+
+impl VirtualDevice {
+    fn create() -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT)
+            .with_context(|| format!("could not open {UINPUT}"))?;
+        for kind in [EV_SYN, EV_KEY, EV_REL, EV_MSC] {
+            ioctl(&file, UI_SET_EVBIT, kind.into())?;
+        }
+        for key in 0..=KEY_MAX {
+            ioctl(&file, UI_SET_KEYBIT, key.into())?;
+        }
+        for axis in 0..=REL_MAX {
+            ioctl(&file, UI_SET_RELBIT, axis.into())?;
+        }
+        ioctl(&file, UI_SET_MSCBIT, MSC_SCAN.into())?;
+
+        let mut setup = UinputSetup {
+            bustype: BUS_VIRTUAL,
+            vendor: 0,
+            product: 0,
+            version: 1,
+            name: [0; 80],
+            ff_effects_max: 0,
+        };
+        setup.name[..VIRTUAL_NAME.len()].copy_from_slice(VIRTUAL_NAME.as_bytes());
+        // SAFETY: UI_DEV_SETUP reads one `struct uinput_setup`, which
+        // `UinputSetup` lays out
+        if unsafe { libc::ioctl(file.as_raw_fd(), UI_DEV_SETUP as _, &setup) } < 0 {
+            return Err(io::Error::last_os_error()).context("could not set up the uinput device");
+        }
+        ioctl(&file, UI_DEV_CREATE, 0).context("could not create the uinput device")?;
+        Ok(VirtualDevice(file))
+    }
+}
+
+impl Drop for VirtualDevice {
+    fn drop(&mut self) {
+        let _ = ioctl(&self.0, UI_DEV_DESTROY, 0);
+    }
+}
+
+/// Whether `file` is a keyboard or mouse, and not our own device.
+fn wanted(file: &File) -> bool {
+    let mut kinds = [0u8; 4];
+    let mut name = [0u8; 80];
+    ioctl_read(file, 0x20, &mut kinds).is_ok()
+        && kinds[0] & (1 << EV_KEY) != 0
+        && ioctl_read(file, 0x06, &mut name).is_ok()
+        && !name.starts_with(VIRTUAL_NAME.as_bytes())
+}
+
+/// Wait for the keys held on `file` to be let go, so the grab doesn't
+/// swallow a release and leave the key stuck for everyone else.
+fn wait_for_release(file: &File) {
+    let started = Instant::now();
+    let mut keys = [0u8; KEY_MAX as usize / 8 + 1];
+    while started.elapsed() < RELEASE_TIMEOUT
+        && ioctl_read(file, 0x18, &mut keys).is_ok()
+        && keys.iter().any(|byte| *byte != 0)
+    {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// A keyboard or mouse `grab_all` reads, grabbed only while locked.
+struct Watched {
+    device: Device,
+    file: File,
+    grabbed: bool,
+}
+
+impl Watched {
+    fn set_grabbed(&mut self, grabbed: bool) -> io::Result<()> {
+        if grabbed {
+            wait_for_release(&self.file);
+        }
+        ioctl(&self.file, EVIOCGRAB, grabbed.into())?;
+        self.grabbed = grabbed;
+        Ok(())
+    }
+}
+
+/// Read every keyboard and mouse, including ones plugged in later. While
+/// `active`, asked once a round, says so they are grabbed and only the events
+/// `pass` lets through are replayed, otherwise their input goes where it
+/// would anyway and `pass` just gets to see it. Blocks for as long as that
+/// works.
+pub fn grab_all(
+    mut active: impl FnMut() -> bool,
+    mut pass: impl FnMut(&InputEvent) -> bool,
+) -> anyhow::Result<()> {
+    let mut output = VirtualDevice::create()?;
+    let mut watched: Vec<Watched> = Vec::new();
+    // Devices that aren't keyboards or mice, or couldn't be opened
+    let mut skipped: HashSet<Device> = HashSet::new();
+    let size = mem::size_of::<libc::input_event>();
+    let mut buf = vec![0u8; size * 64];
+    loop {
+        let present = devices();
+        watched.retain(|watch| present.contains(&watch.device));
+        skipped.retain(|device| present.contains(device));
+        for device in &present {
+            if skipped.contains(device) || watched.iter().any(|watch| watch.device == *device) {
+                continue;
+            }
+            match open(device) {
+                Ok(file) if wanted(&file) => watched.push(Watched {
+                    device: device.clone(),
+                    file,
+                    grabbed: false,
+                }),
+                Ok(_) => {
+                    skipped.insert(device.clone());
+                }
+                Err(error) => {
+                    warn!(device = %device.path.display(), %error, "could not open input device");
+                    skipped.insert(device.clone());
+                }
+            }
+        }
+        anyhow::ensure!(!watched.is_empty(), "no keyboard or mouse could be opened");
+
+        let locked = active();
+        for watch in watched.iter_mut().filter(|watch| watch.grabbed != locked) {
+            match watch.set_grabbed(locked) {
+                Ok(()) => info!(
+                    device = %watch.device.path.display(),
+                    grabbed = locked,
+                    "input device"
+                ),
+                Err(error) => warn!(
+                    device = %watch.device.path.display(),
+                    %error,
+                    "could not grab input device"
+                ),
+            }
+        }
+
+        let mut fds: Vec<libc::pollfd> = watched
+            .iter()
+            .map(|watch| libc::pollfd {
+                fd: watch.file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        // SAFETY: `fds` is a valid array of `fds.len()` pollfds
+        let ready = unsafe {
+            libc::poll(
+                fds.as_mut_ptr(),
+                fds.len() as libc::nfds_t,
+                HOTPLUG_INTERVAL.as_millis() as libc::c_int,
+            )
+        };
+        if ready < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error).context("polling input devices failed");
+        }
+        for (watch, fd) in watched.iter_mut().zip(&fds) {
+            if fd.revents == 0 {
+                continue;
+            }
+            loop {
+                let read = match watch.file.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    // Drained, or unplugged and gone in the next round
+                    Err(_) => break,
+                };
+                for raw in buf[..read].chunks_exact(size) {
+                    if pass(&InputEvent::parse(raw)) && watch.grabbed {
+                        output.0.write_all(raw)?;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Keeping an eye on the input grab. The backend beats a heartbeat while it
+//! works, and if its thread ends or it goes quiet, the grab is started again
+//! a few times before the lock gives up on it. Then it says so on screen and,
+//! with `--on-grab-lost end`, ends the session instead of going on locking
+//! only the terminal.
+//!
+//! Only the evdev backend beats without input. The rdev grab calls back on
+//! input alone, so for it just a thread that ended is noticed, not one that
+//! hangs or a grab the display server dropped.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use tracing::{error, warn};
+
+use crate::{
+    bus::{self, Message},
+    controller::Recipient,
+    session,
+};
+
+/// How often a grab that stopped is started again before giving up.
+const MAX_RESTARTS: u32 = 3;
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Set once the grab is gone for good.
+static LOST: AtomicBool = AtomicBool::new(false);
+
+/// What happens once the grab is gone for good.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnGrabLost {
+    /// Keep locking the terminal and show a warning
+    #[default]
+    Warn,
+    /// Show the warning and end the running session
+    End,
+}
+
+/// Handed to the backend, which beats it to show it still works.
+#[derive(Clone)]
+pub struct Heartbeat(Sender<()>);
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        // Nobody listening any more just means nobody cares
+        let _ = self.0.send(());
+    }
+}
+
+pub fn is_lost() -> bool {
+    LOST.load(Ordering::Relaxed)
+}
+
+/// Run `backend` on a thread of its own and start it again when it returns.
+/// With `silence` it also counts as stopped when it hasn't beaten for that
+/// long, which only makes sense for backends that beat without input too.
+/// A backend that hangs can't be started again next to itself, so silence
+/// gives up right away.
+pub fn supervise(
+    backend: fn(Heartbeat) -> anyhow::Result<()>,
+    silence: Option<Duration>,
+    on_lost: OnGrabLost,
+) {
+    thread::spawn(move || {
+        for attempt in 0..=MAX_RESTARTS {
+            if attempt > 0 {
+                warn!(attempt, "starting the input grab again");
+                thread::sleep(RESTART_DELAY);
+            }
+            let (sender, receiver) = mpsc::channel();
+            let handle = thread::spawn(move || backend(Heartbeat(sender)));
+            let beat = || match silence {
+                Some(silence) => receiver.recv_timeout(silence),
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let stopped = loop {
+                match beat() {
+                    Ok(()) => {}
+                    Err(RecvTimeoutError::Timeout) => break None,
+                    Err(RecvTimeoutError::Disconnected) => break Some(handle.join()),
+                }
+            };
+            match stopped {
+                Some(Ok(Err(error))) => error!(?error, "input grab failed"),
+                Some(_) => error!("input grab ended"),
+                None => {
+                    error!(?silence, "input grab stopped responding");
+                    break;
+                }
+            }
+        }
+        lost(on_lost);
+    });
+}
+
+fn lost(on_lost: OnGrabLost) {
+    LOST.store(true, Ordering::Relaxed);
+    // The terminal is in raw mode, the warning entity tells the user
+    error!("the input grab is gone, only the terminal is locked");
+    bus::publish_event(Recipient::Controller, "grab_failed", "lost");
+    if on_lost == OnGrabLost::End && session::stats().locked {
+        warn!("ending the session without an input grab");
+        bus::publish(Message::Quit);
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::Write;
+
+use crate::{
+    controller::DrawContext,
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout, text, theme,
+};
+use crossterm::{
+    cursor::MoveTo,
+    style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+/// A footer row that stays empty while the input grab works and turns into a
+/// full width bar in the error color once it is lost, so a lock that only
+/// covers the terminal doesn't pass for a real one. Set the `lost` property
+/// to show it.
+pub struct GrabWarningEntity {
+    id: String,
+    row: u16,
+    lost: bool,
+}
+
+impl GrabWarningEntity {
+    /// `row` is the line within the footer.
+    pub fn new(id: &str, row: u16, lost: bool) -> Self {
+        GrabWarningEntity {
+            id: format!("GrabWarningEntity-{id}"),
+            row,
+            lost,
+        }
+    }
+}
+
+impl Named for GrabWarningEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for GrabWarningEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        (key == "lost").then_some(if self.lost { "true" } else { "false" })
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != "lost" {
+            return false;
+        }
+        self.lost = value == "true";
+        true
+    }
+}
+
+impl FullEntity for GrabWarningEntity {}
+
+impl Entity for GrabWarningEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?;
+        if self.lost {
+            let (width, _) = layout::terminal_size();
+            let text = format!(" {}", i18n::t("grab.lost"));
+            let padding = (width as usize).saturating_sub(text::width(&text));
+            draw_context
+                .out
+                .queue(SetForegroundColor(theme::current().error))?
+                .queue(SetAttribute(Attribute::Reverse))?
+                .queue(SetAttribute(Attribute::Bold))?
+                .queue(Print(format!("{text}{}", " ".repeat(padding))))?
+                .queue(SetAttribute(Attribute::Reset))?
+                .queue(ResetColor)?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use chrono::{Local, NaiveTime};
+use crossterm::{
+    cursor::MoveTo,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    i18n, layout, template, text, theme,
+};
+
+/// How often the time of day is looked at again, for long locks.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Greetings from 5 am, noon, 6 pm and 10 pm unless configured otherwise.
+const DEFAULT_TEMPLATES: [(u32, &str); 4] = [
+    (5, "greeting.morning"),
+    (12, "greeting.afternoon"),
+    (18, "greeting.evening"),
+    (22, "greeting.night"),
+];
+
+/// The first name of the user's full name, or the user name if there is none.
+fn first_name() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    // The full name is the first part of the passwd comment field
+    let full_name = Command::new("getent")
+        .args(["passwd", &user])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .and_then(|output| {
+            let entry = String::from_utf8_lossy(&output.stdout).into_owned();
+            let comment = entry.split(':').nth(4)?;
+            comment.split(',').next().map(str::to_string)
+        });
+    full_name
+        .as_deref()
+        .and_then(|name| name.split_whitespace().next())
+        .map_or(user, str::to_string)
+}
+
+/// Greets whoever locked the screen by name and time of day, e.g. "Good
+/// afternoon, Alex", on a row between the banner and the title.
+pub struct GreetingEntity {
+    id: String,
+    name: String,
+    /// From which time of day each greeting applies, until the next one's
+    templates: Vec<(NaiveTime, String)>,
+    text: String,
+}
+
+impl GreetingEntity {
+    /// Greet with `templates` by the time they start from, or the built-in
+    /// ones if empty. `{name}` in them is replaced with the user's name.
+    pub fn new(id: &str, mut templates: Vec<(NaiveTime, String)>) -> Self {
+        if templates.is_empty() {
+            templates = DEFAULT_TEMPLATES
+                .into_iter()
+                .filter_map(|(hour, key)| {
+                    Some((
+                        NaiveTime::from_hms_opt(hour, 0, 0)?,
+                        i18n::t(key).to_string(),
+                    ))
+                })
+                .collect();
+        }
+        templates.sort_by_key(|(from, _)| *from);
+        GreetingEntity {
+            id: format!("GreetingEntity-{id}"),
+            name: first_name(),
+            templates,
+            text: String::new(),
+        }
+    }
+
+    /// The greeting for `now`. Before the first one starts, the last one of
+    /// the day before still applies.
+    fn template(&self, now: NaiveTime) -> &str {
+        self.templates
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= now)
+            .or(self.templates.last())
+            .map_or("", |(_, text)| text.as_str())
+    }
+}
+
+impl Named for GreetingEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for GreetingEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let y = layout::greeting_y();
+        let (width, _) = layout::terminal_size();
+        let x = text::start_column(width, text::width(&self.text), text::is_rtl(&self.text));
+        draw_context
+            .out
+            .queue(MoveTo(0, y))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(MoveTo(x, y))?
+            .queue(SetAttribute(Attribute::Bold))?
+            .queue(Print(&self.text))?
+            .queue(SetAttribute(Attribute::Reset))?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        let template = self.template(Local::now().time());
+        let text = template::expand(template).replace("{name}", &self.name);
+        self.text = theme::glyphs(&text).into_owned();
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(CHECK_INTERVAL)
+    }
+}
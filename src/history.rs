@@ -0,0 +1,132 @@
+//! Completed lock sessions, one JSON object per line, so the lock doubles as
+//! a break tracker.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::session::{InputBurst, SessionStats};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub duration_secs: u64,
+    /// What started the lock, see `SessionStats::reason`
+    pub reason: String,
+    /// Why the machine was locked, as given with `--reason`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub exit_reason: String,
+    pub failed_attempts: u32,
+    /// When there was input during the lock
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input: Vec<InputBurst>,
+}
+
+impl Record {
+    /// The record for a session that just ended.
+    pub fn finished(stats: &SessionStats) -> Self {
+        let end = Local::now();
+        let start = stats.started_at.map(DateTime::from).unwrap_or(end);
+        Record {
+            start,
+            end,
+            duration_secs: (end - start).num_seconds().max(0) as u64,
+            reason: stats.reason.clone(),
+            note: stats.note.clone(),
+            exit_reason: stats.exit_reason.clone(),
+            failed_attempts: stats.failed_attempts,
+            input: stats.input_bursts.clone(),
+        }
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("screenlock").join("history.jsonl"))
+}
+
+pub fn append(path: &Path, record: &Record) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Read all records, skipping lines that don't parse. A missing file is an
+/// empty history.
+pub fn load(path: &Path) -> anyhow::Result<Vec<Record>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    Ok(text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(error) => {
+                warn!(?error, line, "skipping malformed history line");
+                None
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub sessions_today: usize,
+    pub locked_today: Duration,
+    pub failed_attempts_today: u32,
+    /// Consecutive days with at least one break, up to today (or yesterday
+    /// if there was none yet today)
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub sessions_total: usize,
+}
+
+pub fn summarize(records: &[Record], today: NaiveDate) -> Summary {
+    let mut summary = Summary {
+        sessions_total: records.len(),
+        ..Summary::default()
+    };
+    for record in records.iter().filter(|r| r.start.date_naive() == today) {
+        summary.sessions_today += 1;
+        summary.locked_today += Duration::from_secs(record.duration_secs);
+        summary.failed_attempts_today += record.failed_attempts;
+    }
+
+    let days: BTreeSet<NaiveDate> = records.iter().map(|r| r.start.date_naive()).collect();
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in &days {
+        run = match previous {
+            Some(p) if p.checked_add_days(Days::new(1)) == Some(day) => run + 1,
+            _ => 1,
+        };
+        summary.longest_streak = summary.longest_streak.max(run);
+        previous = Some(day);
+    }
+
+    let mut day = if days.contains(&today) {
+        Some(today)
+    } else {
+        today.checked_sub_days(Days::new(1))
+    };
+    while let Some(d) = day.filter(|d| days.contains(d)) {
+        summary.current_streak += 1;
+        day = d.checked_sub_days(Days::new(1));
+    }
+    summary
+}
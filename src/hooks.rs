@@ -0,0 +1,160 @@
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{info, warn};
+
+/// Build a command running `cmd` through the platform shell.
+pub fn shell_command(cmd: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut command = Command::new("cmd");
+        command.args(["/C", cmd]);
+        command
+    }
+    #[cfg(not(windows))]
+    {
+        let mut command = Command::new("sh");
+        command.args(["-c", cmd]);
+        command
+    }
+}
+
+/// Run a user supplied hook without blocking the lock UI. The hook must not
+/// write to the terminal, so its standard streams are discarded.
+pub fn spawn(cmd: &str, env: &[(&str, String)]) {
+    let mut command = shell_command(cmd);
+    command
+        .envs(env.iter().map(|(k, v)| (*k, v.as_str())))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    match command.spawn() {
+        Ok(mut child) => {
+            let cmd = cmd.to_string();
+            thread::spawn(move || match child.wait() {
+                Ok(status) => info!(%cmd, %status, "hook finished"),
+                Err(error) => warn!(%cmd, ?error, "hook could not be awaited"),
+            });
+        }
+        Err(error) => warn!(cmd, ?error, "hook failed to start"),
+    }
+}
+
+/// Run `cmd` through the shell and return its standard output, killing it if
+/// it takes longer than `timeout`.
+pub fn output_with_timeout(cmd: &str, timeout: Duration) -> anyhow::Result<String> {
+    command_output_with_timeout(shell_command(cmd), timeout)
+}
+
+/// Like `output_with_timeout`, for a program run without the shell.
+pub fn command_output_with_timeout(
+    mut command: Command,
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    // Read while waiting, a full pipe would keep the command from exiting
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("timed out after {timeout:?}");
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let output = reader.join().unwrap_or_default();
+    if !status.success() {
+        anyhow::bail!("exited with {status}");
+    }
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn run_failed_attempt_hook(cmd: &str, attempt: u32) {
+    spawn(
+        cmd,
+        &[
+            ("SCREENLOCK_FAILED_ATTEMPTS", attempt.to_string()),
+            ("SCREENLOCK_TIMESTAMP", timestamp().to_string()),
+        ],
+    );
+}
+
+pub fn run_emergency_hook(cmd: &str, failed_attempts: &str) {
+    spawn(
+        cmd,
+        &[
+            ("SCREENLOCK_FAILED_ATTEMPTS", failed_attempts.to_string()),
+            ("SCREENLOCK_TIMESTAMP", timestamp().to_string()),
+        ],
+    );
+}
+
+pub fn run_milestone_hook(cmd: &str, milestone: &str) {
+    spawn(
+        cmd,
+        &[
+            ("SCREENLOCK_MILESTONE", milestone.to_string()),
+            ("SCREENLOCK_TIMESTAMP", timestamp().to_string()),
+        ],
+    );
+}
+
+/// Best effort desktop notification, skipped where we don't know a notifier.
+pub fn desktop_notification(summary: &str, body: &str) {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("osascript");
+        command.args([
+            "-e",
+            &format!("display notification {body:?} with title {summary:?}"),
+        ]);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("notify-send");
+        command.args([summary, body]);
+        command
+    };
+    #[cfg(not(unix))]
+    {
+        let _ = (summary, body);
+        return;
+    }
+    #[cfg(unix)]
+    match command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(error) => warn!(?error, "could not show desktop notification"),
+    }
+}
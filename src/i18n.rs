@@ -0,0 +1,48 @@
+//! Translations of what the lock screen shows, in the language picked once
+//! per process. The catalogs themselves are in `screenlock::catalog`.
+
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    sync::{Mutex, OnceLock},
+};
+
+pub use screenlock::catalog::Lang;
+
+use crate::theme;
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Catalog entries with their emoji replaced for `--ascii`, made once per key.
+static ASCII_TEXT: Mutex<Vec<(&str, &str)>> = Mutex::new(Vec::new());
+
+/// Use `lang` instead of the detected language. Only the first call counts.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+fn lang() -> Lang {
+    *LANG.get_or_init(Lang::detect)
+}
+
+/// The text for `key` in the current language.
+pub fn t(key: &'static str) -> &'static str {
+    let text = lang().text(key);
+    let Cow::Owned(ascii) = theme::glyphs(text) else {
+        return text;
+    };
+    let mut cache = ASCII_TEXT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(&(_, cached)) = cache.iter().find(|(k, _)| *k == key) {
+        return cached;
+    }
+    let ascii: &'static str = Box::leak(ascii.into_boxed_str());
+    cache.push((key, ascii));
+    ascii
+}
+
+/// Like `t`, filling in `{name}` placeholders from `args`.
+pub fn tr(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(t(key).to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
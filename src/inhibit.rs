@@ -0,0 +1,110 @@
+//! Closing the escape routes a terminal lock can't guard itself: switching to
+//! another virtual terminal and suspending the machine, which on wake-up may
+//! not lock again. Linux only.
+
+use std::{
+    fs::{File, OpenOptions},
+    os::{fd::AsRawFd, unix::process::CommandExt},
+    process::{Child, Stdio},
+    sync::Mutex,
+};
+
+use tracing::{info, warn};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Inhibit {
+    /// Virtual terminal switching, e.g. Ctrl+Alt+F2 (needs root or
+    /// CAP_SYS_TTY_CONFIG)
+    Vt,
+    /// Suspend, hibernate and the lid switch, through a logind inhibitor
+    Sleep,
+}
+
+// From linux/vt.h
+const VT_LOCKSWITCH: libc::c_ulong = 0x560B;
+const VT_UNLOCKSWITCH: libc::c_ulong = 0x560C;
+
+/// The console we locked switching on.
+static VT: Mutex<Option<File>> = Mutex::new(None);
+/// `systemd-inhibit` holding the sleep inhibitor for as long as it runs.
+static SLEEP: Mutex<Option<Child>> = Mutex::new(None);
+
+fn lock_vt_switching() -> anyhow::Result<()> {
+    let console = OpenOptions::new()
+        .write(true)
+        .open("/dev/tty0")
+        .or_else(|_| OpenOptions::new().write(true).open("/dev/console"))?;
+    // SAFETY: VT_LOCKSWITCH takes no argument and the descriptor is open
+    if unsafe { libc::ioctl(console.as_raw_fd(), VT_LOCKSWITCH as _, 0) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    *VT.lock().unwrap_or_else(|e| e.into_inner()) = Some(console);
+    Ok(())
+}
+
+fn inhibit_sleep() -> anyhow::Result<()> {
+    // The inhibitor lasts as long as the command it wraps, which exits with
+    // us even if we are killed
+    let pid = format!("--pid={}", std::process::id());
+    let child = std::process::Command::new("systemd-inhibit")
+        .args([
+            "--what=sleep:handle-suspend-key:handle-hibernate-key:handle-lid-switch",
+            "--who=screenlock",
+            "--why=Screen is locked",
+            "--mode=block",
+            "tail",
+            pid.as_str(),
+            "-f",
+            "/dev/null",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    *SLEEP.lock().unwrap_or_else(|e| e.into_inner()) = Some(child);
+    Ok(())
+}
+
+/// Releases what `acquire` took when dropped.
+pub struct Inhibitors(());
+
+impl Drop for Inhibitors {
+    fn drop(&mut self) {
+        release();
+    }
+}
+
+/// Close the given escape routes until the returned guard is dropped. What
+/// can't be inhibited is logged and skipped rather than keeping the lock from
+/// starting.
+pub fn acquire(what: &[Inhibit]) -> Inhibitors {
+    for inhibit in what {
+        let result = match inhibit {
+            Inhibit::Vt => lock_vt_switching(),
+            Inhibit::Sleep => inhibit_sleep(),
+        };
+        match result {
+            Ok(()) => info!(?inhibit, "inhibited"),
+            Err(error) => warn!(?inhibit, %error, "could not inhibit"),
+        }
+    }
+    Inhibitors(())
+}
+
+/// Undo everything `acquire` did. Safe to call more than once and from a
+/// panic hook.
+pub fn release() {
+    if let Some(console) = VT.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        // SAFETY: as above
+        if unsafe { libc::ioctl(console.as_raw_fd(), VT_UNLOCKSWITCH as _, 0) } != 0 {
+            warn!(error = %std::io::Error::last_os_error(), "could not unlock VT switching");
+        }
+    }
+    if let Some(mut child) = SLEEP.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        // Take `tail` down with it, it is in the group we started
+        // SAFETY: plain syscall, the group id is the child's pid
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM) };
+        let _ = child.wait();
+    }
+}
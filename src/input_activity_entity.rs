@@ -0,0 +1,112 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::DrawContext,
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout, theme,
+};
+
+/// How long the count stands out after it went up.
+const HIGHLIGHT: Duration = Duration::from_secs(1);
+
+/// Shows on a footer row how many key presses and clicks the grab swallowed
+/// during the lock, lighting up each time the count goes up, so it is plain
+/// to see whether someone tried the machine. The count arrives in the
+/// `blocked` property.
+pub struct InputActivityEntity {
+    id: String,
+    row: u16,
+    blocked: u32,
+    text: String,
+    /// When the count last went up
+    changed: Option<Instant>,
+}
+
+impl InputActivityEntity {
+    /// `row` is the line within the footer.
+    pub fn new(id: &str, row: u16) -> Self {
+        InputActivityEntity {
+            id: format!("InputActivityEntity-{id}"),
+            row,
+            blocked: 0,
+            text: "0".to_string(),
+            changed: None,
+        }
+    }
+
+    fn highlighted(&self) -> bool {
+        self.changed.is_some_and(|at| at.elapsed() < HIGHLIGHT)
+    }
+}
+
+impl Named for InputActivityEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for InputActivityEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        (key == "blocked").then_some(self.text.as_str())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        let Some(blocked) = value.parse().ok().filter(|_| key == "blocked") else {
+            return false;
+        };
+        if blocked > self.blocked {
+            self.changed = Some(Instant::now());
+        }
+        self.blocked = blocked;
+        self.text = blocked.to_string();
+        true
+    }
+}
+
+impl FullEntity for InputActivityEntity {}
+
+impl Entity for InputActivityEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let theme = theme::current();
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?;
+        // Nobody touched anything yet
+        if self.blocked > 0 {
+            let color = if self.highlighted() {
+                theme.warning
+            } else {
+                theme.muted
+            };
+            let text = i18n::tr("input.blocked_count", &[("count", &self.blocked)]);
+            draw_context
+                .out
+                .queue(SetForegroundColor(color))?
+                .queue(Print(text))?
+                .queue(ResetColor)?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        // Once more when the highlight ends
+        match self.changed {
+            Some(at) if self.highlighted() => {
+                Cadence::Every(HIGHLIGHT.saturating_sub(at.elapsed()))
+            }
+            _ => Cadence::OnChange,
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Local control socket. Each connection sends one line and gets one line
+//! back: `status` answers with the session state as JSON, anything else is
+//! parsed as a `Command` and queued.
+
+use std::{
+    fs::{self, DirBuilder},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    bus,
+    commands::{self, Command},
+    count_down_entity::Trigger,
+    session,
+};
+
+/// How long a client may take to send its request. Requests are answered
+/// one at a time, one that never comes must not hold up the rest.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Status {
+    pub locked: bool,
+    pub remaining_secs: u64,
+    pub reason: String,
+    /// Why the machine is locked, as given with `--reason`
+    #[serde(default)]
+    pub note: Option<String>,
+    pub failed_attempts: u32,
+    /// Unix timestamp of when the current lock started
+    pub started_at: Option<u64>,
+}
+
+impl Status {
+    fn current() -> Self {
+        let stats = session::stats();
+        Status {
+            locked: stats.locked,
+            remaining_secs: stats.remaining.as_secs(),
+            reason: stats.reason,
+            note: stats.note,
+            failed_attempts: stats.failed_attempts,
+            started_at: stats
+                .started_at
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        }
+    }
+
+    /// What a client reports when no lock is running.
+    pub fn unlocked() -> Self {
+        Status {
+            locked: false,
+            remaining_secs: 0,
+            reason: String::new(),
+            note: None,
+            failed_attempts: 0,
+            started_at: None,
+        }
+    }
+}
+
+/// `$XDG_RUNTIME_DIR`, which only we can enter, if there is one.
+fn runtime_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from)
+}
+
+/// A directory of our own in the shared temp dir, for systems without
+/// `$XDG_RUNTIME_DIR`.
+fn fallback_dir() -> PathBuf {
+    let uid = unsafe { libc::getuid() };
+    std::env::temp_dir().join(format!("screenlock-{uid}"))
+}
+
+pub fn socket_path() -> PathBuf {
+    runtime_dir()
+        .unwrap_or_else(fallback_dir)
+        .join("screenlock.sock")
+}
+
+/// Make sure the socket's directory is one only we can enter, creating the
+/// fallback one if `create`. Anyone able to connect can unlock, and anyone
+/// able to put a socket there can answer in our place.
+fn check_socket_dir(create: bool) -> anyhow::Result<()> {
+    if runtime_dir().is_some() {
+        return Ok(());
+    }
+    let dir = fallback_dir();
+    if create {
+        match DirBuilder::new().mode(0o700).create(&dir) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(error) => {
+                return Err(error).with_context(|| format!("could not create {}", dir.display()))
+            }
+        }
+    }
+    let meta = fs::symlink_metadata(&dir)?;
+    let uid = unsafe { libc::getuid() };
+    if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+        anyhow::bail!("{} is not a directory only we can enter", dir.display());
+    }
+    Ok(())
+}
+
+fn respond(stream: UnixStream) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let mut stream = stream;
+    match line.trim() {
+        "status" => {
+            serde_json::to_writer(&mut stream, &Status::current())?;
+            writeln!(stream)?;
+        }
+        request => match request.parse::<Command>() {
+            Ok(command) => {
+                info!(?command, "received IPC command");
+                commands::send(command, Trigger::Ipc);
+                writeln!(stream, "ok")?;
+            }
+            Err(error) => writeln!(stream, "error: {error}")?,
+        },
+    }
+    Ok(())
+}
+
+/// Bind the control socket and answer requests on a background thread.
+pub fn serve() -> anyhow::Result<()> {
+    check_socket_dir(true)?;
+    let path = socket_path();
+    // A socket left behind by a crashed instance would make bind fail
+    if UnixStream::connect(&path).is_err() {
+        let _ = fs::remove_file(&path);
+    }
+    // Created without access for others right away, there is no moment in
+    // which anyone else could connect
+    let umask = unsafe { libc::umask(0o077) };
+    let listener = UnixListener::bind(&path);
+    unsafe { libc::umask(umask) };
+    let listener = listener?;
+    bus::enable();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(error) = respond(stream) {
+                        warn!(?error, "IPC request failed");
+                    }
+                }
+                Err(error) => warn!(?error, "IPC connection failed"),
+            }
+        }
+    });
+    Ok(())
+}
+
+pub fn remove_socket() {
+    let _ = fs::remove_file(socket_path());
+}
+
+/// Ask a running instance for its status, `None` if there is none.
+pub fn query_status() -> anyhow::Result<Option<Status>> {
+    let Ok(mut stream) = UnixStream::connect(socket_path()) else {
+        return Ok(None);
+    };
+    check_socket_dir(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    writeln!(stream, "status")?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+fn format_remaining(secs: u64) -> String {
+    if secs >= 3600 {
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            (secs % 3600) / 60,
+            secs % 60
+        )
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// One line of the waybar custom module protocol (`"return-type": "json"`).
+/// An empty text hides the module while unlocked.
+fn waybar_line(status: &Status) -> serde_json::Value {
+    if status.locked {
+        let mut tooltip = format!(
+            "Locked ({}), {} failed attempts",
+            status.reason, status.failed_attempts
+        );
+        if let Some(note) = &status.note {
+            tooltip = format!("{note}\n{tooltip}");
+        }
+        serde_json::json!({
+            "text": format!("🔒 {}", format_remaining(status.remaining_secs)),
+            "tooltip": tooltip,
+            "class": "locked",
+            "alt": "locked",
+        })
+    } else {
+        serde_json::json!({ "text": "", "class": "unlocked", "alt": "unlocked" })
+    }
+}
+
+/// Write the status of the running lock to `out` once a second until the
+/// reader goes away.
+pub fn stream_status(mut out: impl Write) -> anyhow::Result<()> {
+    loop {
+        let status = query_status()
+            .ok()
+            .flatten()
+            .unwrap_or_else(Status::unlocked);
+        let line = serde_json::to_string(&waybar_line(&status))?;
+        if writeln!(out, "{line}").and_then(|_| out.flush()).is_err() {
+            // Bar restarted or FIFO closed
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{io::Write, process::Command, time::Duration};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, EntityId},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    hooks, i18n, layout,
+    tasks::{self, TaskHandle},
+    theme,
+};
+
+const REFRESH: Duration = Duration::from_secs(2);
+/// PowerShell alone can take a second to start
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    let output = hooks::command_output_with_timeout(command, PROBE_TIMEOUT).ok()?;
+    let text = output.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Best effort guess at the active keyboard layout, `None` if the platform
+/// does not tell us.
+#[cfg(target_os = "linux")]
+fn detect() -> Option<String> {
+    // xkb-switch knows the active group, setxkbmap only the configured ones
+    command_output("xkb-switch", &["-p"]).or_else(|| {
+        command_output("setxkbmap", &["-query"])?
+            .lines()
+            .find_map(|line| line.strip_prefix("layout:"))
+            .map(|layout| layout.trim().to_string())
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> Option<String> {
+    let source = command_output(
+        "defaults",
+        &[
+            "read",
+            "com.apple.HIToolbox",
+            "AppleCurrentKeyboardLayoutInputSourceID",
+        ],
+    )?;
+    Some(
+        source
+            .strip_prefix("com.apple.keylayout.")
+            .unwrap_or(&source)
+            .to_string(),
+    )
+}
+
+#[cfg(windows)]
+fn detect() -> Option<String> {
+    command_output(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             [System.Windows.Forms.InputLanguage]::CurrentInputLanguage.Culture.Name",
+        ],
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn detect() -> Option<String> {
+    None
+}
+
+/// Shows the active keyboard layout below the prompt, since typing a password
+/// with the wrong layout is an easy way to fail unlocking. The layout is
+/// looked up on the background pool, the tools asked can be slow to answer.
+pub struct KeyboardLayoutEntity {
+    id: String,
+    entity_id: EntityId,
+    layout: Option<String>,
+    _task: TaskHandle,
+}
+
+impl KeyboardLayoutEntity {
+    pub fn spawn(id: &str) -> Self {
+        let entity_id = EntityId::next();
+        let task = tasks::every(entity_id, "layout", REFRESH, || {
+            Ok(detect().unwrap_or_default())
+        });
+        KeyboardLayoutEntity {
+            id: format!("KeyboardLayoutEntity-{id}"),
+            entity_id,
+            layout: None,
+            _task: task,
+        }
+    }
+}
+
+impl Named for KeyboardLayoutEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for KeyboardLayoutEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        match key {
+            "layout" => self.layout.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != "layout" {
+            return false;
+        }
+        self.layout = (!value.is_empty()).then(|| value.to_string());
+        true
+    }
+}
+
+impl FullEntity for KeyboardLayoutEntity {}
+
+impl Entity for KeyboardLayoutEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let hint_y = layout::hint_y();
+        draw_context
+            .out
+            .queue(MoveTo(0, hint_y))?
+            .queue(Clear(ClearType::CurrentLine))?;
+        if let Some(layout) = &self.layout {
+            let hint = i18n::tr("keyboard.layout", &[("layout", layout)]);
+            draw_context
+                .out
+                .queue(SetForegroundColor(theme::current().muted))?
+                .queue(Print(theme::glyphs(&hint)))?
+                .queue(ResetColor)?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
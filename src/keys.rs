@@ -0,0 +1,20 @@
+//! Telling typed characters from shortcuts, for password prompts.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// `code` as its main keyboard equivalent. Keypad digits already arrive as
+/// `Char`, but some terminals send keypad Enter as a bare carriage return or
+/// line feed.
+pub fn main_key(code: KeyCode) -> KeyCode {
+    match code {
+        KeyCode::Char('\r' | '\n') => KeyCode::Enter,
+        code => code,
+    }
+}
+
+/// Whether `modifiers` make a key a shortcut rather than something typed.
+/// AltGr arrives as Ctrl+Alt on Windows and still types characters.
+pub fn is_shortcut(modifiers: KeyModifiers) -> bool {
+    let ctrl_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+    modifiers.intersects(ctrl_alt) && !modifiers.contains(ctrl_alt)
+}
@@ -0,0 +1,201 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU16, Ordering},
+    Mutex,
+};
+
+use clap::ValueEnum;
+
+pub const COUNTDOWN_Y: u16 = 0;
+pub const LOGO_Y: u16 = COUNTDOWN_Y + 1;
+
+static LOGO_HEIGHT: AtomicU16 = AtomicU16::new(0);
+static GREETING_HEIGHT: AtomicU16 = AtomicU16::new(0);
+static TITLE_HEIGHT: AtomicU16 = AtomicU16::new(0);
+static FEEDBACK_HEIGHT: AtomicU16 = AtomicU16::new(1);
+static FOOTER_HEIGHT: AtomicU16 = AtomicU16::new(0);
+static AUTH_HINT_HEIGHT: AtomicU16 = AtomicU16::new(0);
+
+/// Set whenever rows moved, so the controller knows to redraw everything.
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Which row the prompt is put on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PromptPosition {
+    /// Right below the title
+    #[default]
+    BelowTitle,
+    /// In the middle of the screen
+    Center,
+    /// As low as the feedback and hints below it allow
+    Bottom,
+}
+
+/// How the prompt line is aligned, mirrored for right-to-left text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PromptAlign {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+#[derive(Clone, Copy)]
+struct PromptLayout {
+    position: PromptPosition,
+    /// Rows to move it down, or up if negative
+    offset: i16,
+    align: PromptAlign,
+}
+
+static PROMPT_LAYOUT: Mutex<PromptLayout> = Mutex::new(PromptLayout {
+    position: PromptPosition::BelowTitle,
+    offset: 0,
+    align: PromptAlign::Start,
+});
+
+/// Pretend the terminal has this size, for rendering frames off screen.
+static SIZE_OVERRIDE: Mutex<Option<(u16, u16)>> = Mutex::new(None);
+
+pub fn set_size_override(size: Option<(u16, u16)>) {
+    *SIZE_OVERRIDE.lock().unwrap_or_else(|e| e.into_inner()) = size;
+}
+
+pub fn terminal_size() -> (u16, u16) {
+    if let Some(size) = *SIZE_OVERRIDE.lock().unwrap_or_else(|e| e.into_inner()) {
+        return size;
+    }
+    crossterm::terminal::size().unwrap_or((80, 24))
+}
+
+/// Report how many rows the banner occupies. Returns true if that moved the
+/// rows below it.
+pub fn set_logo_height(height: u16) -> bool {
+    let changed = LOGO_HEIGHT.swap(height, Ordering::Relaxed) != height;
+    if changed {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+    changed
+}
+
+/// Reserve rows between the banner and the title for a greeting.
+pub fn set_greeting_height(height: u16) {
+    if GREETING_HEIGHT.swap(height, Ordering::Relaxed) != height {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Report how many rows the title occupies. Returns true if that moved the
+/// rows below it.
+pub fn set_title_height(height: u16) -> bool {
+    let changed = TITLE_HEIGHT.swap(height, Ordering::Relaxed) != height;
+    if changed {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+    changed
+}
+
+/// Report how many rows the messages below the prompt occupy. Returns true
+/// if that moved the rows below them.
+pub fn set_feedback_height(height: u16) -> bool {
+    let changed = FEEDBACK_HEIGHT.swap(height, Ordering::Relaxed) != height;
+    if changed {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+    changed
+}
+
+/// Reserve rows at the bottom of the screen, e.g. for plugin widgets.
+pub fn set_footer_height(height: u16) {
+    if FOOTER_HEIGHT.swap(height, Ordering::Relaxed) != height {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn footer_height() -> u16 {
+    FOOTER_HEIGHT.load(Ordering::Relaxed)
+}
+
+pub fn take_changed() -> bool {
+    CHANGED.swap(false, Ordering::Relaxed)
+}
+
+pub fn greeting_y() -> u16 {
+    LOGO_Y + LOGO_HEIGHT.load(Ordering::Relaxed)
+}
+
+pub fn title_y() -> u16 {
+    greeting_y() + GREETING_HEIGHT.load(Ordering::Relaxed)
+}
+
+/// Put the prompt at `position`, `offset` rows further down, aligned by
+/// `align`. It never moves up into the title.
+pub fn set_prompt_layout(position: PromptPosition, offset: i16, align: PromptAlign) {
+    *PROMPT_LAYOUT.lock().unwrap_or_else(|e| e.into_inner()) = PromptLayout {
+        position,
+        offset,
+        align,
+    };
+    CHANGED.store(true, Ordering::Relaxed);
+}
+
+fn prompt_layout() -> PromptLayout {
+    *PROMPT_LAYOUT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn prompt_y() -> u16 {
+    let below_title = title_y() + TITLE_HEIGHT.load(Ordering::Relaxed) + 1; // titles length + 1 line gap
+    let layout = prompt_layout();
+    let row = match layout.position {
+        PromptPosition::BelowTitle => below_title,
+        PromptPosition::Center => terminal_size().1 / 2,
+        // The prompt, feedback, hint and auth hint rows end right above the footer
+        PromptPosition::Bottom => footer_y().saturating_sub(
+            2 + FEEDBACK_HEIGHT.load(Ordering::Relaxed) + AUTH_HINT_HEIGHT.load(Ordering::Relaxed),
+        ),
+    };
+    row.saturating_add_signed(layout.offset).max(below_title)
+}
+
+/// Column a prompt line `line_width` wide starts at on a terminal `width`
+/// wide.
+pub fn prompt_x(width: u16, line_width: usize, rtl: bool) -> u16 {
+    let free = (width as usize).saturating_sub(line_width) as u16;
+    match (prompt_layout().align, rtl) {
+        (PromptAlign::Center, _) => free / 2,
+        (PromptAlign::Start, false) | (PromptAlign::End, true) => 0,
+        (PromptAlign::Start, true) | (PromptAlign::End, false) => free,
+    }
+}
+
+pub fn feedback_y() -> u16 {
+    prompt_y() + 1
+}
+
+/// Row for passive hints below the feedback messages.
+pub fn hint_y() -> u16 {
+    feedback_y() + FEEDBACK_HEIGHT.load(Ordering::Relaxed)
+}
+
+/// First row for hints of the other ways to unlock, e.g. a security key.
+pub fn auth_hint_y() -> u16 {
+    hint_y() + 1
+}
+
+/// Report how many rows the hints of the other ways to unlock take.
+pub fn set_auth_hint_height(height: u16) {
+    if AUTH_HINT_HEIGHT.swap(height, Ordering::Relaxed) != height {
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// First row free for larger drawings below the prompt and its hints.
+pub fn body_y() -> u16 {
+    auth_hint_y() + AUTH_HINT_HEIGHT.load(Ordering::Relaxed) + 1
+}
+
+/// First footer row. The last row stays empty, printing into its corner
+/// scrolls some terminals.
+pub fn footer_y() -> u16 {
+    let (_, height) = terminal_size();
+    height.saturating_sub(footer_height() + 1)
+}
@@ -0,0 +1,10 @@
+//! Embeddable parts of screenlock for use inside other terminal applications.
+//!
+//! Enable the `ratatui` feature to render `LockScreen` as a ratatui widget.
+
+pub mod catalog;
+pub mod keys;
+pub mod lock_screen;
+pub mod secret;
+
+pub use lock_screen::{LockInput, LockScreen, Region};
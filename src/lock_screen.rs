@@ -0,0 +1,306 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{Event, KeyCode, KeyEvent, KeyEventKind},
+    style::Print,
+    QueueableCommand,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    catalog::Lang,
+    keys::{is_shortcut, main_key},
+    secret::{Secret, MAX_PASSWORD_LEN},
+};
+
+const FEEDBACK_DURATION: Duration = Duration::from_secs(2);
+/// Like the lock's `--attempt-delay` default.
+const ATTEMPT_DELAY: Duration = Duration::from_millis(500);
+
+/// Input understood by `LockScreen`. Applications using a different
+/// crossterm version (or no crossterm at all) can translate their own key
+/// events into these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockInput {
+    Char(char),
+    Backspace,
+    Enter,
+}
+
+/// Rectangle of the host application's screen the lock screen draws into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// An in-app lock overlay: the host forwards input and draws it into a region
+/// of its own screen until `is_unlocked` reports true.
+pub struct LockScreen {
+    lang: Lang,
+    /// The language's own title and prompt unless set
+    title: Option<Vec<String>>,
+    prompt: Option<String>,
+    secret: Secret,
+    input: String,
+    started: Instant,
+    timeout: Option<Duration>,
+    attempt_delay: Duration,
+    /// Input is ignored until then after a wrong password
+    retry_at: Option<Instant>,
+    feedback: Option<Instant>,
+    unlocked: bool,
+}
+
+impl LockScreen {
+    /// Unlock with `password`, compared like the lock's own: surrounding
+    /// whitespace and case don't count.
+    pub fn new(password: &str) -> Self {
+        Self::with_secret(Secret::plain(password))
+    }
+
+    pub fn with_secret(secret: Secret) -> Self {
+        LockScreen {
+            lang: Lang::detect(),
+            title: None,
+            prompt: None,
+            secret,
+            input: String::new(),
+            started: Instant::now(),
+            timeout: None,
+            attempt_delay: ATTEMPT_DELAY,
+            retry_at: None,
+            feedback: None,
+            unlocked: false,
+        }
+    }
+
+    /// Speak `lang` rather than the one of the environment.
+    pub fn with_lang(mut self, lang: Lang) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    /// Ignore input for `delay` after a wrong password, against rapid-fire
+    /// guessing.
+    pub fn with_attempt_delay(mut self, delay: Duration) -> Self {
+        self.attempt_delay = delay;
+        self
+    }
+
+    /// Unlock on its own once `timeout` has passed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_title<I, S>(mut self, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.title = Some(lines.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.timeout
+            .map(|t| t.saturating_sub(self.started.elapsed()))
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked || self.remaining().is_some_and(|r| r.is_zero())
+    }
+
+    /// Feed a key to the lock screen. Returns true if it was consumed.
+    pub fn input(&mut self, input: LockInput) -> bool {
+        if self.is_unlocked() {
+            return false;
+        }
+        if self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return true;
+        }
+        match input {
+            LockInput::Char(c) if c.is_control() => return false,
+            LockInput::Char(c) => {
+                if self.input.chars().count() < MAX_PASSWORD_LEN {
+                    self.input.push(c);
+                }
+            }
+            LockInput::Backspace => {
+                self.input.pop();
+            }
+            LockInput::Enter => {
+                if self.secret.matches(&self.input) {
+                    self.unlocked = true;
+                } else {
+                    self.feedback = Some(Instant::now());
+                    self.retry_at = Some(Instant::now() + self.attempt_delay);
+                }
+                self.input.clear();
+            }
+        }
+        true
+    }
+
+    /// Convenience wrapper around `input` for crossterm events. Shortcuts
+    /// such as Ctrl+C are left to the host.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let input = match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind,
+                ..
+            }) if *kind != KeyEventKind::Release => match main_key(*code) {
+                KeyCode::Char(_) if is_shortcut(*modifiers) => return false,
+                KeyCode::Char(c) => LockInput::Char(c),
+                KeyCode::Backspace => LockInput::Backspace,
+                KeyCode::Enter => LockInput::Enter,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        self.input(input)
+    }
+
+    /// The rows of the lock screen, top to bottom, independent of any renderer.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(remaining) = self.remaining() {
+            let secs = remaining.as_secs();
+            lines.push(format!("{:02}:{:02}", secs / 60, secs % 60));
+        }
+        match &self.title {
+            Some(title) => lines.extend(title.iter().cloned()),
+            None => lines.push(self.lang.text("title.locked").to_string()),
+        }
+        lines.push(String::new());
+        let prompt = self
+            .prompt
+            .as_deref()
+            .unwrap_or_else(|| self.lang.text("prompt.password"));
+        lines.push(format!(
+            "{prompt}{}",
+            "*".repeat(self.input.chars().count())
+        ));
+        match self.feedback {
+            Some(shown) if shown.elapsed() < FEEDBACK_DURATION => {
+                lines.push(self.lang.text("feedback.wrong_password").to_string())
+            }
+            _ => lines.push(String::new()),
+        }
+        lines
+    }
+
+    /// Draw into `region` of a crossterm terminal, clipping to its bounds.
+    pub fn draw<W: Write>(&self, out: &mut W, region: Region) -> io::Result<()> {
+        let lines = self.lines();
+        for row in 0..region.height {
+            out.queue(MoveTo(region.x, region.y + row))?;
+            let line = lines.get(row as usize).map(String::as_str).unwrap_or("");
+            let mut used = 0;
+            let clipped: String = line
+                .chars()
+                .take_while(|c| {
+                    used += c.width().unwrap_or(0);
+                    used <= region.width as usize
+                })
+                .collect();
+            let padding = region.width as usize - clipped.width();
+            out.queue(Print(clipped))?;
+            out.queue(Print(" ".repeat(padding)))?;
+        }
+        out.flush()
+    }
+
+    /// Clear `region` once the host application is done with the lock screen.
+    pub fn clear<W: Write>(out: &mut W, region: Region) -> io::Result<()> {
+        for row in 0..region.height {
+            out.queue(MoveTo(region.x, region.y + row))?;
+            out.queue(Print(" ".repeat(region.width as usize)))?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(feature = "ratatui")]
+impl ratatui::widgets::Widget for &LockScreen {
+    fn render(self, area: ratatui::layout::Rect, buf: &mut ratatui::buffer::Buffer) {
+        for (row, line) in self.lines().iter().enumerate().take(area.height as usize) {
+            buf.set_stringn(
+                area.x,
+                area.y + row as u16,
+                line,
+                area.width as usize,
+                ratatui::style::Style::default(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn typing(lock: &mut LockScreen, text: &str) {
+        for c in text.chars() {
+            lock.input(LockInput::Char(c));
+        }
+        lock.input(LockInput::Enter);
+    }
+
+    #[test]
+    fn a_wrong_password_is_reported_and_the_right_one_unlocks() {
+        let mut lock = LockScreen::new("hunter2")
+            .with_lang(Lang::En)
+            .with_attempt_delay(Duration::ZERO);
+        typing(&mut lock, "guess");
+        assert!(!lock.is_unlocked());
+        assert!(lock
+            .lines()
+            .contains(&Lang::En.text("feedback.wrong_password").to_string()));
+        typing(&mut lock, "hunter2");
+        assert!(lock.is_unlocked());
+    }
+
+    #[test]
+    fn input_is_ignored_during_the_attempt_delay() {
+        let mut lock = LockScreen::new("hunter2").with_attempt_delay(Duration::from_secs(60));
+        typing(&mut lock, "guess");
+        typing(&mut lock, "hunter2");
+        assert!(!lock.is_unlocked());
+    }
+
+    #[test]
+    fn shortcuts_are_left_to_the_host() {
+        let mut lock = LockScreen::new("hunter2").with_lang(Lang::En);
+        let ctrl_c = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(!lock.handle_event(&ctrl_c));
+        let lines = lock.lines();
+        assert_eq!(lines[lines.len() - 2], Lang::En.text("prompt.password"));
+    }
+
+    #[test]
+    fn overlong_input_is_cut_off() {
+        let mut lock = LockScreen::new("hunter2").with_prompt("");
+        for _ in 0..MAX_PASSWORD_LEN + 10 {
+            lock.input(LockInput::Char('x'));
+        }
+        let lines = lock.lines();
+        assert_eq!(lines[lines.len() - 2].len(), MAX_PASSWORD_LEN);
+    }
+}
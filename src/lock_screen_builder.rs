@@ -0,0 +1,931 @@
+//! Assembles the entities and listeners of one lock session from a few
+//! settings, so callers never have to wire entities to each other by name.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use chrono::NaiveTime;
+
+use crate::{
+    accessible::{AnnouncerEntity, Muted},
+    animation_entity::{AnimationEntity, ScreensaverStyle},
+    attempt_counter_entity::AttemptCounterEntity,
+    base_entity::BaseEntity,
+    bell_entity::BellEntity,
+    blank_entity::{BlankEntity, BlankMode},
+    breathing_entity::{BreathingEntity, BreathingPattern},
+    calendar_entity::{CalendarEntity, CalendarSource},
+    challenge_entity::{Challenge, ChallengeEntity},
+    command_output_entity::CommandOutputEntity,
+    contact_entity::ContactEntity,
+    controller::{ControlEvent, Controller, EntityId, Recipient},
+    count_down_entity::{
+        ColorThresholds, CountDownEntity, CountdownFormat, CountdownTenths, Milestone, OnExpire,
+    },
+    entity::FullEntity,
+    grab_health,
+    grab_warning_entity::GrabWarningEntity,
+    greeting_entity::GreetingEntity,
+    hooks, i18n,
+    input_activity_entity::InputActivityEntity,
+    keyboard_layout_entity::KeyboardLayoutEntity,
+    layout::{self, PromptAlign, PromptPosition},
+    logo_entity::LogoEntity,
+    markup,
+    message_area_entity::{FeedbackAnimation, MessageAreaEntity},
+    network_entity::NetworkEntity,
+    password_prompt_entity::{Credential, Failure, PasswordPromptEntity},
+    plugin::PluginEntity,
+    qr_code_entity::QrCodeEntity,
+    static_text_entity::{MessageSource, StaticTextEntity},
+    template, theme,
+    totp::Totp,
+};
+
+/// Blocked input cues come at most this often while someone mashes a key.
+const CUE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long messages stay up unless `feedback_duration` says otherwise.
+pub const FEEDBACK_DURATION: Duration = Duration::from_secs(2);
+
+const TITLE_KEYS: [&str; 4] = [
+    "title.unattended",
+    "title.tampering",
+    "title.active",
+    "title.love",
+];
+
+/// How the lock can be ended before the countdown runs out.
+pub enum Auth {
+    /// Not at all
+    Strict,
+    /// Typing one of `credentials`, the user's own password first
+    Password(Vec<Credential>),
+    /// Like `Password`, asking for the code sent to an accountability partner
+    /// instead
+    PartnerCode(Vec<Credential>),
+    /// Solving arithmetic problems
+    Challenge(Challenge),
+}
+
+/// How the lock reacts when the grab swallows a key or click, so whoever is
+/// at the keyboard can tell the machine is locked rather than frozen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum BlockedInputCue {
+    /// Show "input blocked" below the prompt for a moment
+    Flash,
+    /// Ring the terminal bell
+    Bell,
+}
+
+/// The emergency phrase, the message it shows and for how long.
+struct Emergency {
+    phrase: String,
+    message: Option<String>,
+    delay: Duration,
+}
+
+/// Settings of a lock session, turned into a ready `Controller` by `build`.
+/// Everything but the duration is optional and off by default.
+pub struct LockScreenBuilder {
+    duration: Duration,
+    elapsed: Option<Duration>,
+    grace: Option<(Duration, bool)>,
+    on_expire: OnExpire,
+    countdown_colors: ColorThresholds,
+    countdown_format: CountdownFormat,
+    countdown_tenths: CountdownTenths,
+    hide_timer: bool,
+    milestones: Vec<Milestone>,
+    auth: Auth,
+    failed_attempts: u32,
+    second_factor: Option<Totp>,
+    paste: bool,
+    attempt_delay: Duration,
+    failure_messages: Vec<(Failure, String)>,
+    emergency: Option<Emergency>,
+    #[cfg(feature = "fido2")]
+    security_key: bool,
+    #[cfg(all(target_os = "linux", feature = "fprintd"))]
+    fingerprint: bool,
+    #[cfg(feature = "presence")]
+    presence: Option<(
+        crate::auth::presence::PresenceSource,
+        crate::auth::presence::PresencePolicy,
+    )>,
+    message_source: Option<MessageSource>,
+    message_refresh: Duration,
+    reason: Option<String>,
+    feedback_animation: FeedbackAnimation,
+    feedback_duration: Duration,
+    contact: Vec<String>,
+    ascii: bool,
+    accessible: bool,
+    banner: Option<PathBuf>,
+    greeting: Option<Vec<(NaiveTime, String)>>,
+    screensaver: Option<ScreensaverStyle>,
+    blank: Option<(BlankMode, Duration)>,
+    breathing: Option<BreathingPattern>,
+    plugins: Vec<String>,
+    widgets: Vec<String>,
+    widget_refresh: Duration,
+    widget_timeout: Duration,
+    calendar: Option<CalendarSource>,
+    network_indicator: Option<String>,
+    attempt_counter: bool,
+    grab_warning: bool,
+    blocked_input_cues: Vec<BlockedInputCue>,
+    input_activity: bool,
+    #[cfg(feature = "scripting")]
+    scripts: Vec<PathBuf>,
+    layout_hint: bool,
+    prompt_layout: (PromptPosition, i16, PromptAlign),
+    qr_code: Option<(String, Option<String>)>,
+    on_milestone: Option<String>,
+    on_failed_attempt: Option<(String, u32)>,
+    on_emergency_unlock: Option<String>,
+}
+
+impl LockScreenBuilder {
+    /// A strict lock for `duration` showing the default messages.
+    pub fn new(duration: Duration) -> Self {
+        LockScreenBuilder {
+            duration,
+            elapsed: None,
+            grace: None,
+            on_expire: OnExpire::Unlock,
+            countdown_colors: ColorThresholds::default(),
+            countdown_format: CountdownFormat::default(),
+            countdown_tenths: CountdownTenths::default(),
+            hide_timer: false,
+            milestones: Vec::new(),
+            auth: Auth::Strict,
+            failed_attempts: 0,
+            second_factor: None,
+            paste: true,
+            attempt_delay: Duration::from_millis(500),
+            failure_messages: Vec::new(),
+            emergency: None,
+            #[cfg(feature = "fido2")]
+            security_key: false,
+            #[cfg(all(target_os = "linux", feature = "fprintd"))]
+            fingerprint: false,
+            #[cfg(feature = "presence")]
+            presence: None,
+            message_source: None,
+            message_refresh: Duration::from_secs(60),
+            reason: None,
+            feedback_animation: FeedbackAnimation::None,
+            feedback_duration: FEEDBACK_DURATION,
+            contact: Vec::new(),
+            ascii: false,
+            accessible: false,
+            banner: None,
+            greeting: None,
+            screensaver: None,
+            blank: None,
+            breathing: None,
+            plugins: Vec::new(),
+            widgets: Vec::new(),
+            widget_refresh: Duration::from_secs(10),
+            widget_timeout: Duration::from_secs(2),
+            calendar: None,
+            network_indicator: None,
+            attempt_counter: false,
+            grab_warning: false,
+            blocked_input_cues: Vec::new(),
+            input_activity: false,
+            #[cfg(feature = "scripting")]
+            scripts: Vec::new(),
+            layout_hint: false,
+            prompt_layout: Default::default(),
+            qr_code: None,
+            on_milestone: None,
+            on_failed_attempt: None,
+            on_emergency_unlock: None,
+        }
+    }
+
+    /// Continue a lock of which `elapsed` already passed.
+    pub fn elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = Some(elapsed);
+        self
+    }
+
+    /// Wait up to `grace` for a key press once the countdown expires, see
+    /// `CountDownEntity::set_grace`.
+    pub fn grace(mut self, grace: Duration, relock: bool) -> Self {
+        self.grace = Some((grace, relock));
+        self
+    }
+
+    /// What happens once the countdown runs out, see `OnExpire`.
+    pub fn on_expire(mut self, on_expire: OnExpire) -> Self {
+        self.on_expire = on_expire;
+        self
+    }
+
+    pub fn countdown_colors(mut self, colors: ColorThresholds) -> Self {
+        self.countdown_colors = colors;
+        self
+    }
+
+    pub fn countdown_format(mut self, format: CountdownFormat) -> Self {
+        self.countdown_format = format;
+        self
+    }
+
+    pub fn countdown_tenths(mut self, tenths: CountdownTenths) -> Self {
+        self.countdown_tenths = tenths;
+        self
+    }
+
+    /// Show that the lock is on but neither the time left nor milestones.
+    pub fn hide_timer(mut self, hide: bool) -> Self {
+        self.hide_timer = hide;
+        self
+    }
+
+    pub fn milestones(mut self, milestones: Vec<Milestone>) -> Self {
+        self.milestones = milestones;
+        self
+    }
+
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Count on from earlier failures, e.g. of a lock restored after a crash.
+    pub fn failed_attempts(mut self, failed_attempts: u32) -> Self {
+        self.failed_attempts = failed_attempts;
+        self
+    }
+
+    /// Ask for a TOTP code after the password.
+    pub fn second_factor(mut self, totp: Totp) -> Self {
+        self.second_factor = Some(totp);
+        self
+    }
+
+    /// Whether the password may be pasted.
+    pub fn paste(mut self, allowed: bool) -> Self {
+        self.paste = allowed;
+        self
+    }
+
+    /// Ignore input for `delay` after each wrong password.
+    pub fn attempt_delay(mut self, delay: Duration) -> Self {
+        self.attempt_delay = delay;
+        self
+    }
+
+    /// Report `failure` with `message` instead of the default one.
+    pub fn failure_message(mut self, failure: Failure, message: String) -> Self {
+        self.failure_messages.push((failure, message));
+        self
+    }
+
+    /// Unlock on `phrase`, after showing `message` for `delay` if there is
+    /// one.
+    pub fn emergency_phrase(
+        mut self,
+        phrase: &str,
+        message: Option<&str>,
+        delay: Duration,
+    ) -> Self {
+        self.emergency = Some(Emergency {
+            phrase: phrase.to_string(),
+            message: message.map(str::to_string),
+            delay: if message.is_some() {
+                delay
+            } else {
+                Duration::ZERO
+            },
+        });
+        self
+    }
+
+    /// Also unlock with a registered FIDO2 security key.
+    #[cfg(feature = "fido2")]
+    pub fn security_key(mut self, enabled: bool) -> Self {
+        self.security_key = enabled;
+        self
+    }
+
+    /// Also unlock with a fingerprint enrolled in fprintd.
+    #[cfg(all(target_os = "linux", feature = "fprintd"))]
+    pub fn fingerprint(mut self, enabled: bool) -> Self {
+        self.fingerprint = enabled;
+        self
+    }
+
+    /// Check whether the owner is nearby with `source`, and end the lock on
+    /// their return or refuse to unlock while they are away, per `policy`.
+    #[cfg(feature = "presence")]
+    pub fn presence(
+        mut self,
+        source: crate::auth::presence::PresenceSource,
+        policy: crate::auth::presence::PresencePolicy,
+    ) -> Self {
+        self.presence = Some((source, policy));
+        self
+    }
+
+    /// Show these lines instead of the default message, reloaded every
+    /// `refresh`.
+    pub fn messages(mut self, source: MessageSource, refresh: Duration) -> Self {
+        self.message_source = Some(source);
+        self.message_refresh = refresh;
+        self
+    }
+
+    /// Show why the machine is locked above the message.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    pub fn feedback_animation(mut self, animation: FeedbackAnimation) -> Self {
+        self.feedback_animation = animation;
+        self
+    }
+
+    /// How long failures and other messages stay up.
+    pub fn feedback_duration(mut self, duration: Duration) -> Self {
+        self.feedback_duration = duration;
+        self
+    }
+
+    /// Lines at the bottom of the screen, e.g. who to call.
+    pub fn contact(mut self, lines: Vec<String>) -> Self {
+        self.contact = lines;
+        self
+    }
+
+    /// Replace emoji with ASCII and use the high-contrast palette.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Screen reader friendly output, see `AnnouncerEntity`.
+    pub fn accessible(mut self, accessible: bool) -> Self {
+        self.accessible = accessible;
+        self
+    }
+
+    /// Show this ASCII or ANSI art file above the message.
+    pub fn banner(mut self, path: PathBuf) -> Self {
+        self.banner = Some(path);
+        self
+    }
+
+    /// Greet the user by name and time of day above the title, with
+    /// `templates` from the time of day each starts or the built-in ones.
+    pub fn greeting(mut self, templates: Vec<(NaiveTime, String)>) -> Self {
+        self.greeting = Some(templates);
+        self
+    }
+
+    pub fn screensaver(mut self, style: ScreensaverStyle) -> Self {
+        self.screensaver = Some(style);
+        self
+    }
+
+    /// Blank or dim the display once no key was pressed for `after`.
+    pub fn blank(mut self, mode: BlankMode, after: Duration) -> Self {
+        self.blank = Some((mode, after));
+        self
+    }
+
+    pub fn breathing(mut self, pattern: BreathingPattern) -> Self {
+        self.breathing = Some(pattern);
+        self
+    }
+
+    /// Run `command` as a widget plugin, see `PluginEntity`.
+    pub fn plugin(mut self, command: &str) -> Self {
+        self.plugins.push(command.to_string());
+        self
+    }
+
+    /// Show the output of `command` in the footer.
+    pub fn widget(mut self, command: &str) -> Self {
+        self.widgets.push(command.to_string());
+        self
+    }
+
+    /// How often widget commands run and how long they may take.
+    pub fn widget_timing(mut self, refresh: Duration, timeout: Duration) -> Self {
+        self.widget_refresh = refresh;
+        self.widget_timeout = timeout;
+        self
+    }
+
+    pub fn calendar(mut self, source: CalendarSource) -> Self {
+        self.calendar = Some(source);
+        self
+    }
+
+    /// Show whether `addr` (host:port) can be reached.
+    pub fn network_indicator(mut self, addr: &str) -> Self {
+        self.network_indicator = Some(addr.to_string());
+        self
+    }
+
+    /// Count failed unlock attempts on a footer row.
+    pub fn attempt_counter(mut self, shown: bool) -> Self {
+        self.attempt_counter = shown;
+        self
+    }
+
+    /// Keep a footer row for the warning shown once the input grab is lost.
+    pub fn grab_warning(mut self, shown: bool) -> Self {
+        self.grab_warning = shown;
+        self
+    }
+
+    /// React to input the grab swallows with `cues`.
+    pub fn blocked_input_cues(mut self, cues: Vec<BlockedInputCue>) -> Self {
+        self.blocked_input_cues = cues;
+        self
+    }
+
+    /// Count the key presses and clicks the grab swallowed on a footer row.
+    pub fn input_activity(mut self, shown: bool) -> Self {
+        self.input_activity = shown;
+        self
+    }
+
+    /// Load an entity written in Rhai from `path`.
+    #[cfg(feature = "scripting")]
+    pub fn script(mut self, path: PathBuf) -> Self {
+        self.scripts.push(path);
+        self
+    }
+
+    /// Show the active keyboard layout below the prompt.
+    pub fn layout_hint(mut self, shown: bool) -> Self {
+        self.layout_hint = shown;
+        self
+    }
+
+    /// Put the prompt at `position`, `offset` rows further down, see
+    /// `layout::set_prompt_layout`.
+    pub fn prompt_layout(
+        mut self,
+        position: PromptPosition,
+        offset: i16,
+        align: PromptAlign,
+    ) -> Self {
+        self.prompt_layout = (position, offset, align);
+        self
+    }
+
+    /// Show `data` as a QR code with `label` below it.
+    pub fn qr_code(mut self, data: String, label: Option<String>) -> Self {
+        self.qr_code = Some((data, label));
+        self
+    }
+
+    /// Run `hook` at each milestone, see `hooks::run_milestone_hook`.
+    pub fn on_milestone(mut self, hook: String) -> Self {
+        self.on_milestone = Some(hook);
+        self
+    }
+
+    /// Run `hook` on failed attempts from the `threshold`th on.
+    pub fn on_failed_attempt(mut self, hook: String, threshold: u32) -> Self {
+        self.on_failed_attempt = Some((hook, threshold));
+        self
+    }
+
+    pub fn on_emergency_unlock(mut self, hook: String) -> Self {
+        self.on_emergency_unlock = Some(hook);
+        self
+    }
+
+    /// Add `entity`, muted in accessible mode where the announcer speaks for
+    /// it.
+    fn add_drawn<T: FullEntity + 'static>(
+        &self,
+        controller: &mut Controller,
+        entity: T,
+    ) -> EntityId {
+        if self.accessible {
+            controller.add_entity(Muted::new(entity))
+        } else {
+            controller.add_entity(entity)
+        }
+    }
+
+    pub fn build(mut self) -> anyhow::Result<Controller> {
+        theme::set_ascii(self.ascii);
+        let (position, offset, align) = self.prompt_layout;
+        layout::set_prompt_layout(position, offset, align);
+        let mut controller = Controller::new();
+        controller.set_plain(self.accessible);
+
+        let strict = matches!(self.auth, Auth::Strict);
+        let message_source = self.message_source.take().unwrap_or_else(|| {
+            MessageSource::Static(
+                TITLE_KEYS
+                    .iter()
+                    .map(|key| i18n::t(key).to_string())
+                    .collect(),
+            )
+        });
+
+        if let Some(path) = &self.banner {
+            controller.add_entity(BaseEntity::new(LogoEntity::from_file("banner", path)?));
+        }
+
+        let greeting = self.greeting.take().filter(|_| !self.accessible);
+        layout::set_greeting_height(u16::from(greeting.is_some()));
+        if let Some(templates) = greeting {
+            let greeting = GreetingEntity::new("greeting", templates);
+            controller.add_entity(BaseEntity::new(greeting));
+        }
+
+        let heading = self
+            .reason
+            .as_ref()
+            .map(|reason| i18n::tr("title.reason", &[("reason", reason)]));
+
+        let prompt = i18n::t(if matches!(self.auth, Auth::PartnerCode(_)) {
+            "prompt.partner"
+        } else {
+            "prompt.password"
+        });
+
+        // Where failures, milestones and other messages go
+        let messages = if self.accessible {
+            let lines = message_source
+                .load()
+                .context("could not load lock message")?;
+            let intro = heading
+                .into_iter()
+                .chain(
+                    lines
+                        .iter()
+                        .map(|line| markup::plain_text(&template::expand(line))),
+                )
+                .chain(self.contact.iter().cloned())
+                .collect();
+            let mut announcer = AnnouncerEntity::new("messages", intro);
+            if !strict {
+                announcer.set_prompt(prompt);
+            }
+            if self.hide_timer {
+                announcer.hide_time_left();
+            }
+            controller.add_entity(announcer)
+        } else {
+            let mut t_entity =
+                StaticTextEntity::with_source("title", message_source, self.message_refresh);
+            if let Some(heading) = &heading {
+                t_entity.set_heading(heading);
+            }
+            controller.add_entity(t_entity);
+            let mut messages = MessageAreaEntity::new("messages", self.feedback_duration);
+            messages.set_animation(self.feedback_animation);
+            controller.add_entity(messages)
+        };
+
+        let mut c_entity = CountDownEntity::new("countdown", self.duration);
+        if let Some(elapsed) = self.elapsed {
+            c_entity.set_elapsed(elapsed);
+        }
+        if let Some((grace, relock)) = self.grace {
+            c_entity.set_grace(grace, relock);
+        }
+        c_entity.set_on_expire(self.on_expire.clone());
+        c_entity.set_color_thresholds(self.countdown_colors);
+        c_entity.set_format(self.countdown_format);
+        c_entity.set_tenths(self.countdown_tenths);
+        c_entity.set_hidden(self.hide_timer);
+        c_entity.set_milestones(self.milestones.clone());
+        self.add_drawn(&mut controller, c_entity);
+
+        if let Some(style) = self.screensaver {
+            controller.add_entity(BaseEntity::new(AnimationEntity::new("screensaver", style)));
+        }
+
+        if let Some(pattern) = self.breathing {
+            controller.add_entity(BaseEntity::new(BreathingEntity::new("breathing", pattern)));
+        }
+
+        if let Some((mode, after)) = self.blank {
+            controller.add_entity(BaseEntity::new(BlankEntity::new("blank", mode, after)));
+        }
+
+        // Plugins and widgets stack up from the top of the footer, the contact
+        // lines take its last rows
+        for (row, command) in self.plugins.iter().enumerate() {
+            let mut plugin = PluginEntity::spawn(&row.to_string(), command, row as u16)
+                .with_context(|| format!("could not start plugin '{command}'"))?;
+            plugin.set_allow_unlock(!strict);
+            self.add_drawn(&mut controller, BaseEntity::new(plugin));
+        }
+        let mut footer_rows = self.plugins.len() as u16;
+
+        for (idx, command) in self.widgets.iter().enumerate() {
+            let widget = CommandOutputEntity::spawn(
+                &idx.to_string(),
+                command,
+                self.widget_refresh,
+                self.widget_timeout,
+                footer_rows,
+            );
+            self.add_drawn(&mut controller, widget);
+            footer_rows += 1;
+        }
+
+        if let Some(source) = self.calendar.take() {
+            let calendar =
+                CalendarEntity::spawn("calendar", source, Duration::from_secs(300), footer_rows);
+            self.add_drawn(&mut controller, calendar);
+            footer_rows += 1;
+        }
+
+        if let Some(addr) = &self.network_indicator {
+            let network = NetworkEntity::spawn("network", addr, footer_rows);
+            self.add_drawn(&mut controller, network);
+            footer_rows += 1;
+        }
+
+        if self.attempt_counter && !strict {
+            let threshold = self
+                .on_failed_attempt
+                .as_ref()
+                .map(|(_, threshold)| *threshold);
+            let counter = self.add_drawn(
+                &mut controller,
+                AttemptCounterEntity::new("attempts", footer_rows, self.failed_attempts, threshold),
+            );
+            footer_rows += 1;
+            controller.add_listener("failed_attempt", move |value| {
+                vec![ControlEvent {
+                    recipient: Recipient::Entity(counter),
+                    property_key: "failed_attempts".to_string(),
+                    property_value: value.to_string(),
+                }]
+            });
+        }
+
+        if self.input_activity {
+            let activity = self.add_drawn(
+                &mut controller,
+                InputActivityEntity::new("activity", footer_rows),
+            );
+            footer_rows += 1;
+            controller.add_listener("input_blocked", move |value| {
+                vec![ControlEvent {
+                    recipient: Recipient::Entity(activity),
+                    property_key: "blocked".to_string(),
+                    property_value: value.to_string(),
+                }]
+            });
+        }
+
+        let grab_warning = self.grab_warning.then(|| {
+            let warning = self.add_drawn(
+                &mut controller,
+                GrabWarningEntity::new("grab", footer_rows, grab_health::is_lost()),
+            );
+            footer_rows += 1;
+            warning
+        });
+
+        if !self.contact.is_empty() && !self.accessible {
+            let rows = self.contact.len() as u16;
+            controller.add_entity(BaseEntity::new(ContactEntity::new(
+                "contact",
+                self.contact.clone(),
+                footer_rows,
+            )));
+            footer_rows += rows;
+        }
+        layout::set_footer_height(footer_rows);
+
+        #[cfg(feature = "scripting")]
+        for (idx, path) in self.scripts.iter().enumerate() {
+            let mut script = crate::script_entity::ScriptEntity::from_file(&idx.to_string(), path)?;
+            script.set_allow_unlock(!strict);
+            self.add_drawn(&mut controller, script);
+        }
+
+        if self.layout_hint && !strict && !self.accessible {
+            controller.add_entity(KeyboardLayoutEntity::spawn("layout"));
+        }
+
+        let on_milestone = self.on_milestone.clone();
+        let hide_timer = self.hide_timer;
+        controller.add_listener("milestone", move |value| {
+            if let Some(hook) = &on_milestone {
+                hooks::run_milestone_hook(hook, value);
+            }
+            let Some(milestone) = value.parse::<Milestone>().ok().filter(|_| !hide_timer) else {
+                return Vec::new();
+            };
+            vec![ControlEvent {
+                recipient: Recipient::Entity(messages),
+                property_key: "info".to_string(),
+                property_value: milestone.announcement(),
+            }]
+        });
+
+        controller.add_listener("grab_failed", move |_| {
+            let mut events = vec![ControlEvent {
+                recipient: Recipient::Entity(messages),
+                property_key: "message".to_string(),
+                property_value: i18n::t("grab.failed").to_string(),
+            }];
+            if let Some(warning) = grab_warning {
+                events.push(ControlEvent {
+                    recipient: Recipient::Entity(warning),
+                    property_key: "lost".to_string(),
+                    property_value: "true".to_string(),
+                });
+            }
+            events
+        });
+
+        // From a changed config, see `config::watch`
+        controller.add_listener("feedback_duration", move |millis| {
+            vec![ControlEvent {
+                recipient: Recipient::Entity(messages),
+                property_key: "lifetime".to_string(),
+                property_value: millis.to_string(),
+            }]
+        });
+
+        let flash = self.blocked_input_cues.contains(&BlockedInputCue::Flash);
+        let bell = self
+            .blocked_input_cues
+            .contains(&BlockedInputCue::Bell)
+            .then(|| controller.add_entity(BellEntity::new("bell")));
+        if flash || bell.is_some() {
+            let mut last_cue: Option<Instant> = None;
+            controller.add_listener("input_blocked", move |_| {
+                if last_cue.is_some_and(|at| at.elapsed() < CUE_INTERVAL) {
+                    return Vec::new();
+                }
+                last_cue = Some(Instant::now());
+                let mut events = Vec::new();
+                if flash {
+                    events.push(ControlEvent {
+                        recipient: Recipient::Entity(messages),
+                        property_key: "info".to_string(),
+                        property_value: i18n::t("input.blocked").to_string(),
+                    });
+                }
+                if let Some(bell) = bell {
+                    events.push(ControlEvent {
+                        recipient: Recipient::Entity(bell),
+                        property_key: "ring".to_string(),
+                        property_value: String::new(),
+                    });
+                }
+                events
+            });
+        }
+
+        // In strict mode there is no way out but waiting for the countdown
+        match &self.auth {
+            Auth::Strict => {}
+            Auth::Challenge(challenge) => {
+                let mut challenge = ChallengeEntity::new("challenge", *challenge, messages);
+                challenge.set_failed_attempts(self.failed_attempts);
+                controller.add_entity(BaseEntity::new(challenge));
+            }
+            Auth::Password(credentials) | Auth::PartnerCode(credentials) => {
+                let mut p_entity =
+                    PasswordPromptEntity::new("password", prompt, credentials.clone(), messages);
+                p_entity.set_failed_attempts(self.failed_attempts);
+                p_entity.set_paste(self.paste);
+                p_entity.set_attempt_delay(self.attempt_delay);
+                for (failure, message) in &self.failure_messages {
+                    p_entity.set_failure_message(*failure, message);
+                }
+                if let Some(totp) = &self.second_factor {
+                    p_entity.set_second_factor(totp.clone());
+                }
+                if let Some(emergency) = &self.emergency {
+                    p_entity.set_emergency_phrase(
+                        &emergency.phrase,
+                        emergency.message.as_deref(),
+                        emergency.delay,
+                    );
+                }
+                #[cfg(feature = "presence")]
+                if let Some((_, crate::auth::presence::PresencePolicy::Guard)) = self.presence {
+                    p_entity.set_unlock_gate(crate::auth::presence::is_present);
+                }
+                let prompt = self.add_drawn(&mut controller, p_entity);
+                controller.add_listener("failure_message", move |value| {
+                    vec![ControlEvent {
+                        recipient: Recipient::Entity(prompt),
+                        property_key: "failure_message".to_string(),
+                        property_value: value.to_string(),
+                    }]
+                });
+            }
+        }
+
+        if !strict {
+            // Hints of the other ways to unlock go on consecutive rows
+            #[allow(unused_mut)]
+            let mut auth_rows = 0;
+
+            #[cfg(feature = "fido2")]
+            if self.security_key {
+                let security_key =
+                    crate::auth::fido2::SecurityKeyEntity::spawn("security_key", auth_rows)?;
+                self.add_drawn(&mut controller, security_key);
+                auth_rows += 1;
+            }
+
+            #[cfg(all(target_os = "linux", feature = "fprintd"))]
+            if self.fingerprint {
+                let fingerprint =
+                    crate::auth::fprintd::FingerprintEntity::spawn("fingerprint", auth_rows);
+                self.add_drawn(&mut controller, fingerprint);
+                auth_rows += 1;
+            }
+
+            #[cfg(feature = "presence")]
+            if let Some((source, policy)) = self.presence.take() {
+                let presence = crate::auth::presence::PresenceEntity::spawn(
+                    "presence", source, policy, auth_rows,
+                );
+                self.add_drawn(&mut controller, presence);
+                auth_rows += 1;
+            }
+            layout::set_auth_hint_height(auth_rows);
+        }
+
+        if let Some((data, label)) = self.qr_code.as_ref().filter(|_| !self.accessible) {
+            let qr_code = QrCodeEntity::new("qr", data, label.as_deref())?;
+            controller.add_entity(BaseEntity::new(qr_code));
+        }
+
+        if let Some((hook, threshold)) = self.on_failed_attempt.take() {
+            controller.add_listener("failed_attempt", move |value| {
+                if let Ok(attempt) = value.parse::<u32>() {
+                    if attempt >= threshold {
+                        hooks::run_failed_attempt_hook(&hook, attempt);
+                    }
+                }
+                Vec::new()
+            });
+        }
+
+        if let Some(hook) = self.on_emergency_unlock.take() {
+            controller.add_listener("emergency_unlock", move |value| {
+                hooks::run_emergency_hook(&hook, value);
+                Vec::new()
+            });
+        }
+
+        Ok(controller)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        password_prompt_entity::USER_CREDENTIAL,
+        password_source::Secret,
+        testing::{self, assert_golden},
+    };
+
+    fn default_lock() -> Controller {
+        let credentials = vec![Credential::new(USER_CREDENTIAL, Secret::plain("hunter2"))];
+        // Half a second in the countdown reads 24:59 for as long as the test runs
+        LockScreenBuilder::new(Duration::from_secs(25 * 60))
+            .elapsed(Duration::from_millis(500))
+            .auth(Auth::Password(credentials))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn default_layout() {
+        let _guard = testing::exclusive();
+        let frame = default_lock().render_to_string(80, 24).unwrap();
+        assert_golden("default_80x24", &frame);
+    }
+
+    #[test]
+    fn wrapped_title() {
+        let _guard = testing::exclusive();
+        let frame = default_lock().render_to_string(40, 12).unwrap();
+        assert_golden("wrapped_title_40x12", &frame);
+    }
+}
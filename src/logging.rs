@@ -0,0 +1,20 @@
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+
+use tracing::level_filters::LevelFilter;
+
+/// Install the audit log subscriber. Without a log file nothing is recorded,
+/// the terminal itself belongs to the lock UI while it is running.
+pub fn init(path: Option<&Path>, level: LevelFilter) -> anyhow::Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_max_level(level)
+        .with_ansi(false)
+        .with_target(false)
+        .try_init()
+        .map_err(|e| anyhow::anyhow!(e))?;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::{fs, io::Write, path::Path};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    layout::{self, LOGO_Y},
+    text,
+};
+
+/// Code page 437 glyphs for bytes 0x80..=0xFF, the encoding most ANSI art uses.
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+fn decode(bytes: &[u8]) -> String {
+    // Anything after an EOF marker is SAUCE metadata, not art
+    let bytes = bytes.split(|&b| b == 0x1a).next().unwrap_or_default();
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes
+            .iter()
+            .map(|&b| match b {
+                0x00..=0x7f => b as char,
+                _ => CP437_HIGH.chars().nth(b as usize - 0x80).unwrap_or('?'),
+            })
+            .collect(),
+    }
+}
+
+/// Length of an escape sequence starting at `chars[0]`, which must be ESC.
+fn escape_len(chars: &[char]) -> usize {
+    match chars.get(1) {
+        Some('[') => chars[2..]
+            .iter()
+            .position(|c| ('\u{40}'..='\u{7e}').contains(c))
+            .map_or(chars.len(), |end| end + 3),
+        Some(_) => 2,
+        None => 1,
+    }
+}
+
+fn visible_width(line: &str) -> usize {
+    let chars: Vec<char> = line.chars().collect();
+    let mut width = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\x1b' {
+            i += escape_len(&chars[i..]);
+        } else {
+            width += text::char_width(chars[i]);
+            i += 1;
+        }
+    }
+    width
+}
+
+/// Cut `line` down to `width` visible columns, keeping its escape sequences.
+fn truncate(line: &str, width: usize) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut visible = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\x1b' {
+            let len = escape_len(&chars[i..]);
+            out.extend(&chars[i..i + len]);
+            i += len;
+        } else {
+            visible += text::char_width(chars[i]);
+            if visible <= width {
+                out.push(chars[i]);
+            }
+            i += 1;
+        }
+    }
+    out
+}
+
+/// ASCII or ANSI art banner shown centered above the title.
+pub struct LogoEntity {
+    id: String,
+    lines: Vec<String>,
+    rows: Vec<(u16, String)>,
+    height_changed: bool,
+}
+
+impl LogoEntity {
+    pub fn from_file(id: &str, path: &Path) -> anyhow::Result<Self> {
+        let text = decode(&fs::read(path)?);
+        Ok(LogoEntity {
+            id: format!("LogoEntity-{id}"),
+            lines: text
+                .lines()
+                .map(|line| line.trim_end_matches('\r').to_string())
+                .collect(),
+            rows: Vec::new(),
+            height_changed: true,
+        })
+    }
+}
+
+impl Named for LogoEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for LogoEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        for (idx, (column, row)) in self.rows.iter().enumerate() {
+            draw_context
+                .out
+                .queue(MoveTo(0, LOGO_Y + idx as u16))?
+                .queue(Clear(ClearType::CurrentLine))?
+                .queue(MoveTo(*column, LOGO_Y + idx as u16))?
+                .queue(Print(row))?
+                .queue(SetAttribute(Attribute::Reset))?;
+        }
+        if self.height_changed {
+            // The title and everything below it moved
+            draw_context
+                .out
+                .queue(MoveTo(0, LOGO_Y + self.rows.len() as u16))?
+                .queue(Clear(ClearType::FromCursorDown))?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        let (width, height) = context.size;
+        // Never let the banner push the prompt off screen
+        let max_rows = (height / 2) as usize;
+        self.rows = self
+            .lines
+            .iter()
+            .take(max_rows)
+            .map(|line| {
+                let line = truncate(line, width as usize);
+                let column = (width as usize).saturating_sub(visible_width(&line)) / 2;
+                (column as u16, line)
+            })
+            .collect();
+        self.height_changed = layout::set_logo_height(self.rows.len() as u16);
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
@@ -1,27 +1,121 @@
+pub(crate) mod accessible;
+pub(crate) mod animation_entity;
+pub(crate) mod attempt_counter_entity;
+pub(crate) mod auth;
 pub(crate) mod base_entity;
+pub(crate) mod bell_entity;
+pub(crate) mod blank_entity;
+pub(crate) mod breathing_entity;
+pub(crate) mod bus;
+pub(crate) mod calendar_entity;
+pub(crate) mod challenge_entity;
+pub(crate) mod clipboard;
+pub(crate) mod command_output_entity;
+pub(crate) mod commands;
+pub(crate) mod config;
+pub(crate) mod contact_entity;
 pub(crate) mod controller;
 pub(crate) mod count_down_entity;
 pub(crate) mod entity;
-pub(crate) mod feedback_entity;
+#[cfg(target_os = "linux")]
+pub(crate) mod evdev;
+pub(crate) mod grab_health;
+pub(crate) mod grab_warning_entity;
+pub(crate) mod greeting_entity;
+pub(crate) mod history;
+pub(crate) mod hooks;
+pub(crate) mod i18n;
+#[cfg(target_os = "linux")]
+pub(crate) mod inhibit;
+pub(crate) mod input_activity_entity;
+#[cfg(unix)]
+pub(crate) mod ipc;
+pub(crate) mod keyboard_layout_entity;
+pub(crate) mod layout;
+pub(crate) mod lock_screen_builder;
+pub(crate) mod logging;
+pub(crate) mod logo_entity;
+pub(crate) mod markup;
+pub(crate) mod media;
+pub(crate) mod message_area_entity;
+#[cfg(feature = "metrics")]
+pub(crate) mod metrics;
+#[cfg(feature = "mqtt")]
+pub(crate) mod mqtt;
+pub(crate) mod network_entity;
+pub(crate) mod notify;
 pub(crate) mod password_prompt_entity;
+pub(crate) mod password_source;
+pub(crate) mod plugin;
+pub(crate) mod power;
+pub(crate) mod qr_code_entity;
+#[cfg(feature = "remote-unlock")]
+pub(crate) mod remote_unlock;
+pub(crate) mod schedule;
+pub(crate) mod screen;
+#[cfg(feature = "scripting")]
+pub(crate) mod script_entity;
+pub(crate) mod session;
+#[cfg(unix)]
+pub(crate) mod signals;
+pub(crate) mod state;
 pub(crate) mod static_text_entity;
+pub(crate) mod summary_entity;
+pub(crate) mod tasks;
+pub(crate) mod template;
+#[cfg(test)]
+pub(crate) mod testing;
+pub(crate) mod text;
+pub(crate) mod theme;
+pub(crate) mod totp;
+#[cfg(target_os = "linux")]
+pub(crate) mod ttys;
+#[cfg(unix)]
+pub(crate) mod watchdog;
 
 use std::{
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
     thread::{self},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use anyhow::Context;
 use clap::Parser;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use rand::Rng;
 use rdev::{grab, Button, Event as REvent, EventType, Key};
+use tracing::{error, info, level_filters::LevelFilter, warn};
 
 use crate::{
+    animation_entity::ScreensaverStyle,
     base_entity::BaseEntity,
-    controller::Controller,
-    count_down_entity::CountDownEntity,
-    entity::{Named, Visible},
-    feedback_entity::FeedbackEntity,
-    password_prompt_entity::PasswordPromptEntity,
-    static_text_entity::StaticTextEntity,
+    blank_entity::BlankMode,
+    breathing_entity::BreathingPattern,
+    calendar_entity::CalendarSource,
+    challenge_entity::Challenge,
+    config::Config,
+    controller::{Controller, RawMode, Recipient, Scene},
+    count_down_entity::{ColorThresholds, CountdownFormat, CountdownTenths, Milestone, OnExpire},
+    grab_health::Heartbeat,
+    i18n::Lang,
+    layout::{PromptAlign, PromptPosition},
+    lock_screen_builder::{Auth, BlockedInputCue, LockScreenBuilder, FEEDBACK_DURATION},
+    message_area_entity::FeedbackAnimation,
+    notify::Target,
+    password_prompt_entity::{Credential, Failure, USER_CREDENTIAL},
+    password_source::{PasswordSource, Secret},
+    schedule::Schedule,
+    session::InputKind,
+    static_text_entity::MessageSource,
+    summary_entity::SummaryEntity,
+    theme,
+    totp::Totp,
 };
 
 const EVENTS_TO_BLOCK: [EventType; 13] = [
@@ -40,23 +134,152 @@ const EVENTS_TO_BLOCK: [EventType; 13] = [
     EventType::ButtonPress(Button::Left),
 ];
 
-pub(crate) type Lines = [&'static str; 4];
+/// `EVENTS_TO_BLOCK` as evdev key codes, and whether the release is blocked
+/// too.
+#[cfg(target_os = "linux")]
+const EVDEV_KEYS_TO_BLOCK: [(u16, bool); 11] = [
+    (58, true),     // KEY_CAPSLOCK
+    (15, false),    // KEY_TAB
+    (125, false),   // KEY_LEFTMETA
+    (126, false),   // KEY_RIGHTMETA
+    (29, false),    // KEY_LEFTCTRL
+    (97, false),    // KEY_RIGHTCTRL
+    (46, false),    // KEY_C
+    (1, false),     // KEY_ESC
+    (56, false),    // KEY_LEFTALT
+    (100, false),   // KEY_RIGHTALT
+    (0x110, false), // BTN_LEFT
+];
 
-const LINES: Lines = [
-    "💻 This laptop has been intentionally left unattended.",
-    "🙅 Tampering with it is not appreciated.",
-    "🔒 Screen lock is active: Unlocks on timeout or correct password.",
-    "💖 Send love to: https://github.com/fa993/screenlock",
+/// `EVENTS_TO_BLOCK` by the names `[grab] allow` takes, in the order of
+/// `EVDEV_KEYS_TO_BLOCK`.
+const BLOCKED_KEY_NAMES: [&str; 11] = [
+    "CapsLock",
+    "Tab",
+    "MetaLeft",
+    "MetaRight",
+    "ControlLeft",
+    "ControlRight",
+    "KeyC",
+    "Escape",
+    "Alt",
+    "AltGr",
+    "MouseLeft",
 ];
 
-pub const COUNTDOWN_Y: u16 = 0;
-pub const TITLE_Y: u16 = COUNTDOWN_Y + 1;
-pub const PROMPT_Y: u16 = TITLE_Y + LINES.len() as u16 + 1; // titles length + 1 line gap
-pub const FEEDBACK_Y: u16 = PROMPT_Y + 1;
+/// Blocked keys `[grab] allow` lets through anyway.
+static ALLOWED_KEYS: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+
+/// Let the keys named in `names` through while locked.
+fn allow_keys(names: &[String]) {
+    let mut allowed = Vec::new();
+    for name in names {
+        match BLOCKED_KEY_NAMES
+            .iter()
+            .find(|blocked| blocked.eq_ignore_ascii_case(name))
+        {
+            Some(blocked) => allowed.push(*blocked),
+            None => warn!(key = name, "key in [grab] allow is never blocked anyway"),
+        }
+    }
+    if !allowed.is_empty() {
+        info!(keys = ?allowed, "letting blocked keys through");
+    }
+    *ALLOWED_KEYS.write().unwrap_or_else(|e| e.into_inner()) = allowed;
+}
+
+fn is_allowed(name: &str) -> bool {
+    ALLOWED_KEYS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&name)
+}
+
+/// The name `event_type` goes by in `BLOCKED_KEY_NAMES`.
+fn rdev_key_name(event_type: &EventType) -> String {
+    match event_type {
+        EventType::KeyPress(key) | EventType::KeyRelease(key) => format!("{key:?}"),
+        EventType::ButtonPress(button) | EventType::ButtonRelease(button) => {
+            format!("Mouse{button:?}")
+        }
+        _ => String::new(),
+    }
+}
+
+/// How long the evdev backend may go without a round before it counts as
+/// hung. Grabbing a device can wait up to a second for its keys.
+#[cfg(target_os = "linux")]
+const EVDEV_SILENCE: Duration = Duration::from_secs(10);
+
+/// What grabs keyboard and mouse.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum GrabBackend {
+    /// rdev, through X11
+    Rdev,
+    /// The input devices themselves, through evdev and uinput. Works on
+    /// Wayland and the console too, but needs root or the `input` group
+    Evdev,
+}
+
+/// Whether the grab currently blocks input. Between sessions it only watches
+/// for activity.
+static GRAB_ACTIVE: AtomicBool = AtomicBool::new(true);
+static LAST_ACTIVITY: Mutex<Option<Instant>> = Mutex::new(None);
 
-fn capture_control() {
-    let callback = |event: REvent| -> Option<REvent> {
-        if EVENTS_TO_BLOCK.contains(&event.event_type) {
+/// Count a key press or click the grab swallowed and tell the lock screen,
+/// with how many there were this session.
+fn report_blocked() {
+    let mut blocked = 0;
+    session::update_stats(|stats| {
+        stats.blocked_inputs += 1;
+        blocked = stats.blocked_inputs;
+    });
+    bus::publish_event(Recipient::Controller, "input_blocked", &blocked.to_string());
+}
+
+fn record_activity() {
+    *LAST_ACTIVITY.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+}
+
+fn idle_for() -> Duration {
+    LAST_ACTIVITY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .map(|t| t.elapsed())
+        .unwrap_or_default()
+}
+
+/// What kind of input `event_type` is, for the tamper log.
+fn rdev_input_kind(event_type: &EventType) -> Option<InputKind> {
+    match event_type {
+        EventType::KeyPress(_) => Some(InputKind::Key),
+        EventType::ButtonPress(_) => Some(InputKind::Click),
+        EventType::MouseMove { .. } | EventType::Wheel { .. } => Some(InputKind::Move),
+        _ => None,
+    }
+}
+
+fn capture_control(heartbeat: Heartbeat) -> anyhow::Result<()> {
+    // Without input there is nothing to beat on, a silent rdev grab is fine
+    let callback = move |event: REvent| -> Option<REvent> {
+        heartbeat.beat();
+        if !GRAB_ACTIVE.load(Ordering::Relaxed) {
+            record_activity();
+            return Some(event);
+        }
+        if let Some(kind) = rdev_input_kind(&event.event_type) {
+            session::record_input(kind);
+        }
+        if EVENTS_TO_BLOCK.contains(&event.event_type)
+            && !is_allowed(&rdev_key_name(&event.event_type))
+        {
+            if matches!(
+                event.event_type,
+                EventType::KeyPress(_) | EventType::ButtonPress(_)
+            ) {
+                report_blocked();
+            }
             None // CapsLock is now effectively disabled
         } else {
             // println!("Event: {:?}", event);
@@ -64,16 +287,92 @@ fn capture_control() {
         }
     };
     // This will block.
-    if let Err(error) = grab(callback) {
-        println!("Error: {:?}", error)
+    grab(callback).map_err(|error| anyhow::anyhow!("{error:?}"))
+}
+
+#[cfg(target_os = "linux")]
+fn capture_evdev(heartbeat: Heartbeat) -> anyhow::Result<()> {
+    let pass = |event: &evdev::InputEvent| -> bool {
+        if !GRAB_ACTIVE.load(Ordering::Relaxed) {
+            record_activity();
+            return true;
+        }
+        if event.typed() {
+            session::record_input(InputKind::Key);
+        } else if event.clicked() {
+            session::record_input(InputKind::Click);
+        } else if event.moved() {
+            session::record_input(InputKind::Move);
+        }
+        let blocked = |code: u16, release: bool| {
+            EVDEV_KEYS_TO_BLOCK
+                .iter()
+                .zip(BLOCKED_KEY_NAMES)
+                .any(|((key, on_release), name)| {
+                    *key == code && (*on_release || !release) && !is_allowed(name)
+                })
+        };
+        match (event.pressed(), event.released()) {
+            (Some(code), _) if blocked(code, false) => {
+                report_blocked();
+                false
+            }
+            (_, Some(code)) => !blocked(code, true),
+            _ => true,
+        }
+    };
+    // This will block, and picks up devices plugged in later itself. Each
+    // round beats, input or not.
+    let active = move || {
+        heartbeat.beat();
+        GRAB_ACTIVE.load(Ordering::Relaxed)
+    };
+    evdev::grab_all(active, pass)
+}
+
+/// Start the grab `args` ask for under `grab_health` supervision.
+fn start_grab(args: &Args) {
+    // For `grab_failed` and `input_blocked`
+    bus::enable();
+    #[cfg(target_os = "linux")]
+    if args.grab_backend == GrabBackend::Evdev {
+        grab_health::supervise(capture_evdev, Some(EVDEV_SILENCE), args.on_grab_lost);
+        return;
     }
+    // The rdev grab only covers the devices there are when it starts,
+    // elsewhere it is system wide. It can't beat without input, so only its
+    // thread ending is noticed
+    #[cfg(target_os = "linux")]
+    evdev::guard_new_devices(evdev::devices(), || GRAB_ACTIVE.load(Ordering::Relaxed));
+    grab_health::supervise(capture_control, None, args.on_grab_lost);
+}
+
+/// Put the terminal back and let input through before the panic message is
+/// printed. A panic on the UI thread then unwinds out of `main`, one anywhere
+/// else ends the process too: a lock missing its grab or a worker is no use.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        GRAB_ACTIVE.store(false, Ordering::Relaxed);
+        controller::restore_terminal();
+        #[cfg(target_os = "linux")]
+        inhibit::release();
+        error!(%info, "panicked");
+        default_hook(info);
+        if thread::current().name() != Some("main") {
+            std::process::exit(101);
+        }
+    }));
 }
 
-/// Parse strings like "30m", "1h", "20s" into Duration
+/// Parse strings like "30m", "1h", "20s", "500ms" into Duration
 fn parse_duration(s: &str) -> Result<Duration, String> {
     if let Some(stripped) = s.strip_suffix("h") {
         let hours: u64 = stripped.parse().map_err(|_| "Invalid hours")?;
         Ok(Duration::from_secs(hours * 3600))
+    } else if let Some(stripped) = s.strip_suffix("ms") {
+        let millis: u64 = stripped.parse().map_err(|_| "Invalid milliseconds")?;
+        Ok(Duration::from_millis(millis))
     } else if let Some(stripped) = s.strip_suffix("m") {
         let minutes: u64 = stripped.parse().map_err(|_| "Invalid minutes")?;
         Ok(Duration::from_secs(minutes * 60))
@@ -81,62 +380,1460 @@ fn parse_duration(s: &str) -> Result<Duration, String> {
         let secs: u64 = stripped.parse().map_err(|_| "Invalid seconds")?;
         Ok(Duration::from_secs(secs))
     } else {
-        Err("Duration must end with 'h', 'm', or 's'".into())
+        Err("Duration must end with 'h', 'm', 's' or 'ms'".into())
     }
 }
 
+/// Random code for partner unlocks. Avoids 'c', which can't be typed while
+/// locked, and characters that are easy to confuse.
+fn generate_unlock_code() -> String {
+    const ALPHABET: &[u8] = b"abdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    (0..8)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Stream the lock UI is drawn on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Output {
+    Stdout,
+    /// Keeps stdout free, e.g. when piping it into a log
+    Stderr,
+}
+
+impl Output {
+    fn execute(self, controller: &mut Controller) -> anyhow::Result<()> {
+        match self {
+            Output::Stdout => controller.execute(),
+            Output::Stderr => controller.execute_on(std::io::stderr()),
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Action {
+    /// Lock the screen, the same as giving no subcommand. The lock's flags may
+    /// follow it, e.g. `screenlock lock --profile break`
+    Lock,
+    /// Print the state of the running lock, e.g. for a status bar
+    Status {
+        /// Print JSON instead of a human readable line
+        #[arg(long)]
+        json: bool,
+    },
+    /// Summarize past lock sessions: today's breaks and streaks
+    History,
+    /// Choose a password and save its hash, used when LOCK_PASSWORD isn't set
+    SetPassword,
+    /// Register the FIDO2 security key plugged in, touching it then unlocks
+    #[cfg(feature = "fido2")]
+    RegisterKey,
+}
+
 /// Command line arguments
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(author, version, about, args_override_self = true)]
 struct Args {
+    #[command(subcommand)]
+    action: Option<Action>,
+
+    /// Don't lock; stream the running lock's state as waybar JSON lines to
+    /// stdout or the given FIFO every second
+    #[arg(long = "status-stream", num_args = 0..=1, default_missing_value = "-")]
+    status_stream: Option<PathBuf>,
+
     /// Duration for the timer (e.g. 30m, 1h, 20s)
     #[arg(long = "for", value_parser = parse_duration)]
     duration: Option<Duration>,
+
+    /// After the countdown ends, wait this long for a key press before unlocking
+    #[arg(long = "grace", value_parser = parse_duration)]
+    grace: Option<Duration>,
+
+    /// Restart the lock if the grace period expires without a key press
+    #[arg(long = "relock-after-grace", requires = "grace")]
+    relock_after_grace: bool,
+
+    /// What happens when the countdown runs out: unlock, lock (stay locked
+    /// until the password), suspend, hibernate, shutdown or command:<cmd>.
+    /// Suspend, hibernate and shutdown keep the lock on as well, the command
+    /// runs as it unlocks
+    #[arg(long = "on-expire", default_value = "unlock")]
+    on_expire: OnExpire,
+
+    /// Read settings from this TOML file instead of
+    /// ~/.config/screenlock/config.toml
+    #[arg(long = "config")]
+    config: Option<PathBuf>,
+
+    /// Lock with the flags in the config's `[profile.<name>]`, e.g. `break`.
+    /// Flags given here as well take precedence
+    #[arg(long = "profile")]
+    profile: Option<String>,
+
+    /// Why the machine is locked, e.g. "Lunch break, back at 13:30". Shown
+    /// above the message and included in the status and history
+    #[arg(long = "reason")]
+    reason: Option<String>,
+
+    /// Show the contents of this file instead of the default message
+    #[arg(long = "message-file", conflicts_with = "message_cmd")]
+    message_file: Option<PathBuf>,
+
+    /// Show the output of this shell command instead of the default message
+    #[arg(long = "message-cmd")]
+    message_cmd: Option<String>,
+
+    /// How often the message file or command is reloaded
+    #[arg(long = "message-refresh", value_parser = parse_duration, default_value = "60s")]
+    message_refresh: Duration,
+
+    /// Show this ASCII or ANSI art file centered above the message
+    #[arg(long = "banner")]
+    banner: Option<PathBuf>,
+
+    /// Greet you by name and time of day above the message, e.g. "Good
+    /// afternoon, Alex"; the config's [greeting] changes what it says
+    #[arg(long = "greeting")]
+    greeting: bool,
+
+    /// Don't accept a password at all, the lock only ends with the countdown
+    #[arg(
+        long = "strict",
+        conflicts_with_all = ["on_failed_attempt", "on_emergency_unlock", "emergency_message"]
+    )]
+    strict: bool,
+
+    /// Where the password comes from: env (LOCK_PASSWORD) or
+    /// keyring:<service> for the OS keyring (needs the keyring feature).
+    /// Without LOCK_PASSWORD it defaults to the hash saved by set-password
+    #[arg(long = "password-source")]
+    password_source: Option<PasswordSource>,
+
+    /// Read the password from this file descriptor, e.g. 3 with
+    /// `3< <(pass show screenlock)`
+    #[arg(long = "password-fd", conflicts_with_all = ["password_source", "password_file"])]
+    password_fd: Option<i32>,
+
+    /// Read the password from the first line of this file
+    #[arg(long = "password-file", conflicts_with = "password_source")]
+    password_file: Option<PathBuf>,
+
+    /// How long input is ignored after a wrong password, against rapid-fire
+    /// guessing
+    #[arg(long = "attempt-delay", value_parser = parse_duration, default_value = "500ms")]
+    attempt_delay: Duration,
+
+    /// Ignore pastes into the password prompt, the password has to be typed
+    #[arg(long = "no-paste", conflicts_with = "strict")]
+    no_paste: bool,
+
+    /// Unlock with a random code sent to an accountability partner instead of
+    /// the password (webhook:<url>, email:<address> or matrix:<room>@<server>)
+    #[arg(long = "partner-unlock", conflicts_with = "strict")]
+    partner_unlock: Option<Target>,
+
+    /// Points of the countdown announced on screen and to --on-milestone:
+    /// shares of the lock left or time left, comma separated
+    #[arg(
+        long = "milestones",
+        value_delimiter = ',',
+        default_value = "50%,1m,10s"
+    )]
+    milestones: Vec<Milestone>,
+
+    /// Where the countdown turns yellow, red and starts blinking, as shares
+    /// of the lock or time left
+    #[arg(long = "countdown-colors", default_value = "50%,20%,1m")]
+    countdown_colors: ColorThresholds,
+
+    /// How the time left is shown
+    #[arg(long = "countdown-format", value_enum, default_value = "clock")]
+    countdown_format: CountdownFormat,
+
+    /// When to show tenths of a second: never, final (the last ten seconds)
+    /// or always
+    #[arg(long = "countdown-tenths", value_enum, default_value = "never")]
+    countdown_tenths: CountdownTenths,
+
+    /// Show a static "locked" indicator instead of the time left, and don't
+    /// announce milestones on screen. The lock still expires
+    #[arg(long = "hide-timer")]
+    hide_timer: bool,
+
+    /// Don't announce any countdown milestones
+    #[arg(long = "no-milestones", conflicts_with_all = ["milestones", "on_milestone"])]
+    no_milestones: bool,
+
+    /// Shell command run at each countdown milestone, which is passed in
+    /// SCREENLOCK_MILESTONE (e.g. 50% or 1m)
+    #[arg(long = "on-milestone")]
+    on_milestone: Option<String>,
+
+    /// Unlock early by solving arithmetic problems instead of entering the
+    /// password: math[:count[:easy|medium|hard]], e.g. math:3:hard
+    #[arg(long = "challenge", conflicts_with_all = ["strict", "partner_unlock"])]
+    challenge: Option<Challenge>,
+
+    /// How messages such as a wrong password catch the eye
+    #[arg(long = "feedback-animation", value_enum, default_value = "none")]
+    feedback_animation: FeedbackAnimation,
+
+    /// Animate the unused part of the screen while locked
+    #[arg(long = "screensaver", value_enum)]
+    screensaver: Option<ScreensaverStyle>,
+
+    /// Blank the display once no key was pressed for this long, any key
+    /// wakes it (see --blank-mode)
+    #[arg(long = "blank-after", value_parser = parse_duration)]
+    blank_after: Option<Duration>,
+
+    /// How --blank-after blanks the display
+    #[arg(
+        long = "blank-mode",
+        value_enum,
+        default_value = "off",
+        requires = "blank_after"
+    )]
+    blank_mode: BlankMode,
+
+    /// Run this command as a widget plugin speaking JSON lines over stdio,
+    /// can be given more than once
+    #[arg(long = "plugin")]
+    plugins: Vec<String>,
+
+    /// Show the output of this shell command below the lock, e.g. "acpi -b",
+    /// can be given more than once
+    #[arg(long = "widget")]
+    widgets: Vec<String>,
+
+    /// How often widget commands are run again
+    #[arg(long = "widget-refresh", value_parser = parse_duration, default_value = "10s")]
+    widget_refresh: Duration,
+
+    /// How long a widget command may take before it is killed
+    #[arg(long = "widget-timeout", value_parser = parse_duration, default_value = "2s")]
+    widget_timeout: Duration,
+
+    /// Show the next event from this iCalendar file below the lock
+    #[arg(long = "calendar", conflicts_with = "calendar_cmd")]
+    calendar: Option<PathBuf>,
+
+    /// Show the first line this command prints as the next event, e.g.
+    /// "khal list now 1d"
+    #[arg(long = "calendar-cmd")]
+    calendar_cmd: Option<String>,
+
+    /// Show whether the machine is online, checked by connecting to this
+    /// host:port now and then (defaults to 1.1.1.1:53)
+    #[arg(
+        long = "network-indicator",
+        num_args = 0..=1,
+        default_missing_value = "1.1.1.1:53"
+    )]
+    network_indicator: Option<String>,
+
+    /// Count failed unlock attempts at the bottom of the screen, turning
+    /// red as they reach --failed-attempt-threshold
+    #[arg(long = "show-attempts", conflicts_with = "strict")]
+    show_attempts: bool,
+
+    /// Load an entity written in Rhai from this file, can be given more than
+    /// once
+    #[cfg(feature = "scripting")]
+    #[arg(long = "script")]
+    scripts: Vec<PathBuf>,
+
+    /// Guide a breathing exercise while locked, seconds to breathe
+    /// in:hold:out[:hold] (defaults to 4:4:4:4)
+    #[arg(
+        long = "breathing",
+        num_args = 0..=1,
+        default_missing_value = "4:4:4:4",
+        conflicts_with = "screensaver"
+    )]
+    breathing: Option<BreathingPattern>,
+
+    /// Screen reader friendly output: plain lines instead of a screen redrawn
+    /// in place, announcing the time left once a minute
+    #[arg(
+        long = "accessible",
+        conflicts_with_all = ["banner", "screensaver", "breathing", "challenge"]
+    )]
+    accessible: bool,
+
+    /// Replace emoji with ASCII and use a high-contrast palette, for fonts
+    /// that show emoji as boxes or misalign them
+    #[arg(long = "ascii", visible_alias = "no-emoji")]
+    ascii: bool,
+
+    /// Language of the lock screen, detected from the locale by default
+    #[arg(long = "lang", value_enum)]
+    lang: Option<Lang>,
+
+    /// Don't show the active keyboard layout below the prompt
+    #[arg(long = "no-layout-hint")]
+    no_layout_hint: bool,
+
+    /// Where the prompt goes on the screen
+    #[arg(long = "prompt-position", value_enum, default_value = "below-title")]
+    prompt_position: PromptPosition,
+
+    /// Rows to move the prompt down from --prompt-position, or up if negative
+    #[arg(
+        long = "prompt-offset",
+        default_value_t = 0,
+        allow_negative_numbers = true
+    )]
+    prompt_offset: i16,
+
+    /// How the prompt is aligned: start, center or end of the line
+    #[arg(long = "prompt-align", value_enum, default_value = "start")]
+    prompt_align: PromptAlign,
+
+    /// Show this as a QR code on the lock screen, e.g. guest Wi-Fi
+    /// credentials or a contact URL (overrides the [qr] config section)
+    #[arg(long = "qr")]
+    qr: Option<String>,
+
+    /// Publish the lock state to and take commands from this MQTT broker
+    /// (host or host:port)
+    #[cfg(feature = "mqtt")]
+    #[arg(long = "mqtt-broker")]
+    mqtt_broker: Option<String>,
+
+    /// Topic prefix: state goes to <prefix>/state, commands are read from
+    /// <prefix>/command
+    #[cfg(feature = "mqtt")]
+    #[arg(long = "mqtt-topic", default_value = "screenlock")]
+    mqtt_topic: String,
+
+    /// Serve Prometheus metrics on this address, e.g. 127.0.0.1:9184
+    #[cfg(feature = "metrics")]
+    #[arg(long = "metrics-addr")]
+    metrics_addr: Option<std::net::SocketAddr>,
+
+    /// Serve one-time unlock links on this address, e.g. 0.0.0.0:8765. Every
+    /// lock gets a new link, which unlocks it the first time it is opened
+    #[cfg(feature = "remote-unlock")]
+    #[arg(long = "remote-unlock-addr", conflicts_with = "strict")]
+    remote_unlock_addr: Option<std::net::SocketAddr>,
+
+    /// Base of the unlock links, e.g. https://laptop.example.org when behind a
+    /// proxy (defaults to http://<address>)
+    #[cfg(feature = "remote-unlock")]
+    #[arg(long = "remote-unlock-url", requires = "remote_unlock_addr")]
+    remote_unlock_url: Option<String>,
+
+    /// Send each lock's unlock link here, like --partner-unlock
+    #[cfg(feature = "remote-unlock")]
+    #[arg(long = "remote-unlock-notify", requires = "remote_unlock_addr")]
+    remote_unlock_notify: Option<Target>,
+
+    /// Show the unlock link as a QR code on the lock screen, instead of the
+    /// configured one
+    #[cfg(feature = "remote-unlock")]
+    #[arg(long = "remote-unlock-qr", requires = "remote_unlock_addr")]
+    remote_unlock_qr: bool,
+
+    /// Where completed sessions are recorded (defaults to
+    /// <data dir>/screenlock/history.jsonl)
+    #[arg(long = "history")]
+    history: Option<PathBuf>,
+
+    /// Don't record completed sessions
+    #[arg(long = "no-history", conflicts_with = "history")]
+    no_history: bool,
+
+    /// After unlocking, show a short summary of the session for a few seconds
+    #[arg(long = "show-summary")]
+    show_summary: bool,
+
+    /// Append an audit trail of the lock session to this file
+    #[arg(long = "log-file")]
+    log_file: Option<PathBuf>,
+
+    /// Minimum level recorded in the log file (error, warn, info, debug, trace)
+    #[arg(long = "log-level", default_value = "info")]
+    log_level: LevelFilter,
+
+    /// Run the lock UI without grabbing keyboard and mouse, for development
+    /// and demos inside tmux, SSH or CI
+    #[arg(long = "no-grab", visible_alias = "dry-run")]
+    no_grab: bool,
+
+    /// What to do when the input grab is lost for good, after it was started
+    /// again a few times: warn, or end the running session
+    #[arg(long = "on-grab-lost", value_enum, default_value = "warn")]
+    on_grab_lost: grab_health::OnGrabLost,
+
+    /// Show that the machine is locked when the grab swallows a key or
+    /// click: flash (a message below the prompt) and/or bell, e.g. "flash,bell"
+    #[arg(long = "on-blocked-input", value_enum, value_delimiter = ',')]
+    on_blocked_input: Vec<BlockedInputCue>,
+
+    /// Count the key presses and clicks the grab swallowed during the lock
+    /// on a footer row, to see whether someone tried the machine
+    #[arg(long = "show-blocked-input", conflicts_with = "no_grab")]
+    show_blocked_input: bool,
+
+    /// Pause media players that are playing when the lock engages (MPRIS on
+    /// Linux, Music and Spotify on macOS)
+    #[arg(long = "pause-media")]
+    pause_media: bool,
+
+    /// Resume the players --pause-media paused once unlocked
+    #[arg(long = "resume-media", requires = "pause_media")]
+    resume_media: bool,
+
+    /// Block escape routes while locked: vt (virtual terminal switching,
+    /// needs root) and sleep (suspend and the lid switch), e.g. "vt,sleep"
+    #[cfg(target_os = "linux")]
+    #[arg(long = "inhibit", value_enum, value_delimiter = ',')]
+    inhibit: Vec<inhibit::Inhibit>,
+
+    /// Also lock the other virtual consoles you are logged in on, for when
+    /// VT switching can't be inhibited
+    #[cfg(target_os = "linux")]
+    #[arg(long = "lock-ttys", conflicts_with = "partner_unlock")]
+    lock_ttys: bool,
+
+    /// Run only the lock screen for this long, on a console --lock-ttys
+    /// started us on
+    #[cfg(target_os = "linux")]
+    #[arg(long = "tty-child", value_parser = parse_duration, hide = true)]
+    tty_child: Option<Duration>,
+
+    /// What grabs keyboard and mouse: rdev only works under X11, evdev also
+    /// on Wayland and the console
+    #[cfg(target_os = "linux")]
+    #[arg(long = "grab-backend", value_enum, default_value = "rdev")]
+    grab_backend: GrabBackend,
+
+    /// Also unlock with a fingerprint enrolled in fprintd, verified alongside
+    /// the password prompt
+    #[cfg(all(target_os = "linux", feature = "fprintd"))]
+    #[arg(long = "fingerprint")]
+    fingerprint: bool,
+
+    /// Check whether you are nearby: `bluetooth:<address>` for a paired
+    /// phone being connected, or `command:<command>` exiting 0 when you are
+    /// there and 1 when not, e.g. a webcam face detector
+    #[cfg(feature = "presence")]
+    #[arg(long = "presence")]
+    presence: Option<auth::presence::PresenceSource>,
+
+    /// What your presence does: end the lock once you are back after being
+    /// away, or refuse to unlock while you are away
+    #[cfg(feature = "presence")]
+    #[arg(
+        long = "presence-policy",
+        value_enum,
+        default_value = "unlock",
+        requires = "presence"
+    )]
+    presence_policy: auth::presence::PresencePolicy,
+
+    /// Continue a lock that was interrupted, e.g. by a crash, with the time
+    /// it had left; locks as usual if there is none
+    #[arg(long = "resume")]
+    resume: bool,
+
+    /// Run the lock in a session of its own with a watchdog that relaunches
+    /// it, with the time it had left, if it gets killed. Not with
+    /// --password-fd, a relaunched lock would find the descriptor drained
+    #[cfg(unix)]
+    #[arg(long = "exec-shield", conflicts_with_all = ["screenshot", "password_fd"])]
+    exec_shield: bool,
+
+    /// What SIGTERM does: exit ends the lock and quits, ignore carries on
+    /// (the default with --strict)
+    #[cfg(unix)]
+    #[arg(long = "on-sigterm", value_enum)]
+    on_sigterm: Option<signals::SignalAction>,
+
+    /// What SIGINT does, like --on-sigterm
+    #[cfg(unix)]
+    #[arg(long = "on-sigint", value_enum)]
+    on_sigint: Option<signals::SignalAction>,
+
+    /// What SIGTSTP (Ctrl+Z) does, ignored by default so the lock can't be
+    /// suspended
+    #[cfg(unix)]
+    #[arg(long = "on-sigtstp", value_enum)]
+    on_sigtstp: Option<signals::SignalAction>,
+
+    /// Where to draw the lock UI
+    #[arg(long = "output", value_enum, default_value = "stdout")]
+    output: Output,
+
+    /// Don't lock; write the first frame of the lock UI as plain text to
+    /// this file (- for stdout)
+    #[arg(long = "screenshot")]
+    screenshot: Option<PathBuf>,
+
+    /// Count down this long before locking, Esc cancels (e.g. 5s)
+    #[arg(long = "arm-delay", value_parser = parse_duration)]
+    arm_delay: Option<Duration>,
+
+    /// Stay resident and lock on a schedule, e.g. "50m work / 10m lock" or
+    /// ":50 for 10m, 12:30 for 45m"
+    #[arg(long = "schedule")]
+    schedule: Option<Schedule>,
+
+    /// Announce scheduled locks this long before they start (0s to disable)
+    #[arg(long = "warn-before", value_parser = parse_duration, default_value = "60s")]
+    warn_before: Duration,
+
+    /// After unlocking, lock again once there was no keyboard or mouse
+    /// activity for this long
+    #[arg(long = "relock-if-idle", value_parser = parse_duration)]
+    relock_if_idle: Option<Duration>,
+
+    /// Shell command run after failed unlock attempts; the attempt count and
+    /// time are passed in SCREENLOCK_FAILED_ATTEMPTS and SCREENLOCK_TIMESTAMP
+    #[arg(long = "on-failed-attempt")]
+    on_failed_attempt: Option<String>,
+
+    /// Number of failed attempts before the failed-attempt hook starts running
+    #[arg(long = "failed-attempt-threshold", default_value_t = 1)]
+    failed_attempt_threshold: u32,
+
+    /// Shell command run when the LOCK_EMERGENCY_PHRASE is used to unlock
+    #[arg(long = "on-emergency-unlock")]
+    on_emergency_unlock: Option<String>,
+
+    /// Message shown after the emergency phrase is entered
+    #[arg(long = "emergency-message")]
+    emergency_message: Option<String>,
+
+    /// How long the emergency message stays up before unlocking
+    #[arg(long = "emergency-delay", value_parser = parse_duration, default_value = "10s")]
+    emergency_delay: Duration,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+/// Runs the `--arm-delay` countdown in raw mode, returns true on Esc or Ctrl+C.
+fn arm_cancelled(deadline: Instant) -> anyhow::Result<bool> {
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Ok(false);
+        }
+        let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+        print!("\rLocking in {secs}… (Esc to cancel) ");
+        std::io::stdout().flush()?;
+        // Wake up when the displayed second changes
+        let until_next = match left.subsec_nanos() {
+            0 => Duration::from_secs(1),
+            nanos => Duration::from_nanos(nanos as u64),
+        };
+        if !event::poll(until_next)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            let ctrl_c =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if key.code == KeyCode::Esc || ctrl_c {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Count down `delay` before the grab engages, so an accidental launch can be
+/// backed out of. Returns false if cancelled.
+fn arm(delay: Duration) -> anyhow::Result<bool> {
+    let raw_mode = RawMode::enable()?;
+    let cancelled = arm_cancelled(Instant::now() + delay);
+    drop(raw_mode);
+    println!();
+    Ok(!cancelled?)
+}
 
-    let countdown = args.duration.unwrap_or(Duration::from_secs(30));
+/// Why the next lock session starts and, if not `--for`, how long it lasts.
+struct LockRequest {
+    reason: String,
+    duration: Option<Duration>,
+    /// Where an interrupted lock left off
+    resumed: Option<state::LockState>,
+}
 
-    let mut correct_password =
-        std::env::var("LOCK_PASSWORD").unwrap_or_else(|_| "password".to_string());
+impl LockRequest {
+    fn new(reason: &str, duration: Option<Duration>) -> Self {
+        LockRequest {
+            reason: reason.to_string(),
+            duration,
+            resumed: None,
+        }
+    }
 
-    correct_password = correct_password.trim().to_lowercase();
+    fn resume(state: state::LockState) -> Self {
+        LockRequest {
+            reason: "resumed".to_string(),
+            duration: Some(Duration::from_secs(state.total_secs)),
+            resumed: Some(state),
+        }
+    }
 
-    let mut controller = Controller::new();
+    fn failed_attempts(&self) -> u32 {
+        self.resumed
+            .as_ref()
+            .map_or(0, |state| state.failed_attempts)
+    }
+}
 
-    controller.add_entity(BaseEntity::new(StaticTextEntity::new("title", LINES)));
+/// Block until nobody touched the keyboard or mouse for `window`, with the
+/// grab only observing input in the meantime.
+fn wait_until_idle(window: Duration) -> LockRequest {
+    GRAB_ACTIVE.store(false, Ordering::Relaxed);
+    record_activity();
+    println!("Unlocked. Locking again after {window:?} without keyboard or mouse activity.");
+    while idle_for() < window {
+        if let Some((duration, trigger)) = commands::take_lock() {
+            GRAB_ACTIVE.store(true, Ordering::Relaxed);
+            info!(%trigger, "locking on command");
+            return LockRequest::new(&trigger.to_string(), duration);
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+    GRAB_ACTIVE.store(true, Ordering::Relaxed);
+    info!(idle = ?window, "re-locking after inactivity");
+    LockRequest::new("idle", None)
+}
 
-    controller.add_entity(BaseEntity::new(CountDownEntity::new(
-        "countdown",
-        countdown,
-    )));
+/// Stay unlocked, passing input through, until a command source asks to lock.
+fn wait_for_lock_command() -> LockRequest {
+    GRAB_ACTIVE.store(false, Ordering::Relaxed);
+    println!("Waiting for the next lock.");
+    let (duration, trigger) = commands::wait_for_lock(Duration::from_millis(500));
+    GRAB_ACTIVE.store(true, Ordering::Relaxed);
+    info!(%trigger, "locking on command");
+    LockRequest::new(&trigger.to_string(), duration)
+}
+
+#[cfg(unix)]
+fn print_status(json: bool) -> anyhow::Result<()> {
+    let status = ipc::query_status()?.unwrap_or_else(ipc::Status::unlocked);
+    if json {
+        println!("{}", serde_json::to_string(&status)?);
+    } else if status.locked {
+        let secs = status.remaining_secs;
+        println!(
+            "locked ({}), {:02}:{:02} remaining, {} failed attempts",
+            status.reason,
+            secs / 60,
+            secs % 60,
+            status.failed_attempts
+        );
+        if let Some(note) = &status.note {
+            println!("{note}");
+        }
+    } else {
+        println!("unlocked");
+    }
+    Ok(())
+}
 
-    let mut f_entity = FeedbackEntity::new(
-        "feedback",
-        "❌ Wrong password, try again.",
-        Duration::from_secs(2),
+#[cfg(not(unix))]
+fn print_status(_json: bool) -> anyhow::Result<()> {
+    anyhow::bail!("status is only available on unix systems")
+}
+
+fn history_path(args: &Args) -> Option<PathBuf> {
+    if args.no_history {
+        return None;
+    }
+    args.history.clone().or_else(history::default_path)
+}
+
+fn print_history(args: &Args) -> anyhow::Result<()> {
+    let Some(path) = args.history.clone().or_else(history::default_path) else {
+        anyhow::bail!("no history location, pass --history");
+    };
+    let records = history::load(&path)?;
+    let summary = history::summarize(&records, chrono::Local::now().date_naive());
+    let locked = summary.locked_today.as_secs();
+    println!(
+        "Today: {} breaks, {}h {:02}m locked, {} failed attempts",
+        summary.sessions_today,
+        locked / 3600,
+        (locked % 3600) / 60,
+        summary.failed_attempts_today
+    );
+    println!(
+        "Streak: {} days (longest {} days)",
+        summary.current_streak, summary.longest_streak
     );
+    println!("Total: {} breaks", summary.sessions_total);
+    Ok(())
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// Close the session that just ended and record it in `history`.
+fn finish_session(history: Option<&Path>) -> history::Record {
+    #[cfg(feature = "remote-unlock")]
+    remote_unlock::disarm();
+    let record = history::Record::finished(&session::stats());
+    session::end();
+    for burst in &record.input {
+        info!(
+            start = %burst.start.format("%H:%M:%S"),
+            end = %burst.end.format("%H:%M:%S"),
+            keys = burst.keys,
+            clicks = burst.clicks,
+            moves = burst.moves,
+            "input during the lock"
+        );
+    }
+    if let Some(path) = history {
+        if let Err(error) = history::append(path, &record) {
+            warn!(?error, path = %path.display(), "could not record session history");
+        }
+    }
+    record
+}
+
+/// When there was input during the lock, the last few bursts spelled out.
+fn input_summary(bursts: &[session::InputBurst]) -> Option<String> {
+    const SHOWN: usize = 3;
+    if bursts.is_empty() {
+        return None;
+    }
+    let mut times: Vec<String> = bursts
+        .iter()
+        .rev()
+        .take(SHOWN)
+        .rev()
+        .map(|burst| {
+            let kinds: Vec<String> = [
+                (burst.keys, "keys"),
+                (burst.clicks, "clicks"),
+                (burst.moves, "mouse moves"),
+            ]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, kind)| format!("{count} {kind}"))
+            .collect();
+            format!("{} ({})", burst.start.format("%H:%M"), kinds.join(", "))
+        })
+        .collect();
+    if bursts.len() > SHOWN {
+        times.insert(0, format!("{} earlier", bursts.len() - SHOWN));
+    }
+    Some(format!("Input while locked: {}", times.join(", ")))
+}
+
+/// A scene briefly showing how the session that produced `record` went.
+fn summary_scene(record: &history::Record, history: Option<&Path>) -> anyhow::Result<Scene> {
+    let secs = record.duration_secs;
+    let mut details = vec![format!("Locked for {:02}:{:02}", secs / 60, secs % 60)];
+    details.push(match record.failed_attempts {
+        1 => "1 failed attempt".to_string(),
+        n => format!("{n} failed attempts"),
+    });
+    if let Some(path) = history {
+        let records = history::load(path)?;
+        let summary = history::summarize(&records, chrono::Local::now().date_naive());
+        details.push(format!("{} break today", ordinal(summary.sessions_today)));
+    }
+
+    let mut lines = vec!["🔓 Unlocked".to_string(), details.join(", ")];
+    if let Some(input) = input_summary(&record.input) {
+        lines.push(input);
+    }
+
+    let mut scene = Scene::new("summary");
+    scene.add_entity(BaseEntity::new(SummaryEntity::new(
+        "summary",
+        lines,
+        Duration::from_secs(5),
+    )));
+    Ok(scene)
+}
+
+#[cfg(unix)]
+fn stream_status(path: &Path) -> anyhow::Result<()> {
+    if path == Path::new("-") {
+        ipc::stream_status(std::io::stdout().lock())
+    } else {
+        // Opening a FIFO blocks until the bar starts reading
+        ipc::stream_status(std::fs::OpenOptions::new().write(true).open(path)?)
+    }
+}
+
+#[cfg(not(unix))]
+fn stream_status(_path: &Path) -> anyhow::Result<()> {
+    anyhow::bail!("status is only available on unix systems")
+}
+
+fn lock_duration(args: &Args, request: &LockRequest) -> Duration {
+    request
+        .duration
+        .or(args.duration)
+        .unwrap_or(Duration::from_secs(30))
+}
+
+/// What unlocks a session: `password` and the `extra` ones, or with a partner
+/// code in `password` that code alone, so only the partner can unlock early.
+fn session_credentials(password: Secret, extra: &[Credential], partner: bool) -> Vec<Credential> {
+    let extra = if partner { &[][..] } else { extra };
+    std::iter::once(Credential::new(USER_CREDENTIAL, password))
+        .chain(extra.iter().cloned())
+        .collect()
+}
+
+/// Set up the entities and listeners of one lock session.
+fn build_controller(
+    args: &Args,
+    config: &Config,
+    request: &LockRequest,
+    credentials: &[Credential],
+    emergency_phrase: Option<&str>,
+    totp: Option<&Totp>,
+) -> anyhow::Result<Controller> {
+    let auth = match (&args.challenge, &args.partner_unlock) {
+        _ if args.strict => Auth::Strict,
+        (Some(challenge), _) => Auth::Challenge(*challenge),
+        (None, Some(_)) => Auth::PartnerCode(credentials.to_vec()),
+        (None, None) => Auth::Password(credentials.to_vec()),
+    };
+    let duration = lock_duration(args, request);
+    let elapsed = request
+        .resumed
+        .as_ref()
+        .map_or(Duration::ZERO, |state| state.elapsed());
+    template::start(duration.saturating_sub(elapsed), args.reason.as_deref());
+    let mut builder = LockScreenBuilder::new(duration)
+        .auth(auth)
+        .failed_attempts(request.failed_attempts())
+        .paste(!args.no_paste)
+        .attempt_counter(args.show_attempts)
+        .grab_warning(!args.no_grab)
+        .blocked_input_cues(args.on_blocked_input.clone())
+        .input_activity(args.show_blocked_input)
+        .attempt_delay(args.attempt_delay)
+        .countdown_colors(args.countdown_colors)
+        .countdown_format(args.countdown_format)
+        .countdown_tenths(args.countdown_tenths)
+        .hide_timer(args.hide_timer)
+        .feedback_animation(args.feedback_animation)
+        .contact(
+            config
+                .contact
+                .lines
+                .iter()
+                .map(|line| template::expand(line).into_owned())
+                .collect(),
+        )
+        .ascii(args.ascii || config.theme.ascii)
+        .accessible(args.accessible)
+        .widget_timing(args.widget_refresh, args.widget_timeout)
+        .layout_hint(!args.no_layout_hint)
+        .prompt_layout(args.prompt_position, args.prompt_offset, args.prompt_align);
+
+    if let Some(state) = &request.resumed {
+        builder = builder.elapsed(state.elapsed());
+    }
+    for (failure, message) in failure_messages(&config.feedback) {
+        if let Some(message) = message {
+            builder = builder.failure_message(failure, template::expand(message).into_owned());
+        }
+    }
+    if let Some(duration) = config.feedback.duration {
+        builder = builder.feedback_duration(duration);
+    }
+    if let Some(grace) = args.grace {
+        builder = builder.grace(grace, args.relock_after_grace);
+    }
+    builder = builder.on_expire(args.on_expire.clone());
+    if !args.no_milestones {
+        builder = builder.milestones(args.milestones.clone());
+    }
+    if let Some(totp) = totp {
+        builder = builder.second_factor(totp.clone());
+    }
+    if let Some(phrase) = emergency_phrase {
+        builder = builder.emergency_phrase(
+            phrase,
+            args.emergency_message.as_deref(),
+            args.emergency_delay,
+        );
+    }
+    #[cfg(feature = "fido2")]
+    {
+        builder = builder.security_key(auth::fido2::has_keys());
+    }
+    #[cfg(all(target_os = "linux", feature = "fprintd"))]
+    {
+        builder = builder.fingerprint(args.fingerprint);
+    }
+    #[cfg(feature = "presence")]
+    if let Some(source) = &args.presence {
+        builder = builder.presence(source.clone(), args.presence_policy);
+    }
+
+    match (&args.message_file, &args.message_cmd) {
+        (Some(path), _) => {
+            builder = builder.messages(MessageSource::File(path.clone()), args.message_refresh)
+        }
+        (None, Some(cmd)) => {
+            builder = builder.messages(MessageSource::Command(cmd.clone()), args.message_refresh)
+        }
+        (None, None) => {}
+    }
+    if let Some(reason) = &args.reason {
+        builder = builder.reason(reason);
+    }
+
+    if let Some(path) = &args.banner {
+        builder = builder.banner(path.clone());
+    }
+    if args.greeting {
+        let templates = config.greeting.templates.iter();
+        builder = builder.greeting(templates.map(|t| (t.from, t.text.clone())).collect());
+    }
+    if let Some(after) = args.blank_after {
+        builder = builder.blank(args.blank_mode, after);
+    }
+    if let Some(style) = args.screensaver {
+        builder = builder.screensaver(style);
+    }
+    if let Some(pattern) = args.breathing {
+        builder = builder.breathing(pattern);
+    }
+    for command in &args.plugins {
+        builder = builder.plugin(command);
+    }
+    for command in &args.widgets {
+        builder = builder.widget(command);
+    }
+    match (&args.calendar, &args.calendar_cmd) {
+        (Some(path), _) => builder = builder.calendar(CalendarSource::Ics(path.clone())),
+        (None, Some(cmd)) => builder = builder.calendar(CalendarSource::Command(cmd.clone())),
+        (None, None) => {}
+    }
+    if let Some(addr) = &args.network_indicator {
+        builder = builder.network_indicator(addr);
+    }
+    #[cfg(feature = "scripting")]
+    for path in &args.scripts {
+        builder = builder.script(path.clone());
+    }
 
-    let p_entity = BaseEntity::new(PasswordPromptEntity::new(
-        "password",
-        "Enter password: ",
-        correct_password.as_str(),
-        f_entity.get_name(),
-    ));
+    // A requested unlock link takes the place of the configured code
+    #[cfg(feature = "remote-unlock")]
+    let remote_link = remote_unlock::link()
+        .filter(|_| args.remote_unlock_qr)
+        .map(|link| (link, Some(i18n::t("qr.remote_unlock").to_string())));
+    #[cfg(not(feature = "remote-unlock"))]
+    let remote_link = None;
+    let qr_code = remote_link.or_else(|| {
+        let data = args.qr.clone().or_else(|| config.qr.data.clone())?;
+        Some((data, config.qr.label.clone()))
+    });
+    if let Some((data, label)) = qr_code {
+        builder = builder.qr_code(data, label);
+    }
 
-    f_entity.set_visible(false);
+    if let Some(hook) = &args.on_milestone {
+        builder = builder.on_milestone(hook.clone());
+    }
+    if let Some(hook) = &args.on_failed_attempt {
+        builder = builder.on_failed_attempt(hook.clone(), args.failed_attempt_threshold);
+    }
+    if let Some(hook) = &args.on_emergency_unlock {
+        builder = builder.on_emergency_unlock(hook.clone());
+    }
 
-    controller.add_entity(p_entity);
+    builder.build()
+}
 
-    controller.add_entity(f_entity);
+/// The messages `[feedback]` replaces, by failure.
+fn failure_messages(feedback: &config::Feedback) -> [(Failure, &Option<String>); 5] {
+    [
+        (Failure::WrongPassword, &feedback.wrong_password),
+        (Failure::WrongCode, &feedback.wrong_code),
+        (Failure::ExpiredCode, &feedback.expired_code),
+        (Failure::TooSoon, &feedback.too_soon),
+        (Failure::Refused, &feedback.refused),
+    ]
+}
+
+/// Apply what a running lock can pick up from an edited `config`: feedback
+/// messages and how long they stay up, the keys let through and the theme.
+/// What is on screen only changes with the next lock.
+fn apply_config(config: &Config, ascii: bool) {
+    allow_keys(&config.grab.allow);
+    bus::enable();
+    for (failure, message) in failure_messages(&config.feedback) {
+        let message = template::expand(message.as_deref().unwrap_or_default());
+        bus::publish_event(
+            Recipient::Controller,
+            "failure_message",
+            &format!("{}={message}", failure.key()),
+        );
+    }
+    let duration = config.feedback.duration.unwrap_or(FEEDBACK_DURATION);
+    bus::publish_event(
+        Recipient::Controller,
+        "feedback_duration",
+        &duration.as_millis().to_string(),
+    );
+    let ascii = ascii || config.theme.ascii;
+    if theme::is_ascii() != ascii {
+        theme::set_ascii(ascii);
+        bus::publish(bus::Message::Redraw);
+    }
+}
+
+fn run_session(
+    args: &Args,
+    config: &Config,
+    request: &LockRequest,
+    credentials: &[Credential],
+    emergency_phrase: Option<&str>,
+    totp: Option<&Totp>,
+) -> anyhow::Result<()> {
+    let countdown = lock_duration(args, request);
+    let history = history_path(args);
+    let mut controller =
+        build_controller(args, config, request, credentials, emergency_phrase, totp)?;
+    if args.show_summary {
+        let history = history.clone();
+        controller.then(move || {
+            let record = finish_session(history.as_deref());
+            summary_scene(&record, history.as_deref())
+        });
+    }
 
-    thread::spawn(|| {
-        capture_control();
+    info!(duration = ?countdown, strict = args.strict, "lock engaged");
+
+    let ascii = args.ascii;
+    let config_watch = config::watch(args.config.as_deref(), move |config| {
+        apply_config(&config, ascii)
     });
+    let paused_media = args.pause_media.then(media::pause);
+    let clipboard = config
+        .clipboard
+        .clear
+        .then(|| clipboard::lock(config.clipboard.restore));
+    #[cfg(target_os = "linux")]
+    let inhibitors = inhibit::acquire(&args.inhibit);
+    #[cfg(target_os = "linux")]
+    let tty_locks = args.lock_ttys.then(|| ttys::lock_others(countdown));
+    args.output.execute(&mut controller)?;
+    #[cfg(target_os = "linux")]
+    drop((inhibitors, tty_locks));
+    drop((config_watch, clipboard));
+    if let Some(paused) = paused_media.filter(|_| args.resume_media) {
+        media::resume(paused);
+    }
+    // Unless the summary did already, it never comes up after a quit
+    if session::stats().locked {
+        finish_session(history.as_deref());
+    }
+
+    let stats = session::stats();
+    info!(
+        extended = ?stats.extended,
+        paused = ?stats.paused,
+        blocked_inputs = stats.blocked_inputs,
+        "lock released"
+    );
+    if !stats.extended.is_zero() {
+        println!("Lock was extended by {:?} in total.", stats.extended);
+    }
+
+    Ok(())
+}
+
+/// Render one frame of the lock UI as plain text instead of locking.
+fn screenshot(
+    args: &Args,
+    config: &Config,
+    credentials: &[Credential],
+    emergency_phrase: Option<&str>,
+    totp: Option<&Totp>,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let request = LockRequest::new("screenshot", None);
+    let mut controller =
+        build_controller(args, config, &request, credentials, emergency_phrase, totp)?;
+    let (width, height) = layout::terminal_size();
+    let frame = controller.render_to_string(width, height)? + "\n";
+    if path == Path::new("-") {
+        print!("{frame}");
+    } else {
+        std::fs::write(path, frame)?;
+    }
+    Ok(())
+}
+
+/// Parse the command line with the flags of the `--profile` it names in
+/// front, so that the ones given explicitly win.
+fn parse_args() -> anyhow::Result<Args> {
+    let command_line = lock_command_line(std::env::args_os().collect());
+    let args = Args::parse_from(&command_line);
+    let Some(name) = &args.profile else {
+        return Ok(args);
+    };
+    let flags = config::load(args.config.as_deref())?.profile_flags(name)?;
+    let mut command_line = command_line.into_iter();
+    let program = command_line.next().unwrap_or_default();
+    Ok(Args::parse_from(
+        std::iter::once(program)
+            .chain(flags.into_iter().map(Into::into))
+            .chain(command_line),
+    ))
+}
+
+/// `screenlock lock …` without its `lock`, which only spells out the default
+/// action, so that the lock's flags can follow it.
+fn lock_command_line(mut command_line: Vec<OsString>) -> Vec<OsString> {
+    if command_line.get(1).is_some_and(|arg| arg == "lock") {
+        command_line.remove(1);
+    }
+    command_line
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = parse_args()?;
+
+    if let Some(lang) = args.lang {
+        i18n::set_lang(lang);
+    }
+
+    match args.action {
+        Some(Action::Status { json }) => return print_status(json),
+        Some(Action::History) => return print_history(&args),
+        Some(Action::SetPassword) => return password_source::set_password(),
+        #[cfg(feature = "fido2")]
+        Some(Action::RegisterKey) => return auth::fido2::register(),
+        Some(Action::Lock) | None => {}
+    }
+
+    if let Some(path) = &args.status_stream {
+        return stream_status(path);
+    }
+
+    if args.strict && args.on_expire.keeps_lock() {
+        anyhow::bail!(
+            "--strict with --on-expire {} would never unlock",
+            args.on_expire
+        );
+    }
+    #[cfg(target_os = "linux")]
+    if args.inhibit.contains(&inhibit::Inhibit::Sleep)
+        && matches!(args.on_expire, OnExpire::Suspend | OnExpire::Hibernate)
+    {
+        anyhow::bail!(
+            "--inhibit sleep would keep --on-expire {} from working",
+            args.on_expire
+        );
+    }
+
+    logging::init(args.log_file.as_deref(), args.log_level)?;
+    install_panic_hook();
+
+    #[cfg(unix)]
+    if args.exec_shield && !watchdog::is_shielded() {
+        let code = watchdog::run()?;
+        std::process::exit(code);
+    }
+
+    #[cfg(unix)]
+    {
+        let defaults = signals::SignalActions::defaults(args.strict);
+        signals::install(signals::SignalActions {
+            term: args.on_sigterm.unwrap_or(defaults.term),
+            int: args.on_sigint.unwrap_or(defaults.int),
+            tstp: args.on_sigtstp.unwrap_or(defaults.tstp),
+        })
+        .context("could not install signal handlers")?;
+    }
+
+    let mut config = config::load(args.config.as_deref())?;
+    allow_keys(&config.grab.allow);
+
+    let password_source = match (args.password_fd, &args.password_file) {
+        (Some(fd), _) => PasswordSource::Fd(fd),
+        (None, Some(path)) => PasswordSource::File(path.clone()),
+        (None, None) => args
+            .password_source
+            .clone()
+            .unwrap_or_else(PasswordSource::fallback),
+    };
+    let correct_password = password_source
+        .read()
+        .context("could not read the password")?;
+
+    // Further passwords from LOCK_PASSWORD_<NAME>, e.g. an admin override in
+    // LOCK_PASSWORD_ADMIN, tell apart in the log and exit reason
+    let mut extra_credentials: Vec<Credential> = std::env::vars()
+        .filter_map(|(key, value)| {
+            let name = key.strip_prefix("LOCK_PASSWORD_")?.to_lowercase();
+            (!name.is_empty() && !value.trim().is_empty())
+                .then(|| Credential::new(&name, Secret::plain(&value)))
+        })
+        .collect();
+    extra_credentials.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // Only the partner unlocks early, the phrase would be a way around them
+    let emergency_phrase = std::env::var("LOCK_EMERGENCY_PHRASE")
+        .ok()
+        .map(|phrase| phrase.trim().to_lowercase())
+        .filter(|phrase| !phrase.is_empty() && args.partner_unlock.is_none());
+
+    let totp = match std::env::var("LOCK_TOTP_SECRET") {
+        Ok(secret) => Some(Totp::from_base32(&secret)?),
+        Err(_) => None,
+    };
+
+    // Another instance locks this console, everything else is up to that one
+    #[cfg(target_os = "linux")]
+    if let Some(duration) = args.tty_child {
+        session::start("console", args.reason.as_deref());
+        let request = LockRequest::new("console", Some(duration));
+        let credentials = [Credential::new(USER_CREDENTIAL, correct_password)];
+        let mut controller = build_controller(
+            &args,
+            &config,
+            &request,
+            &credentials,
+            emergency_phrase.as_deref(),
+            totp.as_ref(),
+        )?;
+        return args.output.execute(&mut controller);
+    }
+
+    if let Some(path) = &args.screenshot {
+        return screenshot(
+            &args,
+            &config,
+            &[Credential::new(USER_CREDENTIAL, correct_password)],
+            emergency_phrase.as_deref(),
+            totp.as_ref(),
+            path,
+        );
+    }
+
+    #[cfg(feature = "mqtt")]
+    let mqtt = args
+        .mqtt_broker
+        .as_deref()
+        .map(|broker| mqtt::Mqtt::connect(broker, &args.mqtt_topic))
+        .transpose()?;
+
+    // Only a broker or the schedule can ask to lock again, the socket goes
+    // away with us
+    #[cfg(feature = "mqtt")]
+    let wait_for_commands = mqtt.is_some() || args.schedule.is_some();
+    #[cfg(not(feature = "mqtt"))]
+    let wait_for_commands = args.schedule.is_some();
+
+    #[cfg(feature = "metrics")]
+    if let Some(addr) = args.metrics_addr {
+        metrics::serve(addr)?;
+    }
+
+    #[cfg(feature = "remote-unlock")]
+    if let Some(addr) = args.remote_unlock_addr {
+        remote_unlock::serve(addr, args.remote_unlock_url.clone())?;
+    }
+
+    #[cfg(unix)]
+    if let Err(error) = ipc::serve() {
+        warn!(?error, "could not open the control socket");
+    }
+
+    // Relaunched by the watchdog after being killed counts as --resume
+    #[cfg(unix)]
+    let resume = args.resume || watchdog::is_resuming();
+    #[cfg(not(unix))]
+    let resume = args.resume;
+    let state_path = state::default_path();
+    let resumed = match &state_path {
+        Some(path) if resume => state::load(path).unwrap_or_else(|error| {
+            warn!(?error, "could not read the lock state");
+            None
+        }),
+        _ => None,
+    };
+    if let Some(path) = &state_path {
+        state::spawn_writer(path.clone());
+    }
+
+    if let Some(delay) = args
+        .arm_delay
+        .filter(|_| args.schedule.is_none() && resumed.is_none())
+    {
+        if !arm(delay)? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    if args.no_grab {
+        info!("running without grabbing input");
+    } else {
+        start_grab(&args);
+    }
+
+    if let Some(schedule) = &args.schedule {
+        // Resident: nothing happens until the schedule says so
+        schedule::spawn(schedule.clone(), args.warn_before);
+    }
+    let mut request = match resumed {
+        Some(state) => {
+            info!(remaining_secs = state.remaining_secs, "resuming the lock");
+            LockRequest::resume(state)
+        }
+        None if args.schedule.is_some() => wait_for_lock_command(),
+        None => LockRequest::new("manual", None),
+    };
+    loop {
+        let password = match &args.partner_unlock {
+            Some(target) => {
+                let code = generate_unlock_code();
+                target
+                    .notifier()
+                    .send(
+                        "screenlock unlock code",
+                        &format!("The screen was locked. Unlock code: {code}"),
+                    )
+                    .context("could not send the unlock code to the partner")?;
+                info!(?target, "sent unlock code to partner");
+                Secret::Plain(code)
+            }
+            None => correct_password.clone(),
+        };
+        let credentials =
+            session_credentials(password, &extra_credentials, args.partner_unlock.is_some());
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &mqtt {
+            mqtt.publish_state("locked");
+        }
+        #[cfg(feature = "remote-unlock")]
+        if let Some(link) = remote_unlock::arm() {
+            if let Some(target) = &args.remote_unlock_notify {
+                if let Err(error) = target.notifier().send(
+                    "screenlock unlock link",
+                    &format!("The screen was locked. Open this link to unlock it: {link}"),
+                ) {
+                    warn!(?error, ?target, "could not send the unlock link");
+                }
+            }
+        }
+        session::start(&request.reason, args.reason.as_deref());
+        if let Some(state) = &request.resumed {
+            session::update_stats(|stats| {
+                stats.started_at = Some(state.started_at);
+                stats.failed_attempts = state.failed_attempts;
+            });
+        }
+        run_session(
+            &args,
+            &config,
+            &request,
+            &credentials,
+            emergency_phrase.as_deref(),
+            totp.as_ref(),
+        )?;
+        #[cfg(unix)]
+        if signals::quit_requested() {
+            break;
+        }
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &mqtt {
+            mqtt.publish_state("unlocked");
+        }
+        request = match args.relock_if_idle {
+            Some(window) => wait_until_idle(window),
+            None if wait_for_commands => wait_for_lock_command(),
+            None => break,
+        };
+        // Edits during the last lock, including to what is on screen
+        config = match config::load(args.config.as_deref()) {
+            Ok(config) => config,
+            Err(error) => {
+                warn!(?error, "keeping the previous config");
+                config
+            }
+        };
+        allow_keys(&config.grab.allow);
+    }
 
-    controller.execute()?;
+    #[cfg(unix)]
+    ipc::remove_socket();
+    if let Some(path) = &state_path {
+        state::remove(path);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, FakeDrawContext, ScriptedEvents};
+
+    #[test]
+    fn partner_mode_refuses_the_other_passwords() {
+        let _guard = testing::exclusive();
+        let admin = [Credential::new("admin", Secret::plain("letmein"))];
+        let credentials = session_credentials(Secret::Plain("k7q2xp".to_string()), &admin, true);
+        let mut controller = LockScreenBuilder::new(Duration::from_secs(60))
+            .auth(Auth::PartnerCode(credentials))
+            .attempt_delay(Duration::ZERO)
+            .build()
+            .unwrap();
+        let screen = FakeDrawContext::new();
+        let mut events = ScriptedEvents::typing("letmein");
+        for c in "k7q2xp".chars() {
+            events.push(testing::key(KeyCode::Char(c)));
+        }
+        events.push(testing::key(KeyCode::Enter));
+        controller
+            .run_with(&mut events, &mut screen.context())
+            .unwrap();
+        let stats = session::stats();
+        assert_eq!(stats.failed_attempts, 1);
+        assert_eq!(stats.exit_reason, "password");
+    }
+
+    #[test]
+    fn without_a_partner_the_other_passwords_unlock_too() {
+        let admin = [Credential::new("admin", Secret::plain("letmein"))];
+        let credentials = session_credentials(Secret::plain("hunter2"), &admin, false);
+        assert!(credentials.iter().any(|c| c.secret.matches("letmein")));
+    }
+
+    #[test]
+    fn lock_takes_the_flags_of_the_default_action() {
+        let command_line = ["screenlock", "lock", "--profile", "break", "--for", "5m"];
+        let command_line = lock_command_line(command_line.map(OsString::from).to_vec());
+        let args = Args::parse_from(command_line);
+        assert!(args.action.is_none());
+        assert_eq!(args.profile.as_deref(), Some("break"));
+        assert_eq!(args.duration, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn lock_is_only_the_subcommand_in_first_place() {
+        let command_line = ["screenlock", "--reason", "lock"]
+            .map(OsString::from)
+            .to_vec();
+        assert_eq!(lock_command_line(command_line.clone()), command_line);
+    }
+}
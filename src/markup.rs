@@ -0,0 +1,155 @@
+//! Lightweight inline markup for message lines: `**bold**`, `_italic_` and
+//! `{red}colored{/red}` text.
+
+use crossterm::style::Color;
+
+use crate::text;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+fn color_named(name: &str) -> Option<Color> {
+    Some(match name {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "grey" | "gray" => Color::Grey,
+        _ => return None,
+    })
+}
+
+/// Regroup styled characters into spans of equal style.
+fn group(chars: impl IntoIterator<Item = (char, Style)>) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    for (c, style) in chars {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.text.push(c),
+            _ => spans.push(Span {
+                text: c.to_string(),
+                style,
+            }),
+        }
+    }
+    spans
+}
+
+fn parse_chars(line: &str) -> Vec<(char, Style)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = Vec::with_capacity(chars.len());
+    let mut style = Style::default();
+    let mut colors: Vec<Color> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        if c == '*' && next == Some('*') {
+            style.bold = !style.bold;
+            i += 2;
+            continue;
+        }
+        // Only treat underscores at word edges as markup, so snake_case survives
+        let opens = !style.italic && prev.is_none_or(char::is_whitespace);
+        let closes = style.italic && !next.is_some_and(char::is_alphanumeric);
+        if c == '_' && (opens || closes) {
+            style.italic = !style.italic;
+            i += 1;
+            continue;
+        }
+        if c == '{' {
+            if let Some(len) = chars[i..].iter().position(|&c| c == '}') {
+                let tag: String = chars[i + 1..i + len].iter().collect();
+                let recognized = match tag.strip_prefix('/') {
+                    Some(name) if color_named(name).is_some() => {
+                        colors.pop();
+                        true
+                    }
+                    Some(_) => false,
+                    None => match color_named(&tag) {
+                        Some(color) => {
+                            colors.push(color);
+                            true
+                        }
+                        None => false,
+                    },
+                };
+                if recognized {
+                    style.color = colors.last().copied();
+                    i += len + 1;
+                    continue;
+                }
+            }
+        }
+        out.push((c, style));
+        i += 1;
+    }
+    out
+}
+
+pub fn parse(line: &str) -> Vec<Span> {
+    group(parse_chars(line))
+}
+
+/// `line` without its markup.
+pub fn plain_text(line: &str) -> String {
+    parse_chars(line).into_iter().map(|(c, _)| c).collect()
+}
+
+fn columns(chars: &[(char, Style)]) -> usize {
+    chars.iter().map(|(c, _)| text::char_width(*c)).sum()
+}
+
+/// Break spans into rows of at most `width` columns, preferring to break at
+/// whitespace. Full-width characters count as two columns.
+pub fn wrap(spans: &[Span], width: usize) -> Vec<Vec<Span>> {
+    let width = width.max(1);
+    let chars: Vec<(char, Style)> = spans
+        .iter()
+        .flat_map(|s| s.text.chars().map(move |c| (c, s.style)))
+        .collect();
+    let mut rows = Vec::new();
+    let mut row: Vec<(char, Style)> = Vec::new();
+    for word in chars.split(|(c, _)| *c == ' ') {
+        if !row.is_empty() && columns(&row) + 1 + columns(word) > width {
+            rows.push(group(std::mem::take(&mut row)));
+        }
+        if let Some(&(_, style)) = row.last() {
+            row.push((' ', style));
+        }
+        let mut rest = word;
+        while columns(&row) + columns(rest) > width {
+            let mut used = columns(&row);
+            let fits = rest
+                .iter()
+                .take_while(|(c, _)| {
+                    used += text::char_width(*c);
+                    used <= width
+                })
+                .count();
+            // A character wider than the whole row still has to go somewhere
+            let fits = if fits == 0 && row.is_empty() { 1 } else { fits };
+            let (head, tail) = rest.split_at(fits);
+            row.extend_from_slice(head);
+            rows.push(group(std::mem::take(&mut row)));
+            rest = tail;
+        }
+        row.extend_from_slice(rest);
+    }
+    rows.push(group(row));
+    rows
+}
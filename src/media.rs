@@ -0,0 +1,165 @@
+//! `--pause-media`: pausing whatever plays when the lock engages, so music
+//! doesn't keep going through a break, and resuming it on unlock if asked to.
+//! Talks MPRIS through `dbus-send` on Linux and AppleScript on macOS.
+
+use tracing::{info, warn};
+
+/// The players `pause` paused, and only those: resuming must not start one
+/// that was quiet before the lock.
+pub struct Paused(Vec<String>);
+
+/// Run `program` and return what it printed, `None` if it failed.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    use std::process::{Command, Stdio};
+
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::output;
+
+    const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+    /// Bus names of the MPRIS players on the session bus.
+    fn players() -> Vec<String> {
+        let Some(names) = output(
+            "dbus-send",
+            &[
+                "--session",
+                "--print-reply",
+                "--dest=org.freedesktop.DBus",
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus.ListNames",
+            ],
+        ) else {
+            return Vec::new();
+        };
+        // Lines look like `      string "org.mpris.MediaPlayer2.spotify"`
+        names
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("string \""))
+            .filter_map(|name| name.strip_suffix('"'))
+            .filter(|name| name.starts_with(MPRIS_PREFIX))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn playing(player: &str) -> bool {
+        output(
+            "dbus-send",
+            &[
+                "--session",
+                "--print-reply",
+                &format!("--dest={player}"),
+                "/org/mpris/MediaPlayer2",
+                "org.freedesktop.DBus.Properties.Get",
+                "string:org.mpris.MediaPlayer2.Player",
+                "string:PlaybackStatus",
+            ],
+        )
+        .is_some_and(|reply| reply.contains("\"Playing\""))
+    }
+
+    fn call(player: &str, method: &str) -> bool {
+        output(
+            "dbus-send",
+            &[
+                "--session",
+                "--type=method_call",
+                &format!("--dest={player}"),
+                "/org/mpris/MediaPlayer2",
+                &format!("org.mpris.MediaPlayer2.Player.{method}"),
+            ],
+        )
+        .is_some()
+    }
+
+    pub fn pause() -> Vec<String> {
+        players()
+            .into_iter()
+            .filter(|player| playing(player) && call(player, "Pause"))
+            .collect()
+    }
+
+    pub fn resume(player: &str) -> bool {
+        call(player, "Play")
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::output;
+
+    /// The players scriptable the same way.
+    const PLAYERS: [&str; 2] = ["Music", "Spotify"];
+
+    pub fn pause() -> Vec<String> {
+        PLAYERS
+            .into_iter()
+            .filter(|player| {
+                // Asking a player that isn't running would launch it
+                let script = format!(
+                    "if application \"{player}\" is running then\n\
+                     tell application \"{player}\"\n\
+                     if player state is playing then\n\
+                     pause\n\
+                     return \"paused\"\n\
+                     end if\n\
+                     end tell\n\
+                     end if"
+                );
+                output("osascript", &["-e", &script]).is_some_and(|out| out.trim() == "paused")
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn resume(player: &str) -> bool {
+        output(
+            "osascript",
+            &["-e", &format!("tell application \"{player}\" to play")],
+        )
+        .is_some()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod platform {
+    pub fn pause() -> Vec<String> {
+        tracing::warn!("pausing media is not supported on this platform");
+        Vec::new()
+    }
+
+    pub fn resume(_: &str) -> bool {
+        false
+    }
+}
+
+/// Pause every player that is playing right now.
+pub fn pause() -> Paused {
+    let paused = platform::pause();
+    if !paused.is_empty() {
+        info!(players = ?paused, "paused media");
+    }
+    Paused(paused)
+}
+
+/// Resume the players `pause` paused.
+pub fn resume(paused: Paused) {
+    for player in paused.0 {
+        if !platform::resume(&player) {
+            warn!(player, "could not resume media");
+        }
+    }
+}
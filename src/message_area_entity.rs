@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named, Visible},
+    layout,
+    markup::{self, Span},
+    text, theme,
+};
+
+const FRAME: Duration = Duration::from_millis(80);
+const ANIMATION_FRAMES: u32 = 8;
+const SHAKE_OFFSETS: [u16; ANIMATION_FRAMES as usize] = [0, 2, 4, 2, 0, 2, 1, 0];
+
+/// How a message catches the eye when it appears.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FeedbackAnimation {
+    None,
+    /// Blink the message a few times
+    Blink,
+    /// Jitter the message horizontally
+    Shake,
+    /// Show it in inverse video for a moment
+    Flash,
+}
+
+struct Message {
+    text: String,
+    spans: Vec<Span>,
+    color: Color,
+    shown: Instant,
+}
+
+impl Message {
+    /// Frames of the animation played since the message was shown.
+    fn frame(&self) -> u32 {
+        (self.shown.elapsed().as_millis() / FRAME.as_millis()) as u32
+    }
+}
+
+/// Transient messages below the prompt, one per line, each expiring on its
+/// own. Set the `message` property to show an error and `info` for anything
+/// else; repeating a message that is still up restarts it. Messages may use
+/// markup, colored parts keep their own color.
+pub struct MessageAreaEntity {
+    id: String,
+    lifetime: Duration,
+    animation: FeedbackAnimation,
+    messages: Vec<Message>,
+    height_changed: bool,
+    properties: HashMap<String, String>,
+}
+
+impl MessageAreaEntity {
+    pub fn new(id: &str, lifetime: Duration) -> Self {
+        MessageAreaEntity {
+            id: format!("MessageAreaEntity-{id}"),
+            lifetime,
+            animation: FeedbackAnimation::None,
+            messages: Vec::new(),
+            height_changed: false,
+            properties: HashMap::new(),
+        }
+    }
+
+    pub fn set_animation(&mut self, animation: FeedbackAnimation) {
+        self.animation = animation;
+    }
+
+    fn push(&mut self, text: &str, color: Color) {
+        self.messages.retain(|message| message.text != text);
+        self.messages.push(Message {
+            text: text.to_string(),
+            spans: markup::parse(text),
+            color,
+            shown: Instant::now(),
+        });
+    }
+
+    fn animating(&self, message: &Message) -> bool {
+        self.animation != FeedbackAnimation::None && message.frame() < ANIMATION_FRAMES
+    }
+
+    fn draw_message(
+        &self,
+        draw_context: &mut DrawContext,
+        y: u16,
+        message: &Message,
+    ) -> anyhow::Result<()> {
+        let animating = self.animating(message);
+        let frame = message.frame();
+        let (x, hidden, inverse) = match self.animation {
+            FeedbackAnimation::Shake if animating => (SHAKE_OFFSETS[frame as usize], false, false),
+            FeedbackAnimation::Blink if animating => (0, frame % 2 == 1, false),
+            FeedbackAnimation::Flash if animating => (0, false, frame < ANIMATION_FRAMES / 2),
+            _ => (0, false, false),
+        };
+        let (width, _) = layout::terminal_size();
+        let plain = markup::plain_text(&message.text);
+        let start = text::start_column(
+            width.saturating_sub(x),
+            text::width(&plain),
+            text::is_rtl(&plain),
+        );
+        execute!(
+            draw_context.out,
+            MoveTo(0, y),
+            Clear(ClearType::CurrentLine)
+        )?;
+        if !hidden {
+            draw_context.out.queue(MoveTo(start + x, y))?;
+            for span in &message.spans {
+                if inverse {
+                    draw_context.out.queue(SetAttribute(Attribute::Reverse))?;
+                }
+                if span.style.bold {
+                    draw_context.out.queue(SetAttribute(Attribute::Bold))?;
+                }
+                if span.style.italic {
+                    draw_context.out.queue(SetAttribute(Attribute::Italic))?;
+                }
+                draw_context
+                    .out
+                    .queue(SetForegroundColor(
+                        span.style.color.unwrap_or(message.color),
+                    ))?
+                    .queue(Print(theme::glyphs(&span.text)))?
+                    .queue(SetAttribute(Attribute::Reset))?;
+            }
+            execute!(draw_context.out, ResetColor)?;
+        }
+        Ok(())
+    }
+}
+
+impl Named for MessageAreaEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for MessageAreaEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(|s| s.as_str())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "message" => self.push(value, theme::current().error),
+            "info" => self.push(value, theme::current().warning),
+            // In milliseconds
+            "lifetime" => match value.parse() {
+                Ok(millis) => self.lifetime = Duration::from_millis(millis),
+                Err(_) => return false,
+            },
+            _ => {
+                self.properties.insert(key.to_string(), value.to_string());
+            }
+        }
+        true
+    }
+}
+
+impl Visible for MessageAreaEntity {}
+
+impl FullEntity for MessageAreaEntity {}
+
+impl Entity for MessageAreaEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let feedback_y = layout::feedback_y();
+        if self.height_changed {
+            // Rows below moved, whatever was there is redrawn after us
+            execute!(
+                draw_context.out,
+                MoveTo(0, feedback_y),
+                Clear(ClearType::FromCursorDown),
+            )?;
+        }
+        if !self.is_visible() || self.messages.is_empty() {
+            execute!(
+                draw_context.out,
+                MoveTo(0, feedback_y),
+                Clear(ClearType::CurrentLine),
+            )?;
+            return Ok(());
+        }
+        for (row, message) in self.messages.iter().enumerate() {
+            self.draw_message(draw_context, feedback_y + row as u16, message)?;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        let lifetime = self.lifetime;
+        self.messages
+            .retain(|message| context.now.saturating_duration_since(message.shown) < lifetime);
+        let height = self.messages.len().max(1) as u16;
+        self.height_changed = layout::set_feedback_height(height);
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        if self.messages.iter().any(|message| self.animating(message)) {
+            return Cadence::Every(FRAME);
+        }
+        self.messages
+            .iter()
+            .map(|message| self.lifetime.saturating_sub(message.shown.elapsed()))
+            .min()
+            .map_or(Cadence::OnChange, Cadence::Every)
+    }
+}
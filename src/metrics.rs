@@ -0,0 +1,86 @@
+//! Prometheus text exposition of lock activity, served over plain HTTP.
+
+use std::{fmt::Write, net::SocketAddr, thread};
+
+use tiny_http::{Header, Response, Server};
+use tracing::{info, warn};
+
+use crate::session;
+
+fn render() -> String {
+    let stats = session::stats();
+    let totals = session::totals();
+    // Count the running session as it goes, not only once it ended
+    let (sessions, locked, failed_attempts) = if stats.locked {
+        (
+            totals.sessions + 1,
+            totals.locked
+                + stats
+                    .started_at
+                    .and_then(|t| t.elapsed().ok())
+                    .unwrap_or_default(),
+            totals.failed_attempts + stats.failed_attempts as u64,
+        )
+    } else {
+        (totals.sessions, totals.locked, totals.failed_attempts)
+    };
+
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        let _ = writeln!(out, "{name} {value}");
+    };
+    metric(
+        "screenlock_sessions_total",
+        "counter",
+        "Lock sessions started.",
+        sessions.to_string(),
+    );
+    metric(
+        "screenlock_locked_seconds_total",
+        "counter",
+        "Time spent locked.",
+        format!("{:.3}", locked.as_secs_f64()),
+    );
+    metric(
+        "screenlock_failed_attempts_total",
+        "counter",
+        "Wrong passwords or codes entered.",
+        failed_attempts.to_string(),
+    );
+    metric(
+        "screenlock_locked",
+        "gauge",
+        "1 while the screen is locked.",
+        (stats.locked as u8).to_string(),
+    );
+    metric(
+        "screenlock_remaining_seconds",
+        "gauge",
+        "Time left on the current countdown.",
+        stats.remaining.as_secs().to_string(),
+    );
+    out
+}
+
+/// Serve `/metrics` on `addr` from a background thread.
+pub fn serve(addr: SocketAddr) -> anyhow::Result<()> {
+    let server = Server::http(addr).map_err(|e| anyhow::anyhow!(e))?;
+    info!(%addr, "serving metrics");
+    let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
+        .expect("static header is valid");
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = if request.url() == "/metrics" {
+                Response::from_string(render()).with_header(content_type.clone())
+            } else {
+                Response::from_string("not found").with_status_code(404)
+            };
+            if let Err(error) = request.respond(response) {
+                warn!(?error, "could not answer metrics request");
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Home automation hook-up: publishes the lock state to `<prefix>/state`
+//! (retained) and feeds commands from `<prefix>/command` into the command
+//! queue.
+
+use std::{thread, time::Duration};
+
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use tracing::{info, warn};
+
+use crate::{
+    bus,
+    commands::{self, Command},
+    count_down_entity::Trigger,
+};
+
+const DEFAULT_PORT: u16 = 1883;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub struct Mqtt {
+    client: Client,
+    state_topic: String,
+}
+
+impl Mqtt {
+    /// Connect to `broker` (`host` or `host:port`). Credentials are taken from
+    /// SCREENLOCK_MQTT_USERNAME and SCREENLOCK_MQTT_PASSWORD if set.
+    pub fn connect(broker: &str, prefix: &str) -> anyhow::Result<Self> {
+        let (host, port) = match broker.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (broker, DEFAULT_PORT),
+        };
+        let state_topic = format!("{prefix}/state");
+        let command_topic = format!("{prefix}/command");
+
+        let mut options =
+            MqttOptions::new(format!("screenlock-{}", std::process::id()), host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        // Don't leave a stale "locked" behind if we die
+        options.set_last_will(LastWill::new(
+            &state_topic,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let Ok(username) = std::env::var("SCREENLOCK_MQTT_USERNAME") {
+            let password = std::env::var("SCREENLOCK_MQTT_PASSWORD").unwrap_or_default();
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut connection) = Client::new(options, 16);
+        let subscriber = client.clone();
+        thread::spawn(move || {
+            for notification in connection.iter() {
+                match notification {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        info!(topic = %command_topic, "connected to MQTT broker");
+                        // Subscriptions don't survive a reconnect with a clean session
+                        if let Err(error) = subscriber.subscribe(&command_topic, QoS::AtLeastOnce) {
+                            warn!(?error, "could not subscribe to MQTT commands");
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let payload = String::from_utf8_lossy(&publish.payload);
+                        match payload.trim().parse::<Command>() {
+                            Ok(command) => {
+                                info!(?command, "received MQTT command");
+                                commands::send(command, Trigger::Mqtt);
+                            }
+                            Err(error) => warn!(%payload, error, "ignoring MQTT command"),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        warn!(?error, "MQTT connection error, retrying");
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+        bus::enable();
+
+        Ok(Mqtt {
+            client,
+            state_topic,
+        })
+    }
+
+    pub fn publish_state(&self, state: &str) {
+        if let Err(error) = self
+            .client
+            .publish(&self.state_topic, QoS::AtLeastOnce, true, state)
+        {
+            warn!(?error, state, "could not publish lock state");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    io::Write,
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use tracing::info;
+
+use crate::{
+    controller::{DrawContext, EntityId},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout,
+    tasks::{self, TaskHandle},
+    theme,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Whether a TCP connection to `addr` can be opened within the timeout.
+fn reachable(addr: &str) -> bool {
+    let Ok(addrs) = addr.to_socket_addrs() else {
+        return false;
+    };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, CHECK_TIMEOUT).is_ok())
+}
+
+/// Shows on a footer row whether the machine is online, by opening a TCP
+/// connection to `addr` now and then, so it is obvious when remote unlocks
+/// and notifications can't get through.
+pub struct NetworkEntity {
+    id: String,
+    entity_id: EntityId,
+    row: u16,
+    /// `None` until the first check finished
+    online: Option<bool>,
+    _task: TaskHandle,
+}
+
+impl NetworkEntity {
+    /// Start checking `addr` (`host:port`). `row` is the line within the
+    /// footer.
+    pub fn spawn(id: &str, addr: &str, row: u16) -> Self {
+        let id = format!("NetworkEntity-{id}");
+        let entity_id = EntityId::next();
+        let addr = addr.to_string();
+        let mut last = None;
+        let task = tasks::every(entity_id, "online", CHECK_INTERVAL, move || {
+            let online = reachable(&addr);
+            if last.replace(online) != Some(online) {
+                info!(%addr, online, "connectivity changed");
+            }
+            Ok(online.to_string())
+        });
+        NetworkEntity {
+            id,
+            entity_id,
+            row,
+            online: None,
+            _task: task,
+        }
+    }
+}
+
+impl Named for NetworkEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
+}
+
+impl HasProperties for NetworkEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        match (key, self.online) {
+            ("online", Some(true)) => Some("true"),
+            ("online", Some(false)) => Some("false"),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        if key != "online" {
+            return false;
+        }
+        self.online = Some(value == "true");
+        true
+    }
+}
+
+impl FullEntity for NetworkEntity {}
+
+impl Entity for NetworkEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let theme = theme::current();
+        let (color, text) = match self.online {
+            Some(true) => (theme.muted, i18n::t("network.online")),
+            Some(false) => (theme.error, i18n::t("network.offline")),
+            None => (theme.muted, ""),
+        };
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(color))?
+            .queue(Print(text))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
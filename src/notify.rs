@@ -0,0 +1,141 @@
+//! Outbound notifications, used to hand unlock codes to someone else.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::Context;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Environment variable holding the access token for Matrix targets.
+pub const MATRIX_TOKEN_VAR: &str = "SCREENLOCK_MATRIX_TOKEN";
+
+pub trait Notifier {
+    fn send(&self, subject: &str, body: &str) -> anyhow::Result<()>;
+}
+
+/// POSTs `{"subject": .., "body": ..}` as JSON.
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn send(&self, subject: &str, body: &str) -> anyhow::Result<()> {
+        ureq::post(&self.url)
+            .timeout(TIMEOUT)
+            .send_json(ureq::json!({ "subject": subject, "body": body }))?;
+        Ok(())
+    }
+}
+
+/// Hands the mail to the local `sendmail`.
+pub struct EmailNotifier {
+    to: String,
+}
+
+impl Notifier for EmailNotifier {
+    fn send(&self, subject: &str, body: &str) -> anyhow::Result<()> {
+        let mut child = Command::new("sendmail")
+            .arg("-t")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("could not start sendmail")?;
+        if let Some(stdin) = child.stdin.as_mut() {
+            write!(stdin, "To: {}\nSubject: {subject}\n\n{body}\n", self.to)?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("sendmail exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+/// Posts a text message to a Matrix room.
+pub struct MatrixNotifier {
+    homeserver: String,
+    room: String,
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+impl Notifier for MatrixNotifier {
+    fn send(&self, subject: &str, body: &str) -> anyhow::Result<()> {
+        let token = std::env::var(MATRIX_TOKEN_VAR)
+            .with_context(|| format!("{MATRIX_TOKEN_VAR} is not set"))?;
+        let txn = rand::random::<u64>();
+        let url = format!(
+            "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{txn}",
+            self.homeserver.trim_end_matches('/'),
+            percent_encode(&self.room)
+        );
+        ureq::put(&url)
+            .timeout(TIMEOUT)
+            .set("Authorization", &format!("Bearer {token}"))
+            .send_json(
+                ureq::json!({ "msgtype": "m.text", "body": format!("{subject}\n{body}") }),
+            )?;
+        Ok(())
+    }
+}
+
+/// Where to send a notification, written as `webhook:<url>`,
+/// `email:<address>` or `matrix:<room id>@<homeserver url>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    Webhook(String),
+    Email(String),
+    Matrix { room: String, homeserver: String },
+}
+
+impl FromStr for Target {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((kind, rest)) = s.split_once(':') else {
+            anyhow::bail!("expected <kind>:<destination>, e.g. webhook:https://example.com/hook");
+        };
+        Ok(match kind {
+            "webhook" => Target::Webhook(rest.to_string()),
+            "email" => Target::Email(rest.to_string()),
+            "matrix" => {
+                let Some((room, homeserver)) = rest.split_once('@') else {
+                    anyhow::bail!("expected matrix:<room id>@<homeserver url>");
+                };
+                Target::Matrix {
+                    room: room.to_string(),
+                    homeserver: homeserver.to_string(),
+                }
+            }
+            _ => anyhow::bail!("unknown notification target '{kind}'"),
+        })
+    }
+}
+
+impl Target {
+    pub fn notifier(&self) -> Box<dyn Notifier> {
+        match self {
+            Target::Webhook(url) => Box::new(WebhookNotifier { url: url.clone() }),
+            Target::Email(to) => Box::new(EmailNotifier { to: to.clone() }),
+            Target::Matrix { room, homeserver } => Box::new(MatrixNotifier {
+                homeserver: homeserver.clone(),
+                room: room.clone(),
+            }),
+        }
+    }
+}
@@ -1,35 +1,314 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
 use crossterm::{
     cursor::MoveTo,
-    event::{Event, KeyCode, KeyEvent},
+    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
-    style::Print,
+    style::{Color, Print, ResetColor, SetForegroundColor},
     terminal::{Clear, ClearType},
 };
+use screenlock::secret::MAX_PASSWORD_LEN;
+use tracing::{info, warn};
+
+pub use screenlock::keys::{is_shortcut, main_key};
 
 use crate::{
-    controller::{ControlEvent, DrawContext, EventContext, UpdateResult},
-    entity::{Entity, Named},
-    PROMPT_Y,
+    commands::Command,
+    controller::{
+        ControlEvent, DrawContext, EntityId, EventContext, Recipient, UpdateContext, UpdateResult,
+    },
+    count_down_entity::Trigger,
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    i18n, layout,
+    password_source::{constant_time_eq, Secret},
+    session, text, theme,
+    totp::Totp,
 };
 
+/// How long the mask lights up when input was ignored for being too long
+const OVERFLOW_FLASH: Duration = Duration::from_millis(300);
+
+/// A phrase that always unlocks, but not quietly: it is logged, reported to
+/// the controller and can keep a message on screen for a while first.
+struct Emergency {
+    phrase: String,
+    message: Option<String>,
+    delay: Duration,
+    started: Option<Instant>,
+    reported: bool,
+}
+
+/// A secret that unlocks, named so logs and the exit reason tell e.g. an
+/// admin override from the user's own password.
+#[derive(Clone, Debug)]
+pub struct Credential {
+    pub name: String,
+    pub secret: Secret,
+}
+
+/// The name of the user's own password. Unlocks with it end with the exit
+/// reason `password`, the others with `password_<name>`.
+pub const USER_CREDENTIAL: &str = "user";
+
+impl Credential {
+    pub fn new(name: &str, secret: Secret) -> Self {
+        Credential {
+            name: name.to_string(),
+            secret,
+        }
+    }
+}
+
+/// Why input was turned down, each with a message of its own. Messages may
+/// use markup, e.g. for their own colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Failure {
+    WrongPassword,
+    WrongCode,
+    /// An authenticator code that was valid a few minutes ago
+    ExpiredCode,
+    /// Typed during the delay after a wrong password. `{secs}` in the message
+    /// is replaced with the seconds left
+    TooSoon,
+    /// The right password while unlocking isn't allowed, e.g. the owner's
+    /// presence check says they are away
+    Refused,
+}
+
+impl Failure {
+    const ALL: [Failure; 5] = [
+        Failure::WrongPassword,
+        Failure::WrongCode,
+        Failure::ExpiredCode,
+        Failure::TooSoon,
+        Failure::Refused,
+    ];
+
+    /// What it goes by in the `failure_message` property.
+    pub fn key(self) -> &'static str {
+        match self {
+            Failure::WrongPassword => "wrong_password",
+            Failure::WrongCode => "wrong_code",
+            Failure::ExpiredCode => "expired_code",
+            Failure::TooSoon => "too_soon",
+            Failure::Refused => "refused",
+        }
+    }
+
+    fn default_message(self) -> &'static str {
+        i18n::t(match self {
+            Failure::WrongPassword => "feedback.wrong_password",
+            Failure::WrongCode => "feedback.wrong_code",
+            Failure::ExpiredCode => "feedback.expired_code",
+            Failure::TooSoon => "feedback.too_soon",
+            Failure::Refused => "feedback.refused",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    Password,
+    SecondFactor,
+}
+
 pub struct PasswordPromptEntity {
     id: String,
     prompt: String,
-    correct_password: String,
+    credentials: Vec<Credential>,
+    /// The credential the password stage was passed with
+    matched: Option<String>,
+    second_factor: Option<Totp>,
+    emergency: Option<Emergency>,
+    stage: Stage,
     password: String,
+    /// When input was last ignored for being too long
+    overflowed: Option<Instant>,
     dirty: bool,
-    linked_feedback: String,
+    unlocked: bool,
+    failure: Failure,
+    failure_messages: HashMap<Failure, String>,
+    /// Set when input was dropped during the delay after a wrong password
+    too_soon: bool,
+    /// Asked before unlocking with a right password, which is refused if it
+    /// says no
+    unlock_gate: Option<fn() -> bool>,
+    /// Set when a right password was refused by `unlock_gate`
+    refused: bool,
+    paste: bool,
+    attempt_delay: Duration,
+    /// Input is ignored until then after a wrong password
+    retry_at: Option<Instant>,
+    /// Where failures are reported
+    feedback: EntityId,
+    failed_attempts: u32,
 }
 
 impl PasswordPromptEntity {
-    pub fn new(id: &str, prompt: &str, correct_password: &str, linked_feedback_name: &str) -> Self {
+    /// Accept any of `credentials`.
+    pub fn new(id: &str, prompt: &str, credentials: Vec<Credential>, feedback: EntityId) -> Self {
         PasswordPromptEntity {
             id: format!("PasswordPromptEntity-{id}"),
             prompt: prompt.to_string(),
-            correct_password: correct_password.to_string(),
+            credentials,
+            matched: None,
+            second_factor: None,
+            emergency: None,
+            stage: Stage::Password,
             password: String::new(),
+            overflowed: None,
             dirty: true,
-            linked_feedback: linked_feedback_name.to_string(),
+            unlocked: false,
+            failure: Failure::WrongPassword,
+            failure_messages: HashMap::new(),
+            too_soon: false,
+            unlock_gate: None,
+            refused: false,
+            paste: true,
+            attempt_delay: Duration::ZERO,
+            retry_at: None,
+            feedback,
+            failed_attempts: 0,
+        }
+    }
+
+    /// Count on from earlier failures, e.g. of a lock restored after a crash.
+    pub fn set_failed_attempts(&mut self, failed_attempts: u32) {
+        self.failed_attempts = failed_attempts;
+    }
+
+    /// Ask for a TOTP code after the password before unlocking.
+    pub fn set_second_factor(&mut self, totp: Totp) {
+        self.second_factor = Some(totp);
+    }
+
+    /// Whether pasting, e.g. from a password manager, is allowed.
+    pub fn set_paste(&mut self, allowed: bool) {
+        self.paste = allowed;
+    }
+
+    /// Report `failure` with `message` instead of the default one.
+    pub fn set_failure_message(&mut self, failure: Failure, message: &str) {
+        self.failure_messages.insert(failure, message.to_string());
+    }
+
+    /// Ignore input for `delay` after each wrong password, so guessing takes
+    /// a while.
+    pub fn set_attempt_delay(&mut self, delay: Duration) {
+        self.attempt_delay = delay;
+    }
+
+    /// Refuse a right password unless `gate` allows unlocking.
+    pub fn set_unlock_gate(&mut self, gate: fn() -> bool) {
+        self.unlock_gate = Some(gate);
+    }
+
+    /// Unlock right away on `phrase`, after showing `message` for `delay`.
+    pub fn set_emergency_phrase(&mut self, phrase: &str, message: Option<&str>, delay: Duration) {
+        self.emergency = Some(Emergency {
+            phrase: phrase.to_string(),
+            message: message.map(str::to_string),
+            delay,
+            started: None,
+            reported: false,
+        });
+    }
+
+    /// Type `c`, unless the password is as long as it gets.
+    fn push(&mut self, c: char) {
+        if self.password.chars().count() >= MAX_PASSWORD_LEN {
+            self.overflowed = Some(Instant::now());
+            return;
+        }
+        self.password.push(c);
+    }
+
+    fn failure_message(&self, failure: Failure) -> String {
+        let left = self.retry_at.map_or(Duration::ZERO, |at| {
+            at.saturating_duration_since(Instant::now())
+        });
+        let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+        self.failure_messages
+            .get(&failure)
+            .map_or(failure.default_message(), String::as_str)
+            .replace("{secs}", &secs.to_string())
+    }
+
+    fn current_prompt(&self) -> &str {
+        match self.stage {
+            Stage::Password => &self.prompt,
+            Stage::SecondFactor => i18n::t("prompt.second_factor"),
+        }
+    }
+
+    fn submit(&mut self) {
+        if let Some(emergency) = self.emergency.as_mut() {
+            let phrase = constant_time_eq(self.password.as_bytes(), emergency.phrase.as_bytes());
+            if self.stage == Stage::Password && phrase {
+                warn!(
+                    failed_attempts = self.failed_attempts,
+                    "unlocked with emergency phrase"
+                );
+                self.password.clear();
+                emergency.started = Some(Instant::now());
+                session::set_exit_reason("emergency");
+                return;
+            }
+        }
+        let accepted = match (self.stage, &self.second_factor) {
+            (Stage::Password, _) => {
+                // All of them, which one matched must not show in the timing
+                self.matched = self.credentials.iter().fold(None, |matched, credential| {
+                    let matches = credential.secret.matches(&self.password);
+                    matched.or(matches.then(|| credential.name.clone()))
+                });
+                self.matched.is_some()
+            }
+            (Stage::SecondFactor, Some(totp)) => totp.verify(&self.password),
+            (Stage::SecondFactor, None) => false,
+        };
+        let expired = match (self.stage, &self.second_factor) {
+            (Stage::SecondFactor, Some(totp)) => !accepted && totp.expired(&self.password),
+            _ => false,
+        };
+        self.password.clear();
+        if !accepted {
+            self.failed_attempts += 1;
+            session::update_stats(|stats| stats.failed_attempts = self.failed_attempts);
+            warn!(attempt = self.failed_attempts, stage = ?self.stage, "failed unlock attempt");
+            self.failure = match self.stage {
+                Stage::Password => Failure::WrongPassword,
+                Stage::SecondFactor if expired => Failure::ExpiredCode,
+                Stage::SecondFactor => Failure::WrongCode,
+            };
+            self.stage = Stage::Password;
+            self.retry_at = Some(Instant::now() + self.attempt_delay);
+            self.dirty = false;
+        } else if self.stage == Stage::Password && self.second_factor.is_some() {
+            info!(credential = ?self.matched, "password accepted, waiting for second factor");
+            self.stage = Stage::SecondFactor;
+        } else if self.unlock_gate.is_some_and(|allowed| !allowed()) {
+            // Not a failed attempt, the password was right
+            warn!(credential = ?self.matched, "unlock refused");
+            self.stage = Stage::Password;
+            self.refused = true;
+        } else {
+            let credential = self.matched.as_deref().unwrap_or(USER_CREDENTIAL);
+            info!(
+                failed_attempts = self.failed_attempts,
+                second_factor = self.second_factor.is_some(),
+                credential,
+                "unlocked with password"
+            );
+            session::set_exit_reason(&if credential == USER_CREDENTIAL {
+                "password".to_string()
+            } else {
+                format!("password_{credential}")
+            });
+            self.unlocked = true;
         }
     }
 }
@@ -40,66 +319,229 @@ impl Named for PasswordPromptEntity {
     }
 }
 
+impl HasProperties for PasswordPromptEntity {
+    fn get_property(&self, _: &str) -> Option<&str> {
+        None
+    }
+
+    /// `failure_message` is `<failure key>=<message>`, e.g. when the config
+    /// changed. An empty message goes back to the default.
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        let Some((failure, message)) = value.split_once('=').filter(|_| key == "failure_message")
+        else {
+            return false;
+        };
+        let Some(failure) = Failure::ALL.into_iter().find(|f| f.key() == failure) else {
+            return false;
+        };
+        if message.is_empty() {
+            self.failure_messages.remove(&failure);
+        } else {
+            self.set_failure_message(failure, message);
+        }
+        true
+    }
+}
+
+impl FullEntity for PasswordPromptEntity {}
+
 impl Entity for PasswordPromptEntity {
     fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
-        let prompt_col = self.prompt.len() as u16;
+        if let Some(message) = self
+            .emergency
+            .as_ref()
+            .filter(|e| e.started.is_some())
+            .and_then(|e| e.message.as_ref())
+        {
+            let prompt_y = layout::prompt_y();
+            execute!(
+                draw_context.out,
+                MoveTo(0, prompt_y),
+                Clear(ClearType::CurrentLine),
+                SetForegroundColor(theme::current().warning),
+                Print(theme::glyphs(message)),
+                ResetColor,
+            )?;
+            return Ok(());
+        }
+        let prompt = self.current_prompt();
+        let (width, _) = layout::terminal_size();
+        // A wrapping mask would run into the lines below
+        let room = (width as usize).saturating_sub(text::width(prompt) + 1);
+        let mask = "*".repeat(self.password.chars().count().min(room));
+        let line = format!("{prompt}{mask}");
+        let line_width = text::width(&line);
+        let x = layout::prompt_x(width, line_width, text::is_rtl(&line));
+        let prompt_y = layout::prompt_y();
+        let mask_color = match self.overflowed {
+            Some(_) => theme::current().warning,
+            None => Color::Reset,
+        };
         execute!(
             draw_context.out,
-            MoveTo(0, PROMPT_Y),
+            MoveTo(0, prompt_y),
             Clear(ClearType::CurrentLine),
-            MoveTo(0, PROMPT_Y),
-            Print(format!("{}{}", self.prompt, "*".repeat(self.password.len())).as_str()),
-            MoveTo(prompt_col + self.password.len() as u16, PROMPT_Y)
+            MoveTo(x, prompt_y),
+            Print(prompt),
+            SetForegroundColor(mask_color),
+            Print(&mask),
+            ResetColor,
+            MoveTo(x + line_width as u16, prompt_y)
         )?;
         Ok(())
     }
 
-    fn update(&mut self) -> UpdateResult {
-        if self.password == self.correct_password && !self.dirty {
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        if self.unlocked {
             return UpdateResult::kill();
         }
+        if self
+            .overflowed
+            .is_some_and(|at| context.now.saturating_duration_since(at) >= OVERFLOW_FLASH)
+        {
+            self.overflowed = None;
+        }
+        if let Some(emergency) = self.emergency.as_mut() {
+            if let Some(started) = emergency.started {
+                // Report first, a killed controller drops pending events
+                if !emergency.reported {
+                    emergency.reported = true;
+                    context.emit(ControlEvent {
+                        recipient: Recipient::Controller,
+                        property_key: "emergency_unlock".to_string(),
+                        property_value: self.failed_attempts.to_string(),
+                    });
+                    return UpdateResult::focus();
+                }
+                if context.now.saturating_duration_since(started) >= emergency.delay {
+                    return UpdateResult::kill();
+                }
+                return UpdateResult::focus();
+            }
+        }
         if !self.dirty {
             self.dirty = true;
-            return UpdateResult {
-                kill: false,
-                focused: true,
-                events: vec![ControlEvent {
-                    name: self.linked_feedback.clone(),
-                    property_key: "visible".to_string(),
-                    property_value: "true".to_string(),
-                }],
-            };
+            context.emit(ControlEvent {
+                recipient: Recipient::Entity(self.feedback),
+                property_key: "message".to_string(),
+                property_value: self.failure_message(self.failure),
+            });
+            context.emit(ControlEvent {
+                recipient: Recipient::Controller,
+                property_key: "failed_attempt".to_string(),
+                property_value: self.failed_attempts.to_string(),
+            });
+        }
+        if self.refused {
+            self.refused = false;
+            context.emit(ControlEvent {
+                recipient: Recipient::Entity(self.feedback),
+                property_key: "message".to_string(),
+                property_value: self.failure_message(Failure::Refused),
+            });
+        }
+        if self.too_soon {
+            self.too_soon = false;
+            context.emit(ControlEvent {
+                recipient: Recipient::Entity(self.feedback),
+                property_key: "info".to_string(),
+                property_value: self.failure_message(Failure::TooSoon),
+            });
         }
         UpdateResult::focus()
     }
 
     fn handle_event(&mut self, event: EventContext) -> bool {
-        match event.event {
-            Event::Key(KeyEvent { code, .. }) => {
-                match code {
-                    KeyCode::Char(c) => {
-                        self.password.push(*c);
-                        self.dirty = true;
-                        true
-                    }
-                    KeyCode::Backspace => {
-                        self.password.pop();
-                        self.dirty = true;
-                        true
-                    }
-                    KeyCode::Enter => {
-                        self.dirty = false;
-                        if self.password == self.correct_password {
-                            return true; // signal to kill
-                        } else {
-                            self.password.clear();
-                        }
-                        true
-                    }
-                    _ => false,
+        if self.emergency.as_ref().is_some_and(|e| e.started.is_some()) {
+            return false;
+        }
+        if self.retry_at.is_some_and(|at| Instant::now() < at) {
+            self.too_soon = true;
+            return true;
+        }
+        let (code, modifiers, kind) = match event.event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind,
+                ..
+            }) => (code, modifiers, kind),
+            Event::Paste(text) if self.paste => {
+                // Password managers tend to paste a line break along
+                for c in text
+                    .trim_end_matches(['\r', '\n'])
+                    .chars()
+                    .filter(|c| !c.is_control())
+                {
+                    self.push(c);
                 }
+                self.dirty = true;
+                return true;
+            }
+            Event::Paste(_) => {
+                warn!("paste ignored, pasting is disabled");
+                return false;
+            }
+            _ => return false,
+        };
+        match main_key(*code) {
+            // Like a shell: clear the line, delete the last word
+            KeyCode::Char('u') if *modifiers == KeyModifiers::CONTROL => {
+                self.password.clear();
+                self.dirty = true;
+                true
+            }
+            KeyCode::Char('w') if *modifiers == KeyModifiers::CONTROL => {
+                let kept = self
+                    .password
+                    .trim_end()
+                    .trim_end_matches(|c: char| !c.is_whitespace())
+                    .len();
+                self.password.truncate(kept);
+                self.dirty = true;
+                true
+            }
+            KeyCode::Char(_) if is_shortcut(*modifiers) => false,
+            KeyCode::Char(c) => {
+                self.push(c);
+                self.dirty = true;
+                true
+            }
+            KeyCode::Backspace => {
+                self.password.pop();
+                self.dirty = true;
+                true
+            }
+            // A held Enter would submit the next attempt empty
+            KeyCode::Enter if *kind == KeyEventKind::Press => {
+                self.submit();
+                true
             }
             _ => false,
         }
     }
+
+    fn handle_command(&mut self, command: Command, trigger: Trigger) -> bool {
+        if command != Command::Unlock {
+            return false;
+        }
+        info!(%trigger, failed_attempts = self.failed_attempts, "unlocked remotely");
+        session::set_exit_reason(&format!("remote_{trigger}"));
+        self.unlocked = true;
+        true
+    }
+
+    fn cadence(&self) -> Cadence {
+        if let Some(at) = self.overflowed {
+            return Cadence::Every(OVERFLOW_FLASH.saturating_sub(at.elapsed()));
+        }
+        match &self.emergency {
+            Some(Emergency {
+                started: Some(_),
+                delay,
+                ..
+            }) => Cadence::Every(*delay),
+            _ => Cadence::OnChange,
+        }
+    }
 }
@@ -0,0 +1,280 @@
+//! Where the expected password comes from.
+
+use std::{
+    fs,
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString},
+    Argon2,
+};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use tracing::warn;
+
+use crate::controller::RawMode;
+
+use screenlock::secret::normalize;
+pub use screenlock::secret::{constant_time_eq, Secret};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PasswordSource {
+    /// `LOCK_PASSWORD`, `password` if unset
+    Env,
+    /// The OS keyring (Secret Service, Keychain or Windows Credential
+    /// Manager), stored under this service for the current user
+    Keyring(String),
+    /// The first line read from this file descriptor, e.g. a pipe set up by
+    /// the calling script (`--password-fd`)
+    Fd(i32),
+    /// The first line of this file (`--password-file`)
+    File(PathBuf),
+    /// The hash saved by `screenlock set-password`
+    Stored(PathBuf),
+}
+
+impl FromStr for PasswordSource {
+    type Err = anyhow::Error;
+
+    /// Parses `env` and `keyring:<service>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "env" {
+            return Ok(PasswordSource::Env);
+        }
+        match s.split_once(':') {
+            Some(("keyring", service)) if !service.is_empty() => {
+                if cfg!(feature = "keyring") {
+                    Ok(PasswordSource::Keyring(service.to_string()))
+                } else {
+                    anyhow::bail!("built without keyring support, enable the keyring feature")
+                }
+            }
+            _ => anyhow::bail!("expected env or keyring:<service>, e.g. keyring:screenlock"),
+        }
+    }
+}
+
+impl PasswordSource {
+    /// `LOCK_PASSWORD` if set, else the stored hash if there is one.
+    pub fn fallback() -> Self {
+        if std::env::var_os("LOCK_PASSWORD").is_none() {
+            if let Some(path) = stored_hash_path().filter(|path| path.exists()) {
+                return PasswordSource::Stored(path);
+            }
+        }
+        PasswordSource::Env
+    }
+
+    pub fn read(&self) -> anyhow::Result<Secret> {
+        let (secret, origin) = match self {
+            PasswordSource::Env => (
+                Secret::plain(
+                    &std::env::var("LOCK_PASSWORD").unwrap_or_else(|_| "password".to_string()),
+                ),
+                "LOCK_PASSWORD".to_string(),
+            ),
+            PasswordSource::Keyring(service) => (
+                Secret::plain(&read_keyring(service)?),
+                format!("the keyring entry of {service}"),
+            ),
+            PasswordSource::Fd(fd) => (
+                Secret::plain(&first_line(&read_fd(*fd)?)),
+                format!("file descriptor {fd}"),
+            ),
+            PasswordSource::File(path) => {
+                warn_if_world_readable(path);
+                (
+                    Secret::plain(&first_line(&fs::read_to_string(path)?)),
+                    path.display().to_string(),
+                )
+            }
+            PasswordSource::Stored(path) => {
+                let hash = first_line(&fs::read_to_string(path)?);
+                PasswordHash::new(&hash).map_err(|error| {
+                    anyhow::anyhow!("{} is no password hash: {error}", path.display())
+                })?;
+                return Ok(Secret::Hash(hash));
+            }
+        };
+        // Anything typed would be checked against nothing, a bare Enter unlocks
+        if matches!(&secret, Secret::Plain(text) if text.is_empty()) {
+            anyhow::bail!("the password from {origin} is empty");
+        }
+        Ok(secret)
+    }
+}
+
+pub fn stored_hash_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("screenlock").join("password.hash"))
+}
+
+/// Read a line without echoing it, showing a `*` per character instead.
+/// Returns `None` if cancelled with Esc.
+fn read_masked(prompt: &str) -> anyhow::Result<Option<String>> {
+    let mut out = stdout();
+    write!(out, "{prompt}")?;
+    out.flush()?;
+    let raw_mode = RawMode::enable()?;
+    let mut text = String::new();
+    let entered = loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => break true,
+            KeyCode::Esc => break false,
+            KeyCode::Backspace => {
+                if text.pop().is_some() {
+                    write!(out, "\x08 \x08")?;
+                }
+            }
+            KeyCode::Char(c) => {
+                text.push(c);
+                write!(out, "*")?;
+            }
+            _ => continue,
+        }
+        out.flush()?;
+    };
+    drop(raw_mode);
+    println!();
+    Ok(entered.then_some(text))
+}
+
+fn write_private(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        // An existing file keeps its mode otherwise
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// `screenlock set-password`: ask for a new password twice and store its
+/// argon2 hash, which the lock uses when no other source is given.
+pub fn set_password() -> anyhow::Result<()> {
+    let path = stored_hash_path().context("no config directory to store the password in")?;
+    let Some(password) = read_masked("New password: ")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let password = normalize(&password);
+    if password.is_empty() {
+        anyhow::bail!("the password can't be empty");
+    }
+    let Some(repeated) = read_masked("Repeat it: ")? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    if normalize(&repeated) != password {
+        anyhow::bail!("the passwords don't match");
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|error| anyhow::anyhow!("could not hash the password: {error}"))?;
+    write_private(&path, &format!("{hash}\n"))?;
+    println!("Password saved to {}.", path.display());
+    Ok(())
+}
+
+/// Secrets usually come with a trailing newline, anything after it is
+/// ignored.
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> anyhow::Result<String> {
+    use std::{io::Read, os::fd::FromRawFd};
+
+    // The file closes its descriptor when dropped, the terminal must stay
+    anyhow::ensure!(
+        fd > 2,
+        "--password-fd {fd} is standard input, output or error, use 3 or above"
+    );
+    // SAFETY: the descriptor was handed to us for this and isn't used
+    // anywhere else, it is closed once read
+    let mut file = unsafe { fs::File::from_raw_fd(fd) };
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_: i32) -> anyhow::Result<String> {
+    anyhow::bail!("--password-fd is only supported on Unix")
+}
+
+#[cfg(unix)]
+fn warn_if_world_readable(path: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.permissions().mode() & 0o004 != 0 {
+            warn!(path = %path.display(), "password file is world-readable, chmod 600 it");
+            eprintln!(
+                "Warning: {} is readable by everyone, chmod 600 it.",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_world_readable(_: &std::path::Path) {}
+
+#[cfg(feature = "keyring")]
+fn read_keyring(service: &str) -> anyhow::Result<String> {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let entry = keyring::Entry::new(service, &user)?;
+    entry
+        .get_password()
+        .with_context(|| format!("no password in the keyring for '{service}' (user '{user}')"))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn read_keyring(_: &str) -> anyhow::Result<String> {
+    anyhow::bail!("built without keyring support, enable the keyring feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_password_file_is_refused() {
+        let path = std::env::temp_dir().join(format!("screenlock-empty-{}", std::process::id()));
+        fs::write(&path, "  \nhunter2\n").unwrap();
+        let read = PasswordSource::File(path.clone()).read();
+        fs::remove_file(&path).unwrap();
+        assert!(read.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn standard_streams_are_no_password_fd() {
+        for fd in 0..=2 {
+            assert!(PasswordSource::Fd(fd).read().is_err());
+        }
+    }
+}
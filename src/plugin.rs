@@ -0,0 +1,194 @@
+//! External widgets: a plugin is any program that speaks JSON lines over
+//! stdio. It is started with the lock session and killed when it ends.
+//!
+//! Plugin to screenlock, one object per line:
+//! - `{"type": "text", "text": "☀ 18°C"}` replaces the widget's line
+//! - `{"type": "want_keys"}` starts forwarding key presses to the plugin
+//! - `{"type": "unlock"}` ends the lock, for custom authentication
+//!
+//! screenlock to plugin, once keys are wanted:
+//! - `{"type": "key", "key": "a"}`, with `enter`, `backspace` and `esc` for
+//!   those keys
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{Event, KeyCode, KeyEvent},
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    controller::{DrawContext, EventContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    hooks, layout, session, theme,
+};
+
+/// How often messages from the plugin are picked up.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Text { text: String },
+    WantKeys,
+    Unlock,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Input<'a> {
+    Key { key: &'a str },
+}
+
+/// Runs a plugin command and shows its text on a footer row of its own.
+pub struct PluginEntity {
+    id: String,
+    command: String,
+    row: u16,
+    allow_unlock: bool,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    inbox: Arc<Mutex<Vec<Message>>>,
+    text: String,
+    want_keys: bool,
+}
+
+impl PluginEntity {
+    /// Start `command` through the shell. `row` is the plugin's line within
+    /// the footer.
+    pub fn spawn(id: &str, command: &str, row: u16) -> anyhow::Result<Self> {
+        let mut child = hooks::shell_command(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let inbox = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&inbox);
+        let name = command.to_string();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str::<Message>(&line) {
+                    Ok(message) => sink.lock().unwrap_or_else(|e| e.into_inner()).push(message),
+                    Err(error) => {
+                        warn!(plugin = %name, %error, "ignoring malformed plugin message")
+                    }
+                }
+            }
+        });
+        info!(plugin = command, "plugin started");
+        Ok(PluginEntity {
+            id: format!("PluginEntity-{id}"),
+            command: command.to_string(),
+            row,
+            allow_unlock: true,
+            stdin: child.stdin.take(),
+            child,
+            inbox,
+            text: String::new(),
+            want_keys: false,
+        })
+    }
+
+    /// Ignore `unlock` messages, e.g. in strict mode.
+    pub fn set_allow_unlock(&mut self, allow: bool) {
+        self.allow_unlock = allow;
+    }
+
+    fn send(&mut self, input: Input) {
+        let Some(stdin) = &mut self.stdin else {
+            return;
+        };
+        let sent = serde_json::to_string(&input)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(writeln!(stdin, "{line}")?));
+        if let Err(error) = sent {
+            warn!(plugin = %self.command, %error, "plugin stopped reading input");
+            self.stdin = None;
+        }
+    }
+}
+
+impl Drop for PluginEntity {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Named for PluginEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for PluginEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, _) = layout::terminal_size();
+        let text: String = self.text.chars().take(width as usize).collect();
+        draw_context
+            .out
+            .queue(MoveTo(0, layout::footer_y() + self.row))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(SetForegroundColor(theme::current().accent))?
+            .queue(Print(text))?
+            .queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        let messages = std::mem::take(&mut *self.inbox.lock().unwrap_or_else(|e| e.into_inner()));
+        for message in messages {
+            match message {
+                Message::Text { text } => self.text = text,
+                Message::WantKeys => self.want_keys = true,
+                Message::Unlock if self.allow_unlock => {
+                    info!(plugin = %self.command, "unlocked by plugin");
+                    session::set_exit_reason("plugin");
+                    return UpdateResult::kill();
+                }
+                Message::Unlock => warn!(plugin = %self.command, "ignoring plugin unlock"),
+            }
+        }
+        UpdateResult::nop()
+    }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        if !self.want_keys {
+            return false;
+        }
+        let Event::Key(KeyEvent { code, .. }) = event.event else {
+            return false;
+        };
+        let mut buf = [0; 4];
+        let key = match code {
+            KeyCode::Char(c) => &*c.encode_utf8(&mut buf),
+            KeyCode::Enter => "enter",
+            KeyCode::Backspace => "backspace",
+            KeyCode::Esc => "esc",
+            _ => return false,
+        };
+        self.send(Input::Key { key });
+        // Whatever the plugin makes of it arrives with the next update
+        false
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(POLL_INTERVAL)
+    }
+}
@@ -0,0 +1,122 @@
+//! What `--on-expire` does once a lock ran out: suspend, hibernate or power
+//! off the machine with the lock still on, so it wakes up locked, or run a
+//! command as the lock ends.
+
+use std::thread;
+
+use tracing::{info, warn};
+
+use crate::{count_down_entity::OnExpire, hooks};
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    fn systemctl(verb: &str) -> anyhow::Result<()> {
+        let status = Command::new("systemctl").arg(verb).status()?;
+        anyhow::ensure!(status.success(), "systemctl {verb} failed with {status}");
+        Ok(())
+    }
+
+    pub fn suspend() -> anyhow::Result<()> {
+        systemctl("suspend")
+    }
+
+    pub fn hibernate() -> anyhow::Result<()> {
+        systemctl("hibernate")
+    }
+
+    pub fn shutdown() -> anyhow::Result<()> {
+        systemctl("poweroff")
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> anyhow::Result<()> {
+        let status = Command::new(program).args(args).status()?;
+        anyhow::ensure!(status.success(), "{program} failed with {status}");
+        Ok(())
+    }
+
+    pub fn suspend() -> anyhow::Result<()> {
+        run("pmset", &["sleepnow"])
+    }
+
+    pub fn hibernate() -> anyhow::Result<()> {
+        anyhow::bail!("hibernating is up to `pmset hibernatemode` on macOS, use suspend")
+    }
+
+    pub fn shutdown() -> anyhow::Result<()> {
+        run(
+            "osascript",
+            &["-e", "tell application \"System Events\" to shut down"],
+        )
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> anyhow::Result<()> {
+        let status = Command::new(program).args(args).status()?;
+        anyhow::ensure!(status.success(), "{program} failed with {status}");
+        Ok(())
+    }
+
+    pub fn suspend() -> anyhow::Result<()> {
+        run("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"])
+    }
+
+    pub fn hibernate() -> anyhow::Result<()> {
+        run("shutdown", &["/h"])
+    }
+
+    pub fn shutdown() -> anyhow::Result<()> {
+        run("shutdown", &["/s", "/t", "0"])
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    pub fn suspend() -> anyhow::Result<()> {
+        anyhow::bail!("suspending is not supported on this platform")
+    }
+
+    pub fn hibernate() -> anyhow::Result<()> {
+        anyhow::bail!("hibernating is not supported on this platform")
+    }
+
+    pub fn shutdown() -> anyhow::Result<()> {
+        anyhow::bail!("powering off is not supported on this platform")
+    }
+}
+
+/// Do what `on_expire` asks of a lock that just ran out. Called from within
+/// the session, on a thread of its own so the lock keeps drawing while e.g.
+/// `systemctl suspend` waits for the machine to go to sleep.
+pub fn on_expired(on_expire: &OnExpire) {
+    if matches!(on_expire, OnExpire::Unlock | OnExpire::Lock) {
+        return;
+    }
+    let on_expire = on_expire.clone();
+    thread::spawn(move || {
+        let result = match &on_expire {
+            OnExpire::Unlock | OnExpire::Lock => Ok(()),
+            OnExpire::Suspend => platform::suspend(),
+            OnExpire::Hibernate => platform::hibernate(),
+            OnExpire::Shutdown => platform::shutdown(),
+            OnExpire::Command(command) => {
+                hooks::spawn(command, &[]);
+                Ok(())
+            }
+        };
+        match result {
+            Ok(()) => info!(%on_expire, "lock ran out, acted on it"),
+            Err(error) => warn!(?error, %on_expire, "could not act on the lock running out"),
+        }
+    });
+}
@@ -0,0 +1,153 @@
+use std::io::Write;
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    QueueableCommand,
+};
+use qrcode::QrCode;
+
+use crate::{
+    controller::{DrawContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    layout, text, theme,
+};
+
+/// Light modules around the code, scanners need some margin.
+const QUIET_ZONE: usize = 2;
+
+/// How big a module is drawn. Terminal cells are about twice as high as
+/// wide, so both keep modules square.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scale {
+    /// Two columns and one row per module
+    Large,
+    /// One column and half a row per module, using half blocks
+    Compact,
+}
+
+/// A QR code of arbitrary data, e.g. guest Wi-Fi credentials or a link,
+/// centered in the space between the prompt and the footer and as large as
+/// fits there. Black on white whatever the terminal's colors, so phones read
+/// it.
+pub struct QrCodeEntity {
+    id: String,
+    /// Dark modules row by row, quiet zone included
+    modules: Vec<Vec<bool>>,
+    label: Option<String>,
+}
+
+impl QrCodeEntity {
+    pub fn new(id: &str, data: &str, label: Option<&str>) -> anyhow::Result<Self> {
+        let code = QrCode::new(data.as_bytes())?;
+        let width = code.width();
+        let colors = code.to_colors();
+        let size = width + 2 * QUIET_ZONE;
+        let mut modules = vec![vec![false; size]; size];
+        for (idx, color) in colors.iter().enumerate() {
+            modules[idx / width + QUIET_ZONE][idx % width + QUIET_ZONE] =
+                *color == qrcode::Color::Dark;
+        }
+        Ok(QrCodeEntity {
+            id: format!("QrCodeEntity-{id}"),
+            modules,
+            label: label.map(|label| theme::glyphs(label).into_owned()),
+        })
+    }
+
+    /// Columns and rows the code takes at `scale`.
+    fn size(&self, scale: Scale) -> (u16, u16) {
+        let modules = self.modules.len() as u16;
+        match scale {
+            Scale::Large => (modules * 2, modules),
+            Scale::Compact => (modules, modules.div_ceil(2)),
+        }
+    }
+
+    /// The largest scale that fits `width` by `height`, `None` if not even
+    /// the compact one does.
+    fn fit(&self, width: u16, height: u16) -> Option<Scale> {
+        [Scale::Large, Scale::Compact].into_iter().find(|&scale| {
+            let (columns, rows) = self.size(scale);
+            columns <= width && rows <= height
+        })
+    }
+
+    fn draw_rows(
+        &self,
+        draw_context: &mut DrawContext,
+        x: u16,
+        top: u16,
+        scale: Scale,
+    ) -> anyhow::Result<()> {
+        let color = |dark: bool| if dark { Color::Black } else { Color::White };
+        let out = &mut draw_context.out;
+        match scale {
+            Scale::Large => {
+                for (row, line) in self.modules.iter().enumerate() {
+                    out.queue(MoveTo(x, top + row as u16))?;
+                    for &dark in line {
+                        out.queue(SetBackgroundColor(color(dark)))?
+                            .queue(Print("  "))?;
+                    }
+                    out.queue(ResetColor)?;
+                }
+            }
+            Scale::Compact => {
+                for (row, pair) in self.modules.chunks(2).enumerate() {
+                    out.queue(MoveTo(x, top + row as u16))?;
+                    for col in 0..self.modules.len() {
+                        let upper = pair[0][col];
+                        let lower = pair.get(1).is_some_and(|line| line[col]);
+                        out.queue(SetForegroundColor(color(upper)))?
+                            .queue(SetBackgroundColor(color(lower)))?
+                            .queue(Print('▀'))?;
+                    }
+                    out.queue(ResetColor)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Named for QrCodeEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for QrCodeEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, _) = layout::terminal_size();
+        let space_top = layout::body_y();
+        let label_rows = self.label.is_some() as u16;
+        let space = layout::footer_y()
+            .saturating_sub(space_top)
+            .saturating_sub(label_rows);
+        // Rather nothing than a cut off code nobody can scan
+        let Some(scale) = self.fit(width, space) else {
+            return Ok(());
+        };
+        let (columns, rows) = self.size(scale);
+        let top = space_top + (space - rows) / 2;
+        self.draw_rows(draw_context, (width - columns) / 2, top, scale)?;
+        if let Some(label) = &self.label {
+            let x = (width as usize).saturating_sub(text::width(label)) as u16 / 2;
+            draw_context
+                .out
+                .queue(MoveTo(x, top + rows))?
+                .queue(Print(label))?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _: UpdateContext) -> UpdateResult {
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
+}
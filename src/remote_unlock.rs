@@ -0,0 +1,138 @@
+//! One-time unlock links: every lock gets a fresh random token, and
+//! `/unlock/<token>`, e.g. opened on a phone, shows a page whose button
+//! unlocks it. Only that button's POST counts, so link previews and
+//! prefetching don't unlock. The token is forgotten once used or once the
+//! lock ends.
+
+use std::{
+    net::{IpAddr, SocketAddr, UdpSocket},
+    sync::Mutex,
+    thread,
+};
+
+use rand::Rng;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+use crate::{
+    commands::{self, Command},
+    count_down_entity::Trigger,
+    password_source::constant_time_eq,
+};
+
+const CONFIRM_PAGE: &str = "<!DOCTYPE html>
+<html>
+<head><meta name=\"viewport\" content=\"width=device-width\"><title>screenlock</title></head>
+<body>
+<form method=\"post\"><button type=\"submit\">Unlock now</button></form>
+</body>
+</html>
+";
+
+/// Where the links point, set once the server runs.
+static BASE_URL: Mutex<Option<String>> = Mutex::new(None);
+/// The token of the running lock, `None` while unlocked or once used.
+static TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+fn generate_token() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The address other devices on the network reach us at, for a server bound
+/// to all interfaces. Connecting a UDP socket sends nothing, it only picks
+/// the interface with the default route.
+fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+fn page(text: &str, status: u16) -> Response<std::io::Cursor<Vec<u8>>> {
+    let content_type =
+        Header::from_bytes("Content-Type", "text/plain; charset=utf-8").expect("static header");
+    Response::from_string(text)
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn confirm_page() -> Response<std::io::Cursor<Vec<u8>>> {
+    let content_type =
+        Header::from_bytes("Content-Type", "text/html; charset=utf-8").expect("static header");
+    Response::from_string(CONFIRM_PAGE).with_header(content_type)
+}
+
+/// Whether `token` is the one of the running lock, taking it if `consume`.
+fn check_token(token: &str, consume: bool) -> bool {
+    let mut current = TOKEN.lock().unwrap_or_else(|e| e.into_inner());
+    let valid = current
+        .as_ref()
+        .is_some_and(|current| constant_time_eq(current.as_bytes(), token.as_bytes()));
+    if valid && consume {
+        current.take();
+    }
+    valid
+}
+
+fn answer(request: &Request) -> Response<std::io::Cursor<Vec<u8>>> {
+    let token = request.url().strip_prefix("/unlock/").unwrap_or_default();
+    match request.method() {
+        Method::Get if check_token(token, false) => confirm_page(),
+        Method::Post if check_token(token, true) => {
+            info!(remote = ?request.remote_addr(), "unlocked through link");
+            commands::send(Command::Unlock, Trigger::Link);
+            page("Unlocked.", 200)
+        }
+        _ => {
+            warn!(remote = ?request.remote_addr(), "unknown or used unlock link");
+            page("This link is not valid (anymore).", 404)
+        }
+    }
+}
+
+/// Serve unlock links on `addr` from a background thread. Links point at
+/// `base_url` if given, else at `addr`.
+pub fn serve(addr: SocketAddr, base_url: Option<String>) -> anyhow::Result<()> {
+    let server = Server::http(addr).map_err(|e| anyhow::anyhow!(e))?;
+    info!(%addr, "serving remote unlock links");
+    let base_url = base_url.unwrap_or_else(|| {
+        let ip = match addr.ip() {
+            ip if ip.is_unspecified() => local_ip().unwrap_or(ip),
+            ip => ip,
+        };
+        format!("http://{}", SocketAddr::new(ip, addr.port()))
+    });
+    *BASE_URL.lock().unwrap_or_else(|e| e.into_inner()) =
+        Some(base_url.trim_end_matches('/').to_string());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = answer(&request);
+            if let Err(error) = request.respond(response) {
+                warn!(?error, "could not answer unlock request");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Make a fresh token for the lock that is starting and return its link,
+/// `None` if the server isn't running.
+pub fn arm() -> Option<String> {
+    let base_url = BASE_URL.lock().unwrap_or_else(|e| e.into_inner()).clone()?;
+    let token = generate_token();
+    let link = format!("{base_url}/unlock/{token}");
+    *TOKEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(token);
+    Some(link)
+}
+
+/// The link of the running lock, if it wasn't used yet.
+pub fn link() -> Option<String> {
+    let base_url = BASE_URL.lock().unwrap_or_else(|e| e.into_inner()).clone()?;
+    let token = TOKEN.lock().unwrap_or_else(|e| e.into_inner()).clone()?;
+    Some(format!("{base_url}/unlock/{token}"))
+}
+
+/// Invalidate the link once the lock ended.
+pub fn disarm() {
+    TOKEN.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
@@ -0,0 +1,378 @@
+//! Recurring locks. A schedule is a comma separated list of entries:
+//!
+//! - `50m work / 10m lock`: lock for 10 minutes after every 50 minutes unlocked
+//! - `:50 [for 10m]`: every hour at minute 50
+//! - `12:30 [for 45m]`: every day at 12:30
+//!
+//! Entries without `for` lock for `--for`.
+
+use std::{str::FromStr, sync::Mutex, thread, time::Duration};
+
+use chrono::{DateTime, Local, NaiveTime, Timelike};
+use tracing::{info, warn};
+
+use crate::{
+    bus,
+    commands::{self, Command},
+    count_down_entity::Trigger,
+    hooks, parse_duration, session,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Entry {
+    Cycle {
+        work: Duration,
+        lock: Duration,
+    },
+    Hourly {
+        minute: u32,
+        lock: Option<Duration>,
+    },
+    Daily {
+        time: NaiveTime,
+        lock: Option<Duration>,
+    },
+}
+
+impl FromStr for Entry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((work, lock)) = s.split_once('/') {
+            let work = work.trim().trim_end_matches("work").trim();
+            let lock = lock.trim().trim_end_matches("lock").trim();
+            return Ok(Entry::Cycle {
+                work: parse_duration(work)?,
+                lock: parse_duration(lock)?,
+            });
+        }
+        let mut words = s.split_whitespace();
+        let at = words.next().ok_or("Empty schedule entry")?;
+        let lock = match (words.next(), words.next()) {
+            (None, _) => None,
+            (Some("for"), Some(duration)) => Some(parse_duration(duration)?),
+            _ => return Err(format!("Expected 'for <duration>' after '{at}'")),
+        };
+        if let Some(minute) = at.strip_prefix(':') {
+            let minute = minute
+                .parse::<u32>()
+                .ok()
+                .filter(|m| *m < 60)
+                .ok_or_else(|| format!("Invalid minute in '{at}'"))?;
+            return Ok(Entry::Hourly { minute, lock });
+        }
+        let time = NaiveTime::parse_from_str(at, "%H:%M")
+            .map_err(|_| format!("Expected HH:MM or :MM, got '{at}'"))?;
+        Ok(Entry::Daily { time, lock })
+    }
+}
+
+impl Entry {
+    /// The first time this entry fires after `after`. Cycles count from
+    /// `cycle_start`, the last time the screen was unlocked.
+    fn next_after(
+        &self,
+        after: DateTime<Local>,
+        cycle_start: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        match self {
+            Entry::Cycle { work, .. } => Some(cycle_start + *work),
+            Entry::Hourly { minute, .. } => {
+                let at = after
+                    .with_minute(*minute)?
+                    .with_second(0)?
+                    .with_nanosecond(0)?;
+                Some(if at <= after {
+                    at + chrono::Duration::hours(1)
+                } else {
+                    at
+                })
+            }
+            Entry::Daily { time, .. } => {
+                let today = after
+                    .date_naive()
+                    .and_time(*time)
+                    .and_local_timezone(Local)
+                    .earliest()?;
+                Some(if today <= after {
+                    today + chrono::Duration::days(1)
+                } else {
+                    today
+                })
+            }
+        }
+    }
+
+    fn lock(&self) -> Option<Duration> {
+        match self {
+            Entry::Cycle { lock, .. } => Some(*lock),
+            Entry::Hourly { lock, .. } | Entry::Daily { lock, .. } => *lock,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    entries: Vec<Entry>,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entries = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Entry>, _>>()?;
+        Ok(Schedule { entries })
+    }
+}
+
+impl Schedule {
+    /// The next lock after `after` and how long it should last.
+    pub fn next_after(
+        &self,
+        after: DateTime<Local>,
+        cycle_start: DateTime<Local>,
+    ) -> Option<(DateTime<Local>, Option<Duration>)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| Some((entry.next_after(after, cycle_start)?, entry.lock())))
+            .min_by_key(|(at, _)| *at)
+    }
+}
+
+/// How long `delay` without a duration postpones the next lock.
+pub const DELAY_STEP: Duration = Duration::from_secs(5 * 60);
+
+static POSTPONE: Mutex<Option<(Duration, Trigger)>> = Mutex::new(None);
+
+/// Ask the scheduler to push the upcoming lock back by `by`.
+pub fn postpone(by: Duration, trigger: Trigger) {
+    *POSTPONE.lock().unwrap_or_else(|e| e.into_inner()) = Some((by, trigger));
+}
+
+fn take_postpone() -> Option<(Duration, Trigger)> {
+    POSTPONE.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// The next lock the scheduler is waiting for.
+struct Pending {
+    at: DateTime<Local>,
+    lock: Option<Duration>,
+    warned: bool,
+    postponed: bool,
+}
+
+/// Queue a `Lock` command whenever the schedule says so, announcing it
+/// `warn_before` ahead. Locks that come due while the screen is already
+/// locked are skipped.
+pub fn spawn(schedule: Schedule, warn_before: Duration) {
+    bus::enable();
+    thread::spawn(move || {
+        let mut cycle_start = Local::now();
+        let mut was_locked = false;
+        let mut pending: Option<Pending> = None;
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let now = Local::now();
+            let locked = session::stats().locked;
+            if was_locked && !locked {
+                cycle_start = now;
+            }
+            was_locked = locked;
+            if locked {
+                pending = None;
+                continue;
+            }
+            if pending.is_none() {
+                pending = schedule
+                    .next_after(now, cycle_start)
+                    .map(|(at, lock)| Pending {
+                        at,
+                        lock,
+                        warned: false,
+                        postponed: false,
+                    });
+            }
+            let Some(next) = pending.as_mut() else {
+                continue;
+            };
+            if let Some((by, trigger)) = take_postpone() {
+                if next.postponed {
+                    warn!(%trigger, "next lock was already postponed once");
+                } else {
+                    next.at += by;
+                    next.postponed = true;
+                    next.warned = false;
+                    info!(%trigger, ?by, "next lock postponed");
+                    println!("Next lock postponed by {by:?}.");
+                }
+            }
+            if !next.warned && !warn_before.is_zero() && now + warn_before >= next.at {
+                next.warned = true;
+                announce(next.at - now, !next.postponed);
+            }
+            if now >= next.at {
+                info!(lock = ?next.lock, "scheduled lock due");
+                commands::send(Command::Lock(next.lock), Trigger::Schedule);
+                cycle_start = now;
+                pending = None;
+            }
+        }
+    });
+}
+
+fn announce(until: chrono::Duration, can_postpone: bool) {
+    let secs = until.num_seconds().max(0);
+    let mut body = format!("The screen locks in {secs}s.");
+    if can_postpone {
+        body.push_str(" Send 'delay' to postpone it once by 5 minutes.");
+    }
+    info!(secs, "warning about scheduled lock");
+    println!("⚠️  {body}");
+    hooks::desktop_notification("screenlock", &body);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 1, day, hour, minute, second)
+            .unwrap()
+    }
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn entries_parse() {
+        assert_eq!(
+            "50m work / 10m lock".parse(),
+            Ok(Entry::Cycle {
+                work: minutes(50),
+                lock: minutes(10)
+            })
+        );
+        assert_eq!(
+            "25m/5m".parse(),
+            Ok(Entry::Cycle {
+                work: minutes(25),
+                lock: minutes(5)
+            })
+        );
+        assert_eq!(
+            ":50".parse(),
+            Ok(Entry::Hourly {
+                minute: 50,
+                lock: None
+            })
+        );
+        assert_eq!(
+            " :05 for 10m ".parse(),
+            Ok(Entry::Hourly {
+                minute: 5,
+                lock: Some(minutes(10))
+            })
+        );
+        assert_eq!(
+            "12:30 for 45m".parse(),
+            Ok(Entry::Daily {
+                time: NaiveTime::from_hms_opt(12, 30, 0).unwrap(),
+                lock: Some(minutes(45))
+            })
+        );
+    }
+
+    #[test]
+    fn garbage_and_out_of_range_times_are_refused() {
+        for entry in [
+            "",
+            "lunch",
+            "50m work / soon",
+            ":60",
+            ":-5",
+            "24:00",
+            "12:60",
+            "12:30 until 13:00",
+            "12:30 for",
+            "12:30 for ages",
+        ] {
+            assert!(entry.parse::<Entry>().is_err(), "{entry:?}");
+        }
+        assert!("12:30, nonsense".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn hourly_entries_fire_at_their_minute_or_the_next_hour() {
+        let entry = Entry::Hourly {
+            minute: 50,
+            lock: None,
+        };
+        let start = at(15, 8, 0, 0);
+        assert_eq!(
+            entry.next_after(at(15, 9, 20, 0), start),
+            Some(at(15, 9, 50, 0))
+        );
+        assert_eq!(
+            entry.next_after(at(15, 9, 50, 0), start),
+            Some(at(15, 10, 50, 0))
+        );
+        assert_eq!(
+            entry.next_after(at(15, 23, 55, 30), start),
+            Some(at(16, 0, 50, 0))
+        );
+    }
+
+    #[test]
+    fn daily_entries_fire_today_or_tomorrow() {
+        let entry = Entry::Daily {
+            time: NaiveTime::from_hms_opt(12, 30, 0).unwrap(),
+            lock: None,
+        };
+        let start = at(15, 8, 0, 0);
+        assert_eq!(
+            entry.next_after(at(15, 11, 0, 0), start),
+            Some(at(15, 12, 30, 0))
+        );
+        assert_eq!(
+            entry.next_after(at(15, 12, 30, 0), start),
+            Some(at(16, 12, 30, 0))
+        );
+        assert_eq!(
+            entry.next_after(at(31, 23, 59, 59), start),
+            Local.with_ymd_and_hms(2024, 2, 1, 12, 30, 0).single()
+        );
+    }
+
+    #[test]
+    fn cycles_count_from_the_last_unlock() {
+        let entry = Entry::Cycle {
+            work: minutes(50),
+            lock: minutes(10),
+        };
+        assert_eq!(
+            entry.next_after(at(15, 23, 30, 0), at(15, 23, 20, 0)),
+            Some(at(16, 0, 10, 0))
+        );
+    }
+
+    #[test]
+    fn the_schedule_picks_the_earliest_entry() {
+        let schedule: Schedule = "12:30 for 45m, :50".parse().unwrap();
+        let start = at(15, 8, 0, 0);
+        assert_eq!(
+            schedule.next_after(at(15, 12, 0, 0), start),
+            Some((at(15, 12, 30, 0), Some(minutes(45))))
+        );
+        assert_eq!(
+            schedule.next_after(at(15, 12, 40, 0), start),
+            Some((at(15, 12, 50, 0), None))
+        );
+    }
+}
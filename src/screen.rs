@@ -0,0 +1,252 @@
+//! A minimal terminal emulator: replays what entities drew into a grid of
+//! cells, so a frame can be captured as plain text for screenshots and golden
+//! tests. Only the sequences crossterm emits for our entities are understood;
+//! styling is dropped.
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crate::text;
+
+/// Growable byte buffer that stays readable after a `DrawContext` took a
+/// handle to it.
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    pub fn bytes(&self) -> Vec<u8> {
+        self.0.borrow().clone()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct ScreenBuffer {
+    width: u16,
+    height: u16,
+    /// What each cell shows. The cell right of a wide glyph is empty, the
+    /// glyph covers it.
+    cells: Vec<Vec<String>>,
+    x: u16,
+    y: u16,
+    saved: (u16, u16),
+}
+
+impl ScreenBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        ScreenBuffer {
+            width,
+            height,
+            cells: vec![vec![" ".to_string(); width as usize]; height as usize],
+            x: 0,
+            y: 0,
+            saved: (0, 0),
+        }
+    }
+
+    /// Blank columns `from..to` of row `y`. A wide glyph cut in half goes
+    /// entirely, like on a real terminal.
+    fn blank(&mut self, y: u16, from: usize, to: usize) {
+        let Some(row) = self.cells.get_mut(y as usize) else {
+            return;
+        };
+        let to = to.min(row.len());
+        if from >= to {
+            return;
+        }
+        let from = if row[from].is_empty() {
+            from.saturating_sub(1)
+        } else {
+            from
+        };
+        let to = if row.get(to).is_some_and(|cell| cell.is_empty()) {
+            to + 1
+        } else {
+            to
+        };
+        for cell in &mut row[from..to] {
+            *cell = " ".to_string();
+        }
+    }
+
+    fn clear_row_from(&mut self, y: u16, from: u16) {
+        self.blank(y, from as usize, self.width as usize);
+    }
+
+    fn put(&mut self, c: char) {
+        let (x, width) = (self.x as usize, text::char_width(c));
+        if width == 0 {
+            // Combining marks and variation selectors join the glyph before
+            if let Some(cell) = self
+                .cells
+                .get_mut(self.y as usize)
+                .and_then(|row| row.iter_mut().take(x).rev().find(|cell| !cell.is_empty()))
+            {
+                cell.push(c);
+            }
+            return;
+        }
+        self.blank(self.y, x, x + width);
+        if let Some(row) = self.cells.get_mut(self.y as usize) {
+            // A glyph that doesn't fit the row any more is dropped
+            if x + width <= row.len() {
+                row[x] = c.to_string();
+                for cell in &mut row[x + 1..x + width] {
+                    cell.clear();
+                }
+            }
+        }
+        self.x = self.x.saturating_add(width as u16);
+    }
+
+    /// Apply a CSI sequence with the given parameters and final character.
+    fn csi(&mut self, params: &str, action: char) {
+        let mut numbers = params
+            .trim_start_matches('?')
+            .split(';')
+            .map(|n| n.parse::<u16>().ok());
+        let mut next = |default: u16| numbers.next().flatten().unwrap_or(default);
+        match action {
+            'H' | 'f' => {
+                self.y = next(1).saturating_sub(1);
+                self.x = next(1).saturating_sub(1);
+            }
+            'G' => self.x = next(1).saturating_sub(1),
+            'A' => self.y = self.y.saturating_sub(next(1)),
+            'B' => self.y = self.y.saturating_add(next(1)),
+            'C' => self.x = self.x.saturating_add(next(1)),
+            'D' => self.x = self.x.saturating_sub(next(1)),
+            'K' => match next(0) {
+                0 => self.clear_row_from(self.y, self.x),
+                1 => self.blank(self.y, 0, self.x as usize + 1),
+                _ => self.clear_row_from(self.y, 0),
+            },
+            'J' => match next(0) {
+                0 => {
+                    self.clear_row_from(self.y, self.x);
+                    for y in self.y + 1..self.height {
+                        self.clear_row_from(y, 0);
+                    }
+                }
+                1 => {
+                    for y in 0..self.y {
+                        self.clear_row_from(y, 0);
+                    }
+                }
+                _ => {
+                    for y in 0..self.height {
+                        self.clear_row_from(y, 0);
+                    }
+                }
+            },
+            's' => self.saved = (self.x, self.y),
+            'u' => (self.x, self.y) = self.saved,
+            // Colors, attributes, cursor visibility
+            _ => {}
+        }
+    }
+
+    /// Interpret `bytes` as terminal output.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let text = String::from_utf8_lossy(bytes);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        for c in chars.by_ref() {
+                            if ('\u{40}'..='\u{7e}').contains(&c) {
+                                self.csi(&params, c);
+                                break;
+                            }
+                            params.push(c);
+                        }
+                    }
+                    Some('7') => self.saved = (self.x, self.y),
+                    Some('8') => (self.x, self.y) = self.saved,
+                    _ => {}
+                },
+                '\r' => self.x = 0,
+                '\n' => self.y = self.y.saturating_add(1),
+                c if c.is_control() => {}
+                c => self.put(c),
+            }
+        }
+    }
+
+    /// The screen as text, one line per row without trailing blanks.
+    pub fn contents(&self) -> String {
+        let mut lines: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| row.concat().trim_end().to_string())
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(width: u16, height: u16, output: &str) -> String {
+        let mut screen = ScreenBuffer::new(width, height);
+        screen.feed(output.as_bytes());
+        screen.contents()
+    }
+
+    #[test]
+    fn cursor_moves() {
+        let output = "\x1b[2;3Hab\x1b7\x1b[1;1Hc\x1b[2Cd\x1b8e\x1b[3;1Hf\x1b[Ag";
+        assert_eq!(render(10, 4, output), "c  d\n gabe\nf");
+    }
+
+    #[test]
+    fn clear_line() {
+        let output = "hello\r\nworld\r\nkeep\x1b[1;3H\x1b[K\x1b[2;1H\x1b[2K";
+        assert_eq!(render(10, 4, output), "he\n\nkeep");
+        let output = "hello\x1b[1;3H\x1b[1K";
+        assert_eq!(render(10, 1, output), "   lo");
+    }
+
+    #[test]
+    fn clear_below() {
+        let output = "one\r\ntwo\r\nthree\x1b[2;2H\x1b[J";
+        assert_eq!(render(10, 4, output), "one\nt");
+    }
+
+    #[test]
+    fn wide_glyphs_take_two_cells() {
+        assert_eq!(render(10, 1, "💻x\x1b[1;5Hy"), "💻x y");
+        assert_eq!(render(10, 1, "日本語"), "日本語");
+        // Overwriting either half of a wide glyph wipes all of it
+        assert_eq!(render(10, 1, "💻x\x1b[1;2Hz"), " zx");
+        assert_eq!(render(10, 1, "💻x\x1b[1;1Hz"), "z x");
+        assert_eq!(render(10, 1, "ab💻\x1b[1;4H\x1b[K"), "ab");
+        // One that doesn't fit the last column is dropped
+        assert_eq!(render(3, 1, "ab💻"), "ab");
+    }
+
+    #[test]
+    fn combining_marks_join_the_glyph_before() {
+        assert_eq!(render(10, 1, "e\u{301}x"), "e\u{301}x");
+        assert_eq!(render(10, 1, "e\u{301}\x1b[1;2Hy"), "e\u{301}y");
+        assert_eq!(render(10, 1, "💻\u{fe0f}y"), "💻\u{fe0f}y");
+    }
+}
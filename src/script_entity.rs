@@ -0,0 +1,223 @@
+//! Entities written in Rhai. A script may define any of these hooks:
+//!
+//! - `update()`, called every second
+//! - `on_event(key, value)`, called when a `ControlEvent` sets one of its
+//!   properties
+//! - `on_key(key)`, called for key presses (`enter`, `backspace`, `esc` or
+//!   the typed character)
+//!
+//! and use this API:
+//!
+//! - `draw(x, y, text)` puts text on screen; the lines drawn by one hook
+//!   replace those of the previous one
+//! - `property(key)` reads one of the entity's properties, `""` if unset
+//! - `emit(entity, key, value)` sends a `ControlEvent` to the entity of that
+//!   name, or to the controller's listeners for `Controller`
+//! - `unlock()` ends the lock, e.g. once a custom challenge was solved
+//! - `hour()` and `minute()` give the local time of day
+
+use std::{cell::RefCell, collections::HashMap, io::Write, path::Path, rc::Rc, time::Duration};
+
+use chrono::{Local, Timelike};
+use crossterm::{
+    cursor::MoveTo,
+    event::{Event, KeyCode, KeyEvent},
+    style::Print,
+    QueueableCommand,
+};
+use rhai::{Engine, Scope, AST};
+use tracing::{info, warn};
+
+use crate::{
+    controller::{ControlEvent, DrawContext, EventContext, Recipient, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    session, text,
+};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the script asked for through the API since the last update.
+#[derive(Default)]
+struct ScriptState {
+    draws: Vec<(u16, u16, String)>,
+    events: Vec<ControlEvent>,
+    unlock: bool,
+    properties: HashMap<String, String>,
+}
+
+fn coordinate(value: i64) -> u16 {
+    value.clamp(0, u16::MAX as i64) as u16
+}
+
+pub struct ScriptEntity {
+    id: String,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    state: Rc<RefCell<ScriptState>>,
+    properties: HashMap<String, String>,
+    allow_unlock: bool,
+    frame: Vec<(u16, u16, String)>,
+    stale: Vec<(u16, u16, usize)>,
+}
+
+impl ScriptEntity {
+    pub fn from_file(id: &str, path: &Path) -> anyhow::Result<Self> {
+        let state = Rc::new(RefCell::new(ScriptState::default()));
+        let mut engine = Engine::new();
+
+        let draws = Rc::clone(&state);
+        engine.register_fn("draw", move |x: i64, y: i64, text: &str| {
+            draws
+                .borrow_mut()
+                .draws
+                .push((coordinate(x), coordinate(y), text.to_string()));
+        });
+        let properties = Rc::clone(&state);
+        engine.register_fn("property", move |key: &str| {
+            properties
+                .borrow()
+                .properties
+                .get(key)
+                .cloned()
+                .unwrap_or_default()
+        });
+        let events = Rc::clone(&state);
+        engine.register_fn("emit", move |name: &str, key: &str, value: &str| {
+            events.borrow_mut().events.push(ControlEvent {
+                recipient: Recipient::Named(name.to_string()),
+                property_key: key.to_string(),
+                property_value: value.to_string(),
+            });
+        });
+        let unlock = Rc::clone(&state);
+        engine.register_fn("unlock", move || unlock.borrow_mut().unlock = true);
+        engine.register_fn("hour", || Local::now().hour() as i64);
+        engine.register_fn("minute", || Local::now().minute() as i64);
+
+        let ast = engine
+            .compile_file(path.into())
+            .map_err(|e| anyhow::anyhow!("could not load script {}: {e}", path.display()))?;
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| anyhow::anyhow!("script {} failed: {e}", path.display()))?;
+
+        Ok(ScriptEntity {
+            id: format!("ScriptEntity-{id}"),
+            engine,
+            ast,
+            scope,
+            state,
+            properties: HashMap::new(),
+            allow_unlock: true,
+            frame: Vec::new(),
+            stale: Vec::new(),
+        })
+    }
+
+    /// Ignore `unlock()`, e.g. in strict mode.
+    pub fn set_allow_unlock(&mut self, allow: bool) {
+        self.allow_unlock = allow;
+    }
+
+    /// Call `name` if the script defines it, returns whether it does. Errors
+    /// are logged, a broken hook should not take the lock down.
+    fn call_hook(&mut self, name: &str, args: impl rhai::FuncArgs) -> bool {
+        if !self.ast.iter_functions().any(|f| f.name == name) {
+            return false;
+        }
+        if let Err(error) =
+            self.engine
+                .call_fn::<rhai::Dynamic>(&mut self.scope, &self.ast, name, args)
+        {
+            warn!(script = %self.id, hook = name, %error, "script hook failed");
+        }
+        true
+    }
+}
+
+impl Named for ScriptEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl HasProperties for ScriptEntity {
+    fn get_property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(|s| s.as_str())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        self.properties.insert(key.to_string(), value.to_string());
+        self.state
+            .borrow_mut()
+            .properties
+            .insert(key.to_string(), value.to_string());
+        self.call_hook("on_event", (key.to_string(), value.to_string()));
+        true
+    }
+}
+
+impl FullEntity for ScriptEntity {}
+
+impl Entity for ScriptEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        for (x, y, width) in &self.stale {
+            draw_context
+                .out
+                .queue(MoveTo(*x, *y))?
+                .queue(Print(" ".repeat(*width)))?;
+        }
+        for (x, y, text) in &self.frame {
+            draw_context.out.queue(MoveTo(*x, *y))?.queue(Print(text))?;
+        }
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        self.call_hook("update", ());
+        let allow_unlock = self.allow_unlock;
+        let mut state = self.state.borrow_mut();
+        self.stale.clear();
+        if !state.draws.is_empty() {
+            self.stale = self
+                .frame
+                .iter()
+                .map(|(x, y, line)| (*x, *y, text::width(line)))
+                .collect();
+            self.frame = std::mem::take(&mut state.draws);
+        }
+        if std::mem::take(&mut state.unlock) {
+            if allow_unlock {
+                info!(script = %self.id, "unlocked by script");
+                session::set_exit_reason("script");
+                return UpdateResult::kill();
+            }
+            warn!(script = %self.id, "ignoring script unlock");
+        }
+        for event in state.events.drain(..) {
+            context.emit(event);
+        }
+        UpdateResult::nop()
+    }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        let Event::Key(KeyEvent { code, .. }) = event.event else {
+            return false;
+        };
+        let key = match code {
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            _ => return false,
+        };
+        self.call_hook("on_key", (key,))
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(UPDATE_INTERVAL)
+    }
+}
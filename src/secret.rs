@@ -0,0 +1,52 @@
+//! What a typed password is checked against, shared by the lock and the
+//! embeddable `LockScreen`.
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Argon2,
+};
+
+/// Longer input is ignored, nobody types a passphrase this long on purpose.
+pub const MAX_PASSWORD_LEN: usize = 256;
+
+/// What a typed password is checked against.
+#[derive(Clone, Debug)]
+pub enum Secret {
+    Plain(String),
+    /// An argon2 hash in PHC string format
+    Hash(String),
+}
+
+impl Secret {
+    /// `text` as typed at the lock: surrounding whitespace and case don't
+    /// count.
+    pub fn plain(text: &str) -> Self {
+        Secret::Plain(normalize(text))
+    }
+
+    pub fn matches(&self, password: &str) -> bool {
+        match self {
+            Secret::Plain(secret) => constant_time_eq(secret.as_bytes(), password.as_bytes()),
+            Secret::Hash(hash) => PasswordHash::new(hash).is_ok_and(|hash| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &hash)
+                    .is_ok()
+            }),
+        }
+    }
+}
+
+/// Compare all of `a` and `b` however early they differ, so the time taken
+/// tells nothing about how much of a guess was right.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = (0..a.len().max(b.len())).fold(a.len() ^ b.len(), |diff, idx| {
+        let (x, y) = (a.get(idx).unwrap_or(&0), b.get(idx).unwrap_or(&0));
+        diff | (x ^ y) as usize
+    });
+    diff == 0
+}
+
+/// How a password is compared: surrounding whitespace and case don't count.
+pub fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
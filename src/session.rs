@@ -0,0 +1,164 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Input further apart than this starts a new burst.
+const BURST_GAP: Duration = Duration::from_secs(5);
+
+/// What kind of input arrived during a lock. Which key it was is never
+/// recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    Key,
+    Click,
+    Move,
+}
+
+/// Input during a lock with no longer pause than `BURST_GAP` in between.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputBurst {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub keys: u32,
+    pub clicks: u32,
+    pub moves: u32,
+}
+
+/// State of the current lock session. Running totals are reported once it
+/// ends, the rest is served to status queries while it runs.
+#[derive(Clone, Debug)]
+pub struct SessionStats {
+    pub locked: bool,
+    /// What started the lock: `manual`, `idle` or the trigger of a command
+    pub reason: String,
+    /// Why the machine is locked, in the user's words (`--reason`)
+    pub note: Option<String>,
+    pub started_at: Option<SystemTime>,
+    /// Length of the lock, extensions included
+    pub total: Duration,
+    pub remaining: Duration,
+    pub failed_attempts: u32,
+    /// Key presses and clicks the grab swallowed
+    pub blocked_inputs: u32,
+    /// When there was input of any kind, for the tamper log
+    pub input_bursts: Vec<InputBurst>,
+    /// How the lock ended, e.g. `password` or `countdown`
+    pub exit_reason: String,
+    pub extended: Duration,
+    pub paused: Duration,
+}
+
+static STATS: Mutex<SessionStats> = Mutex::new(SessionStats {
+    locked: false,
+    reason: String::new(),
+    note: None,
+    started_at: None,
+    total: Duration::ZERO,
+    remaining: Duration::ZERO,
+    failed_attempts: 0,
+    blocked_inputs: 0,
+    input_bursts: Vec::new(),
+    exit_reason: String::new(),
+    extended: Duration::ZERO,
+    paused: Duration::ZERO,
+});
+
+/// Totals over all sessions since the process started.
+#[derive(Clone, Copy, Debug)]
+pub struct Totals {
+    pub sessions: u64,
+    pub locked: Duration,
+    pub failed_attempts: u64,
+}
+
+static TOTALS: Mutex<Totals> = Mutex::new(Totals {
+    sessions: 0,
+    locked: Duration::ZERO,
+    failed_attempts: 0,
+});
+
+pub fn start(reason: &str, note: Option<&str>) {
+    update_stats(|stats| {
+        stats.locked = true;
+        stats.reason = reason.to_string();
+        stats.note = note.map(str::to_string);
+        stats.started_at = Some(SystemTime::now());
+        stats.total = Duration::ZERO;
+        stats.remaining = Duration::ZERO;
+        stats.failed_attempts = 0;
+        stats.blocked_inputs = 0;
+        stats.input_bursts.clear();
+        stats.exit_reason = "unknown".to_string();
+        stats.extended = Duration::ZERO;
+        stats.paused = Duration::ZERO;
+    });
+}
+
+pub fn end() {
+    let stats = stats();
+    let mut totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+    totals.sessions += 1;
+    totals.locked += stats
+        .started_at
+        .and_then(|t| t.elapsed().ok())
+        .unwrap_or_default();
+    totals.failed_attempts += stats.failed_attempts as u64;
+    update_stats(|stats| {
+        stats.locked = false;
+        stats.remaining = Duration::ZERO;
+    });
+}
+
+/// Note input of `kind` during the lock, adding to the burst it belongs to.
+pub fn record_input(kind: InputKind) {
+    let now = Local::now();
+    update_stats(|stats| {
+        if !stats.locked {
+            return;
+        }
+        let ongoing = stats
+            .input_bursts
+            .last()
+            .is_some_and(|burst| (now - burst.end).to_std().is_ok_and(|gap| gap < BURST_GAP));
+        if !ongoing {
+            info!(?kind, "input during the lock");
+            stats.input_bursts.push(InputBurst {
+                start: now,
+                end: now,
+                keys: 0,
+                clicks: 0,
+                moves: 0,
+            });
+        }
+        let Some(burst) = stats.input_bursts.last_mut() else {
+            return;
+        };
+        burst.end = now;
+        match kind {
+            InputKind::Key => burst.keys += 1,
+            InputKind::Click => burst.clicks += 1,
+            InputKind::Move => burst.moves += 1,
+        }
+    });
+}
+
+pub fn set_exit_reason(reason: &str) {
+    update_stats(|stats| stats.exit_reason = reason.to_string());
+}
+
+pub fn totals() -> Totals {
+    *TOTALS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn stats() -> SessionStats {
+    STATS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn update_stats(f: impl FnOnce(&mut SessionStats)) {
+    f(&mut STATS.lock().unwrap_or_else(|e| e.into_inner()));
+}
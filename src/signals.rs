@@ -0,0 +1,100 @@
+//! What SIGTERM, SIGINT and SIGTSTP do to a running lock. Without this a
+//! `kill` would end it without restoring the terminal, and SIGTSTP would stop
+//! it and hand the shell back.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use signal_hook::{
+    consts::{SIGINT, SIGTERM, SIGTSTP},
+    iterator::Signals,
+};
+use tracing::{info, warn};
+
+use crate::{
+    bus::{self, Message},
+    ipc, session,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum SignalAction {
+    /// End the lock as if it was over, record it and quit
+    Exit,
+    /// Carry on as if nothing happened
+    Ignore,
+}
+
+/// The action for each signal we handle.
+#[derive(Clone, Copy, Debug)]
+pub struct SignalActions {
+    pub term: SignalAction,
+    pub int: SignalAction,
+    pub tstp: SignalAction,
+}
+
+impl SignalActions {
+    /// SIGTERM and SIGINT end the lock unless it is strict, SIGTSTP never
+    /// suspends it.
+    pub fn defaults(strict: bool) -> Self {
+        let stop = if strict {
+            SignalAction::Ignore
+        } else {
+            SignalAction::Exit
+        };
+        SignalActions {
+            term: stop,
+            int: stop,
+            tstp: SignalAction::Ignore,
+        }
+    }
+
+    fn get(&self, signal: i32) -> SignalAction {
+        match signal {
+            SIGTERM => self.term,
+            SIGINT => self.int,
+            _ => self.tstp,
+        }
+    }
+}
+
+static QUIT: AtomicBool = AtomicBool::new(false);
+
+/// Whether a signal asked us to quit once the current lock has ended.
+pub fn quit_requested() -> bool {
+    QUIT.load(Ordering::Relaxed)
+}
+
+fn name(signal: i32) -> &'static str {
+    match signal {
+        SIGTERM => "SIGTERM",
+        SIGINT => "SIGINT",
+        _ => "SIGTSTP",
+    }
+}
+
+/// Take over the signals and handle them on a background thread.
+pub fn install(actions: SignalActions) -> anyhow::Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGTSTP])?;
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            let signal_name = name(signal);
+            if actions.get(signal) == SignalAction::Ignore {
+                warn!(signal = signal_name, "ignoring signal");
+                continue;
+            }
+            info!(signal = signal_name, "quitting on signal");
+            QUIT.store(true, Ordering::Relaxed);
+            if session::stats().locked {
+                // The main loop records the session and cleans up
+                bus::publish(Message::Quit);
+            } else {
+                // Waiting for the next lock, there is nothing to tear down
+                ipc::remove_socket();
+                std::process::exit(0);
+            }
+        }
+    });
+    Ok(())
+}
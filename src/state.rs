@@ -0,0 +1,102 @@
+//! The running lock's state on disk, refreshed every few seconds, so a lock
+//! that was killed or crashed can be brought back where it left off.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::session;
+
+const WRITE_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockState {
+    pub started_at: SystemTime,
+    /// Length of the lock, extensions included
+    pub total_secs: u64,
+    pub remaining_secs: u64,
+    pub failed_attempts: u32,
+}
+
+impl LockState {
+    fn current() -> Option<Self> {
+        let stats = session::stats();
+        // Nothing to restore before the countdown first reported in
+        if !stats.locked || stats.total.is_zero() {
+            return None;
+        }
+        Some(LockState {
+            started_at: stats.started_at.unwrap_or_else(SystemTime::now),
+            total_secs: stats.total.as_secs(),
+            remaining_secs: stats.remaining.as_secs(),
+            failed_attempts: stats.failed_attempts,
+        })
+    }
+
+    /// How long the lock had been counting down.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs(self.total_secs.saturating_sub(self.remaining_secs))
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("screenlock").join("state.json"))
+}
+
+fn save(path: &Path, state: &LockState) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Written aside and renamed, so a kill never leaves half a file
+    let partial = path.with_extension("json.tmp");
+    fs::write(&partial, serde_json::to_vec(state)?)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// The state a killed or crashed lock left behind, `None` if it ended
+/// normally.
+pub fn load(path: &Path) -> anyhow::Result<Option<LockState>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Forget the state once the lock ended for good.
+pub fn remove(path: &Path) {
+    let _ = fs::remove_file(path);
+}
+
+/// Keep `path` up to date on a background thread: the current state while
+/// locked, no file otherwise.
+pub fn spawn_writer(path: PathBuf) {
+    thread::spawn(move || {
+        let mut saved = false;
+        loop {
+            match LockState::current() {
+                Some(state) => {
+                    if let Err(error) = save(&path, &state) {
+                        warn!(?error, path = %path.display(), "could not save lock state");
+                    }
+                    saved = true;
+                }
+                None if saved => {
+                    remove(&path);
+                    saved = false;
+                }
+                None => {}
+            }
+            thread::sleep(WRITE_INTERVAL);
+        }
+    });
+}
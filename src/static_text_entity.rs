@@ -1,40 +1,191 @@
 use crate::{
-    controller::DrawContext,
-    entity::{Entity, Named},
-    Lines, TITLE_Y,
+    controller::{DrawContext, EntityId, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, FullEntity, HasProperties, Named},
+    hooks, layout,
+    markup::{self, Span, Style},
+    tasks::{self, TaskHandle},
+    template, text, theme,
 };
 use crossterm::QueueableCommand;
-use crossterm::{cursor::MoveTo, style::Print};
-use std::io::Write;
+use crossterm::{
+    cursor::MoveTo,
+    style::{Attribute, Print, SetAttribute, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+use std::{fs, io::Write, path::PathBuf, time::Duration};
+use tracing::warn;
+
+/// Longest a message command may run before its output is given up on.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the lines of a `StaticTextEntity` come from.
+pub enum MessageSource {
+    Static(Vec<String>),
+    File(PathBuf),
+    Command(String),
+}
+
+impl MessageSource {
+    /// The lines to show, read from the file or command output.
+    pub fn load(&self) -> anyhow::Result<Vec<String>> {
+        let text = match self {
+            MessageSource::Static(lines) => return Ok(lines.clone()),
+            MessageSource::File(path) => fs::read_to_string(path)?,
+            MessageSource::Command(cmd) => hooks::output_with_timeout(cmd, LOAD_TIMEOUT)
+                .map_err(|error| anyhow::anyhow!("'{cmd}' {error}"))?,
+        };
+        Ok(text
+            .lines()
+            .map(|line| {
+                line.replace('\t', "    ")
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .collect()
+            })
+            .collect())
+    }
+}
 
 pub struct StaticTextEntity {
     id: String,
-    lines: Lines,
+    entity_id: EntityId,
+    heading: Option<Span>,
+    lines: Vec<String>,
+    rows: Vec<Vec<Span>>,
+    height_changed: bool,
+    /// Reloads the lines of a file or command on the task pool
+    _task: Option<TaskHandle>,
 }
 
 impl StaticTextEntity {
-    pub fn new(id: &str, lines: Lines) -> Self {
-        StaticTextEntity {
+    /// Show the lines from `source`, reloading it every `refresh`. Files and
+    /// commands are read in the background, the lines show once they are in.
+    pub fn with_source(id: &str, source: MessageSource, refresh: Duration) -> Self {
+        let entity_id = EntityId::next();
+        let (lines, task) = match source {
+            MessageSource::Static(lines) => (lines, None),
+            source => {
+                let task = tasks::every(entity_id, "lines", refresh, move || {
+                    source
+                        .load()
+                        .map(|lines| lines.join("\n"))
+                        .map_err(|error| error.to_string())
+                });
+                (Vec::new(), Some(task))
+            }
+        };
+        let mut entity = StaticTextEntity {
             id: format!("StaticTextEntity-{id}"),
-            lines,
-        }
+            entity_id,
+            heading: None,
+            lines: Vec::new(),
+            rows: Vec::new(),
+            height_changed: true,
+            _task: task,
+        };
+        entity.set_lines(&lines);
+        entity
+    }
+
+    /// Show `text` in bold above the lines, taken as is rather than as markup.
+    pub fn set_heading(&mut self, text: &str) {
+        self.heading = Some(Span {
+            text: theme::glyphs(text).into_owned(),
+            style: Style {
+                bold: true,
+                italic: false,
+                color: Some(theme::current().warning),
+            },
+        });
+    }
+
+    fn set_lines(&mut self, lines: &[String]) {
+        self.lines = lines
+            .iter()
+            .map(|line| theme::glyphs(&template::expand(line)).into_owned())
+            .collect();
     }
 }
 
 impl Entity for StaticTextEntity {
     fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
         // Static UI (title + explanation)
-        for (idx, line) in self.lines.iter().enumerate() {
-            draw_context.out.queue(MoveTo(0, TITLE_Y + idx as u16))?;
-            draw_context.out.queue(Print(line))?;
+        let top = layout::title_y();
+        let (width, _) = layout::terminal_size();
+        for (idx, row) in self.rows.iter().enumerate() {
+            let y = top + idx as u16;
+            let line: String = row.iter().map(|span| span.text.as_str()).collect();
+            let x = text::start_column(width, text::width(&line), text::is_rtl(&line));
+            draw_context.out.queue(MoveTo(0, y))?;
+            draw_context.out.queue(Clear(ClearType::CurrentLine))?;
+            draw_context.out.queue(MoveTo(x, y))?;
+            for span in row {
+                if span.style.bold {
+                    draw_context.out.queue(SetAttribute(Attribute::Bold))?;
+                }
+                if span.style.italic {
+                    draw_context.out.queue(SetAttribute(Attribute::Italic))?;
+                }
+                if let Some(color) = span.style.color {
+                    draw_context.out.queue(SetForegroundColor(color))?;
+                }
+                draw_context.out.queue(Print(&span.text))?;
+                draw_context.out.queue(SetAttribute(Attribute::Reset))?;
+            }
+        }
+        if self.height_changed {
+            // Everything below moved, drop what was drawn at the old rows
+            draw_context
+                .out
+                .queue(MoveTo(0, top + self.rows.len() as u16))?;
+            draw_context.out.queue(Clear(ClearType::FromCursorDown))?;
         }
         draw_context.out.flush()?;
         Ok(())
     }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        let (width, _) = context.size;
+        let heading = self.heading.iter().map(|span| vec![span.clone()]);
+        self.rows = heading
+            .chain(self.lines.iter().map(|line| markup::parse(line)))
+            .flat_map(|spans| markup::wrap(&spans, width as usize))
+            .collect();
+        self.height_changed = layout::set_title_height(self.rows.len() as u16);
+        UpdateResult::nop()
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::OnChange
+    }
 }
 
 impl Named for StaticTextEntity {
     fn get_name(&self) -> &str {
         self.id.as_str()
     }
+
+    fn entity_id(&self) -> Option<EntityId> {
+        Some(self.entity_id)
+    }
 }
+
+impl HasProperties for StaticTextEntity {
+    fn get_property(&self, _: &str) -> Option<&str> {
+        None
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "lines" => self.set_lines(&value.lines().map(str::to_string).collect::<Vec<_>>()),
+            "error" => {
+                warn!(error = value, "could not load lock message");
+                self.lines = vec![format!("⚠️ Could not load message: {value}")];
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl FullEntity for StaticTextEntity {}
@@ -0,0 +1,95 @@
+use std::{io::Write, time::Duration};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{Event, KeyEvent, KeyEventKind},
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+use crate::{
+    controller::{DrawContext, EventContext, UpdateContext, UpdateResult},
+    entity::{Cadence, Entity, Named},
+    layout, text, theme,
+};
+
+/// Shows how the lock went for a few seconds after it ended. Any key
+/// dismisses it early.
+pub struct SummaryEntity {
+    id: String,
+    lines: Vec<String>,
+    shown_for: Duration,
+    /// How long it has been up
+    shown: Duration,
+    dismissed: bool,
+}
+
+impl SummaryEntity {
+    pub fn new(id: &str, lines: Vec<String>, shown_for: Duration) -> Self {
+        SummaryEntity {
+            id: format!("SummaryEntity-{id}"),
+            lines,
+            shown_for,
+            shown: Duration::ZERO,
+            dismissed: false,
+        }
+    }
+}
+
+impl Named for SummaryEntity {
+    fn get_name(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+impl Entity for SummaryEntity {
+    fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+        let (width, height) = layout::terminal_size();
+        let top = height.saturating_sub(self.lines.len() as u16) / 2;
+        for (idx, line) in self.lines.iter().enumerate() {
+            let column = (width as usize).saturating_sub(text::width(line)) as u16 / 2;
+            draw_context
+                .out
+                .queue(MoveTo(0, top + idx as u16))?
+                .queue(Clear(ClearType::CurrentLine))?
+                .queue(MoveTo(column, top + idx as u16))?
+                .queue(SetForegroundColor(if idx == 0 {
+                    theme::current().ok
+                } else {
+                    Color::Reset
+                }))?
+                .queue(Print(theme::glyphs(line)))?;
+        }
+        draw_context.out.queue(ResetColor)?;
+        draw_context.out.flush()?;
+        Ok(())
+    }
+
+    fn update(&mut self, context: UpdateContext) -> UpdateResult {
+        self.shown += context.dt;
+        if self.dismissed || self.shown >= self.shown_for {
+            return UpdateResult::remove();
+        }
+        UpdateResult::nop()
+    }
+
+    fn handle_event(&mut self, event: EventContext) -> bool {
+        // Not a repeat of the key that just unlocked
+        if matches!(
+            event.event,
+            Event::Key(KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            })
+        ) {
+            self.dismissed = true;
+            return true;
+        }
+        false
+    }
+
+    fn cadence(&self) -> Cadence {
+        Cadence::Every(self.shown_for.saturating_sub(self.shown))
+    }
+}
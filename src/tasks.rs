@@ -0,0 +1,174 @@
+//! Background work for entities: slow commands, file reads and network checks
+//! run on a small pool of worker threads, never on the UI loop. Each result
+//! is published on the message bus as a `ControlEvent` setting a property on
+//! the entity that asked for it.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, Once, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bus::{self, Message},
+    controller::{ControlEvent, EntityId, Recipient},
+};
+
+const WORKERS: usize = 2;
+
+/// How often the scheduler looks for due tasks.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Longest a job may keep a worker waiting. A hung job is left to finish on
+/// its own thread and isn't queued again until it did, its result is dropped.
+const JOB_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnMut() -> Result<String, String> + Send>;
+
+struct Task {
+    entity: EntityId,
+    property_key: String,
+    interval: Duration,
+    job: Mutex<Job>,
+    next_run: Mutex<Instant>,
+    running: AtomicBool,
+    cancelled: Arc<AtomicBool>,
+}
+
+struct Pool {
+    tasks: Mutex<Vec<Arc<Task>>>,
+    queue: Mutex<Sender<Arc<Task>>>,
+    jobs: Mutex<Receiver<Arc<Task>>>,
+}
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+static STARTED: Once = Once::new();
+
+/// Tasks alive right now, so the controller knows to check for results.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+fn run(task: Arc<Task>) {
+    let (done, result) = mpsc::channel();
+    let job = Arc::clone(&task);
+    thread::spawn(move || {
+        let result = (job.job.lock().unwrap_or_else(|e| e.into_inner()))();
+        *job.next_run.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now() + job.interval;
+        job.running.store(false, Ordering::Relaxed);
+        let _ = done.send(result);
+    });
+    let result = match result.recv_timeout(JOB_TIMEOUT) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(format!("timed out after {JOB_TIMEOUT:?}")),
+        Err(RecvTimeoutError::Disconnected) => Err("the job panicked".to_string()),
+    };
+    if task.cancelled.load(Ordering::Relaxed) {
+        return;
+    }
+    let (property_key, property_value) = match result {
+        Ok(value) => (task.property_key.clone(), value),
+        Err(error) => ("error".to_string(), error),
+    };
+    bus::publish(Message::Event(ControlEvent {
+        recipient: Recipient::Entity(task.entity),
+        property_key,
+        property_value,
+    }));
+}
+
+/// Queue the tasks that are due. Cancelled ones are dropped here.
+fn schedule(pool: &Pool) {
+    let now = Instant::now();
+    let mut tasks = pool.tasks.lock().unwrap_or_else(|e| e.into_inner());
+    tasks.retain(|task| !task.cancelled.load(Ordering::Relaxed));
+    for task in tasks.iter() {
+        let due = *task.next_run.lock().unwrap_or_else(|e| e.into_inner()) <= now;
+        if due && !task.running.swap(true, Ordering::Relaxed) {
+            let _ = pool
+                .queue
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .send(Arc::clone(task));
+        }
+    }
+}
+
+impl Pool {
+    fn new() -> Self {
+        let (queue, jobs) = mpsc::channel();
+        Pool {
+            tasks: Mutex::new(Vec::new()),
+            queue: Mutex::new(queue),
+            jobs: Mutex::new(jobs),
+        }
+    }
+
+    fn start(&'static self) {
+        for _ in 0..WORKERS {
+            thread::spawn(move || loop {
+                let next = self.jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                let Ok(task) = next else {
+                    return;
+                };
+                run(task);
+            });
+        }
+        thread::spawn(move || loop {
+            schedule(self);
+            thread::sleep(TICK);
+        });
+    }
+}
+
+fn pool() -> &'static Pool {
+    let pool = POOL.get_or_init(Pool::new);
+    STARTED.call_once(|| pool.start());
+    pool
+}
+
+/// Stops its task when dropped, keep it in the entity the task reports to.
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Run `job` right away and then every `interval` after it finished, setting
+/// `property_key` on `entity` to what it returns, or its `error` property if
+/// it failed.
+pub fn every(
+    entity: EntityId,
+    property_key: &str,
+    interval: Duration,
+    job: impl FnMut() -> Result<String, String> + Send + 'static,
+) -> TaskHandle {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let task = Arc::new(Task {
+        entity,
+        property_key: property_key.to_string(),
+        interval,
+        job: Mutex::new(Box::new(job)),
+        next_run: Mutex::new(Instant::now()),
+        running: AtomicBool::new(false),
+        cancelled: Arc::clone(&cancelled),
+    });
+    pool()
+        .tasks
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(task);
+    ACTIVE.fetch_add(1, Ordering::Relaxed);
+    TaskHandle { cancelled }
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed) > 0
+}
@@ -0,0 +1,113 @@
+//! `{user}`, `{hostname}`, `{until_time}` and `{reason}` in configured text,
+//! e.g. "Locked by {user} until {until_time}", the same braces as the
+//! greetings' `{name}` and the feedback's `{secs}`. The values are fixed when
+//! a lock starts; other names, such as markup, are left as they are.
+
+use std::{
+    borrow::Cow,
+    process::{Command, Stdio},
+    sync::RwLock,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Local};
+
+static VARS: RwLock<Vec<(&str, String)>> = RwLock::new(Vec::new());
+
+fn hostname() -> String {
+    if let Ok(name) = std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME")) {
+        return name;
+    }
+    Command::new("hostname")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Fill in the values for a lock ending after `left`, locked for `reason`.
+pub fn start(left: Duration, reason: Option<&str>) {
+    let until = DateTime::<Local>::from(SystemTime::now() + left);
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    *VARS.write().unwrap_or_else(|e| e.into_inner()) = vec![
+        ("user", user),
+        ("hostname", hostname()),
+        ("until_time", until.format("%H:%M").to_string()),
+        ("reason", reason.unwrap_or_default().to_string()),
+    ];
+}
+
+/// `text` with the placeholders replaced.
+pub fn expand(text: &str) -> Cow<'_, str> {
+    if !text.contains('{') {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(expand_with(
+        text,
+        &VARS.read().unwrap_or_else(|e| e.into_inner()),
+    ))
+}
+
+/// `text` with each `{name}` in `vars` replaced by its value, in one pass so
+/// that braces in a value stay as they are.
+fn expand_with(text: &str, vars: &[(&str, String)]) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let (_, value) = vars.iter().find(|(name, _)| *name == &after[..close])?;
+            Some((value, close))
+        });
+        match value {
+            Some((value, close)) => {
+                expanded.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                expanded.push('{');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Vec<(&'static str, String)> {
+        vec![
+            ("user", "jane".to_string()),
+            ("until_time", "13:30".to_string()),
+            ("reason", "Lunch {user}".to_string()),
+        ]
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        assert_eq!(
+            expand_with("Locked by {user} until {until_time}", &vars()),
+            "Locked by jane until 13:30"
+        );
+        assert_eq!(expand_with("{user}{user}", &vars()), "janejane");
+        assert_eq!(expand_with("{reason}", &vars()), "Lunch {user}");
+    }
+
+    #[test]
+    fn unknown_names_and_stray_braces_are_kept() {
+        assert_eq!(
+            expand_with("{red}{user}{/red} waits {secs}s", &vars()),
+            "{red}jane{/red} waits {secs}s"
+        );
+        assert_eq!(expand_with("{ {user} }", &vars()), "{ jane }");
+        assert_eq!(expand_with("{user", &vars()), "{user");
+        assert_eq!(expand_with("${USER}", &vars()), "${USER}");
+    }
+}
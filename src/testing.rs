@@ -0,0 +1,275 @@
+//! Headless harness for exercising entities: run a `Controller` against
+//! scripted input and look at what it drew, without a real terminal.
+//!
+//! ```ignore
+//! let screen = FakeDrawContext::new();
+//! let mut events = ScriptedEvents::typing("hunter2");
+//! controller.run_with(&mut events, &mut screen.context())?;
+//! assert!(screen.output().contains("Enter password"));
+//! // Keys nobody read would reach the shell once a real terminal is let go
+//! assert_eq!(events.leftover(), 0);
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fs,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    thread,
+    time::Duration,
+};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    bus,
+    controller::{DrawContext, EventSource},
+    screen::SharedBuffer,
+};
+
+/// Run tests that go through process wide state, the bus, the layout and the
+/// session stats, one at a time. Messages an earlier test left are dropped.
+pub fn exclusive() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    bus::drain();
+    guard
+}
+
+/// Compare `frame` with `tests/golden/<name>.txt`. With `BLESS=1` set the
+/// file is written from `frame` instead, review the diff before committing.
+pub fn assert_golden(name: &str, frame: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.txt"));
+    let frame = format!("{frame}\n");
+    if std::env::var_os("BLESS").is_some() {
+        fs::write(&path, &frame).unwrap();
+        return;
+    }
+    let golden = fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("could not read {}: {error}", path.display()));
+    assert_eq!(frame, golden, "frame differs from {}", path.display());
+}
+
+/// A key press without modifiers.
+pub fn key(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+/// Collects everything the entities draw, escape sequences included.
+#[derive(Default)]
+pub struct FakeDrawContext {
+    buffer: SharedBuffer,
+}
+
+impl FakeDrawContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn context(&self) -> DrawContext {
+        DrawContext::with_writer(self.buffer.clone())
+    }
+
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.bytes()).into_owned()
+    }
+}
+
+/// Hands out a fixed list of events, then behaves like a terminal nobody
+/// touches, so time based behavior such as countdown expiry still plays out.
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        ScriptedEvents {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    /// Key presses for `text` followed by Enter.
+    pub fn typing(text: &str) -> Self {
+        Self::new(
+            text.chars()
+                .map(KeyCode::Char)
+                .chain([KeyCode::Enter])
+                .map(key),
+        )
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// How many of the scripted events the controller didn't read.
+    pub fn leftover(&self) -> usize {
+        self.events.len()
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn poll(&mut self, timeout: Duration) -> anyhow::Result<bool> {
+        if self.events.is_empty() {
+            thread::sleep(timeout);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn read(&mut self) -> anyhow::Result<Event> {
+        self.events
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("no scripted events left"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::{cursor::MoveTo, execute, style::Print};
+
+    use super::*;
+    use crate::{
+        base_entity::BaseEntity,
+        bus::Message,
+        controller::{Controller, EventContext, Scene, UpdateContext, UpdateResult},
+        entity::{Cadence, Entity, Named},
+        i18n,
+        lock_screen_builder::{Auth, LockScreenBuilder},
+        password_prompt_entity::{Credential, USER_CREDENTIAL},
+        password_source::Secret,
+        session,
+    };
+
+    /// Shows its name and ends its scene on the first key press, or right
+    /// away when `eager`.
+    struct Ender {
+        name: &'static str,
+        eager: bool,
+        pressed: bool,
+    }
+
+    impl Ender {
+        fn new(name: &'static str, eager: bool) -> BaseEntity<Self> {
+            BaseEntity::new(Ender {
+                name,
+                eager,
+                pressed: false,
+            })
+        }
+    }
+
+    impl Named for Ender {
+        fn get_name(&self) -> &str {
+            self.name
+        }
+    }
+
+    impl Entity for Ender {
+        fn draw(&self, draw_context: &mut DrawContext) -> anyhow::Result<()> {
+            execute!(draw_context.out, MoveTo(0, 0), Print(self.name))?;
+            Ok(())
+        }
+
+        fn update(&mut self, _: UpdateContext) -> UpdateResult {
+            if self.eager || self.pressed {
+                UpdateResult::kill()
+            } else {
+                UpdateResult::nop()
+            }
+        }
+
+        fn handle_event(&mut self, event: EventContext) -> bool {
+            self.pressed = matches!(event.event, Event::Key(_));
+            self.pressed
+        }
+
+        fn cadence(&self) -> Cadence {
+            Cadence::OnChange
+        }
+    }
+
+    fn password_lock(duration: Duration) -> Controller {
+        let credentials = vec![Credential::new(USER_CREDENTIAL, Secret::plain("hunter2"))];
+        LockScreenBuilder::new(duration)
+            .auth(Auth::Password(credentials))
+            .attempt_delay(Duration::ZERO)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn the_lock_ends_when_the_countdown_runs_out() {
+        let _guard = exclusive();
+        let mut controller = LockScreenBuilder::new(Duration::from_millis(1500))
+            .build()
+            .unwrap();
+        let screen = FakeDrawContext::new();
+        controller
+            .run_with(&mut ScriptedEvents::new([]), &mut screen.context())
+            .unwrap();
+        assert!(screen.output().contains("00:01"));
+        assert_eq!(session::stats().exit_reason, "countdown");
+    }
+
+    #[test]
+    fn a_wrong_password_is_reported_and_the_right_one_unlocks() {
+        let _guard = exclusive();
+        let mut controller = password_lock(Duration::from_secs(60));
+        let screen = FakeDrawContext::new();
+        let mut events = ScriptedEvents::typing("guess");
+        for c in "hunter2".chars() {
+            events.push(key(KeyCode::Char(c)));
+        }
+        events.push(key(KeyCode::Enter));
+        controller
+            .run_with(&mut events, &mut screen.context())
+            .unwrap();
+        assert!(screen.output().contains(i18n::t("feedback.wrong_password")));
+        let stats = session::stats();
+        assert_eq!(stats.exit_reason, "password");
+        assert_eq!(stats.failed_attempts, 1);
+        assert_eq!(events.leftover(), 0);
+    }
+
+    #[test]
+    fn a_kill_ends_the_scene_and_the_next_one_starts() {
+        let _guard = exclusive();
+        let mut controller = Controller::new();
+        controller.add_entity(Ender::new("lock", false));
+        controller.then(|| {
+            let mut scene = Scene::new("summary");
+            scene.add_entity(Ender::new("summary", true));
+            Ok(scene)
+        });
+        let screen = FakeDrawContext::new();
+        let mut events = ScriptedEvents::new([key(KeyCode::Char('x')), key(KeyCode::Char('y'))]);
+        controller
+            .run_with(&mut events, &mut screen.context())
+            .unwrap();
+        assert!(screen.output().contains("summary"));
+        // The summary ended by itself, without reading the second key
+        assert_eq!(events.leftover(), 1);
+    }
+
+    #[test]
+    fn quitting_skips_the_scenes_still_queued() {
+        let _guard = exclusive();
+        let mut controller = Controller::new();
+        controller.add_entity(Ender::new("lock", false));
+        controller.then(|| {
+            let mut scene = Scene::new("summary");
+            scene.add_entity(Ender::new("summary", true));
+            Ok(scene)
+        });
+        bus::publish(Message::Quit);
+        let screen = FakeDrawContext::new();
+        controller
+            .run_with(&mut ScriptedEvents::new([]), &mut screen.context())
+            .unwrap();
+        assert!(screen.output().contains("lock"));
+        assert!(!screen.output().contains("summary"));
+    }
+}
@@ -0,0 +1,38 @@
+//! How many terminal columns text takes and which way it runs, so full-width
+//! CJK and right-to-left messages line up.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns `text` occupies; CJK and most emoji take two.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// Hebrew, Arabic and the other scripts written right to left.
+fn is_rtl_char(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF
+    )
+}
+
+/// Whether the first letter of `text` belongs to a right to left script.
+pub fn is_rtl(text: &str) -> bool {
+    text.chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(is_rtl_char)
+}
+
+/// Column to start `text_width` columns at: right aligned within `width` for
+/// right to left text, at the left edge otherwise.
+pub fn start_column(width: u16, text_width: usize, rtl: bool) -> u16 {
+    if rtl {
+        (width as usize).saturating_sub(text_width) as u16
+    } else {
+        0
+    }
+}
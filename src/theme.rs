@@ -0,0 +1,112 @@
+//! Colors and glyphs the entities draw with. `--ascii` swaps in a high
+//! contrast palette and plain ASCII for emoji, for terminals and fonts that
+//! show them as boxes or get their width wrong.
+
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crossterm::style::Color;
+
+pub struct Theme {
+    /// Plenty of time left, unlocked
+    pub ok: Color,
+    /// Milestones, the countdown running low
+    pub warning: Color,
+    /// Wrong passwords, the countdown almost over
+    pub error: Color,
+    /// Plugin output and the breathing bar
+    pub accent: Color,
+    /// Hints that should not draw attention
+    pub muted: Color,
+    pub bar_full: &'static str,
+    pub bar_empty: &'static str,
+    /// What bounces around in the bounce screensaver, two columns wide
+    pub lock: &'static str,
+    pub stars: [&'static str; 4],
+}
+
+const DEFAULT: Theme = Theme {
+    ok: Color::Green,
+    warning: Color::Yellow,
+    error: Color::Red,
+    accent: Color::Cyan,
+    muted: Color::DarkGrey,
+    bar_full: "█",
+    bar_empty: "░",
+    lock: "🔒",
+    stars: ["·", "+", "*", "✦"],
+};
+
+const HIGH_CONTRAST: Theme = Theme {
+    ok: Color::Green,
+    warning: Color::Yellow,
+    error: Color::Red,
+    accent: Color::White,
+    muted: Color::White,
+    bar_full: "#",
+    bar_empty: "-",
+    lock: "[]",
+    stars: [".", "+", "*", "x"],
+};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Emoji we use ourselves and what stands in for them. Any other emoji is
+/// dropped.
+const FALLBACKS: &[(char, &str)] = &[
+    ('🔒', "[locked]"),
+    ('🔓', "[unlocked]"),
+    ('💖', "<3"),
+    ('❌', "[x]"),
+    ('⚠', "[!]"),
+    ('⏳', "[~]"),
+    ('⌨', "[kbd]"),
+];
+
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+pub fn current() -> &'static Theme {
+    if is_ascii() {
+        &HIGH_CONTRAST
+    } else {
+        &DEFAULT
+    }
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x2300..=0x23FF | 0x2600..=0x27BF | 0x1F000..=0x1FAFF | 0xFE0F | 0x200D
+    )
+}
+
+/// `text` as it should be shown: with `--ascii`, emoji are replaced by their
+/// fallbacks or dropped along with the space after them.
+pub fn glyphs(text: &str) -> Cow<'_, str> {
+    if !is_ascii() || !text.chars().any(is_emoji) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some((_, fallback)) = FALLBACKS.iter().find(|(emoji, _)| *emoji == c) {
+            out.push_str(fallback);
+        } else if is_emoji(c) {
+            while chars.next_if(|c| *c == '\u{FE0F}').is_some() {}
+            if out.is_empty() || out.ends_with(' ') {
+                chars.next_if_eq(&' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
@@ -0,0 +1,195 @@
+//! Time-based one-time passwords (RFC 6238) as produced by common
+//! authenticator apps: HMAC-SHA1, 30 second steps, 6 digits.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+const STEP_SECS: u64 = 30;
+const DIGITS: u32 = 6;
+/// How far back `Totp::expired` looks, five minutes
+const EXPIRED_STEPS: u64 = 10;
+
+fn decode_base32(encoded: &str) -> anyhow::Result<Vec<u8>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut out = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let Some(value) = ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())
+        else {
+            anyhow::bail!("'{c}' is not a base32 character");
+        };
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// The value of a code typed as exactly `DIGITS` digits, leading zeros
+/// included. Anything shorter, signed or otherwise different is no code.
+fn parse_code(code: &str) -> Option<u32> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    code.parse().ok()
+}
+
+#[derive(Clone)]
+pub struct Totp {
+    secret: Vec<u8>,
+    /// The step of the last code accepted, shared by the clones so that no
+    /// lock of this run takes a code, or an older one, a second time
+    last_used: Arc<AtomicU64>,
+}
+
+impl Totp {
+    /// Build from the base32 secret authenticator apps are provisioned with.
+    pub fn from_base32(secret: &str) -> anyhow::Result<Self> {
+        let secret = decode_base32(secret)?;
+        if secret.is_empty() {
+            anyhow::bail!("empty TOTP secret");
+        }
+        Ok(Totp {
+            secret,
+            last_used: Arc::default(),
+        })
+    }
+
+    fn code_at(&self, counter: u64) -> u32 {
+        let mut mac =
+            Hmac::<Sha1>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(&counter.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        value % 10u32.pow(DIGITS)
+    }
+
+    fn current_counter() -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        now / STEP_SECS
+    }
+
+    /// Check `code` against the current step, tolerating one step of clock
+    /// drift either way. A code is good once: neither it nor one of an
+    /// earlier step is accepted after it (RFC 6238 section 5.2).
+    pub fn verify(&self, code: &str) -> bool {
+        self.verify_at(code, Self::current_counter())
+    }
+
+    fn verify_at(&self, code: &str, counter: u64) -> bool {
+        let Some(code) = parse_code(code) else {
+            return false;
+        };
+        // Check every window, a match must not show in the timing
+        let matched = [counter.saturating_sub(1), counter, counter + 1]
+            .into_iter()
+            .fold(None, |matched, c| {
+                if self.code_at(c) == code {
+                    Some(c)
+                } else {
+                    matched
+                }
+            });
+        matched.is_some_and(|step| self.last_used.fetch_max(step, Ordering::SeqCst) < step)
+    }
+
+    /// Whether `code` was valid during the last few minutes but no longer is,
+    /// e.g. read off the app just before it changed.
+    pub fn expired(&self, code: &str) -> bool {
+        self.expired_at(code, Self::current_counter())
+    }
+
+    fn expired_at(&self, code: &str, counter: u64) -> bool {
+        let Some(code) = parse_code(code) else {
+            return false;
+        };
+        (2..=EXPIRED_STEPS).any(|back| self.code_at(counter.saturating_sub(back)) == code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA1 secret of RFC 6238 appendix B, "12345678901234567890"
+    fn rfc_6238() -> Totp {
+        Totp::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap()
+    }
+
+    #[test]
+    fn codes_match_the_rfc_6238_vectors() {
+        let totp = rfc_6238();
+        // The RFC lists 8 digits, these are their last 6
+        let vectors = [
+            (59, 287082),
+            (1111111109, 81804),
+            (1111111111, 50471),
+            (1234567890, 5924),
+            (2000000000, 279037),
+            (20000000000, 353130),
+        ];
+        for (time, code) in vectors {
+            assert_eq!(totp.code_at(time / STEP_SECS), code, "at {time}");
+        }
+    }
+
+    #[test]
+    fn codes_are_exactly_six_digits() {
+        assert_eq!(parse_code("081804"), Some(81804));
+        assert_eq!(parse_code(" 287082\n"), Some(287082));
+        assert_eq!(parse_code("81804"), None);
+        assert_eq!(parse_code("2870823"), None);
+        assert_eq!(parse_code("+81804"), None);
+        assert_eq!(parse_code("28 082"), None);
+        assert_eq!(parse_code(""), None);
+    }
+
+    #[test]
+    fn a_code_is_accepted_once() {
+        let totp = rfc_6238();
+        let counter = 1234567890 / STEP_SECS;
+        let previous = format!("{:06}", totp.code_at(counter - 1));
+        let current = format!("{:06}", totp.code_at(counter));
+        assert!(totp.clone().verify_at(&current, counter));
+        assert!(!totp.verify_at(&current, counter));
+        assert!(!totp.verify_at(&previous, counter));
+        let next = format!("{:06}", totp.code_at(counter + 1));
+        assert!(totp.verify_at(&next, counter + 1));
+    }
+
+    #[test]
+    fn codes_of_a_few_minutes_ago_are_expired() {
+        let totp = rfc_6238();
+        let counter = 1234567890 / STEP_SECS;
+        let code = |step| format!("{:06}", totp.code_at(step));
+        assert!(totp.expired_at(&code(counter - 2), counter));
+        assert!(totp.expired_at(&code(counter - EXPIRED_STEPS), counter));
+        assert!(!totp.expired_at(&code(counter - EXPIRED_STEPS - 1), counter));
+        assert!(!totp.expired_at(&code(counter - 1), counter));
+        assert!(!totp.expired_at("12345", counter));
+    }
+}
@@ -0,0 +1,152 @@
+//! `--lock-ttys`: without root, VT switching can't be inhibited, and a
+//! session logged in on another virtual console is one Ctrl+Alt+F2 away.
+//! This starts a lock instance on each of those consoles instead, and stops
+//! whatever runs in their foreground meanwhile so it doesn't compete for the
+//! keys. Linux only.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    os::{
+        fd::AsRawFd,
+        unix::{fs::MetadataExt, process::CommandExt},
+    },
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+use tracing::{info, warn};
+
+/// Virtual consoles the kernel can have.
+const MAX_VT: u32 = 63;
+
+// From linux/kd.h
+const KDGETMODE: libc::c_ulong = 0x4B3B;
+const KD_GRAPHICS: libc::c_int = 1;
+
+/// The lock instances started by `lock_others`, ended when dropped.
+pub struct TtyLocks(Vec<TtyLock>);
+
+struct TtyLock {
+    path: PathBuf,
+    child: Child,
+    /// The foreground process group we stopped
+    stopped: Option<libc::pid_t>,
+}
+
+impl Drop for TtyLocks {
+    fn drop(&mut self) {
+        for lock in &mut self.0 {
+            // SAFETY: plain syscalls on processes we started or stopped
+            unsafe { libc::kill(lock.child.id() as libc::pid_t, libc::SIGTERM) };
+            let _ = lock.child.wait();
+            if let Some(pgrp) = lock.stopped {
+                unsafe { libc::kill(-pgrp, libc::SIGCONT) };
+            }
+            info!(tty = %lock.path.display(), "unlocked console");
+        }
+    }
+}
+
+/// `tty_nr` in /proc/<pid>/stat for the device `rdev`.
+fn tty_nr(rdev: u64) -> u64 {
+    let (major, minor) = (libc::major(rdev) as u64, libc::minor(rdev) as u64);
+    (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12)
+}
+
+/// The foreground process group of the console `rdev`, read off any process
+/// running on it.
+fn foreground_group(rdev: u64) -> Option<libc::pid_t> {
+    let tty = tty_nr(rdev);
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let stat = fs::read_to_string(entry.path().join("stat")).ok()?;
+        // The command name in parentheses may contain spaces
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        let (tty_nr, tpgid) = (fields.get(4)?.parse::<u64>().ok()?, fields.get(5)?);
+        (tty_nr == tty)
+            .then(|| tpgid.parse::<libc::pid_t>().ok())
+            .flatten()
+            .filter(|pgrp| *pgrp > 0)
+    })
+}
+
+/// Whether the console at `path` shows a graphical session. Stopping its
+/// foreground would freeze the display server.
+fn graphical(path: &PathBuf) -> bool {
+    let Ok(console) = OpenOptions::new().read(true).open(path) else {
+        return true;
+    };
+    let mut mode: libc::c_int = 0;
+    // SAFETY: KDGETMODE writes one int into `mode`, the descriptor is open
+    let result = unsafe { libc::ioctl(console.as_raw_fd(), KDGETMODE as _, &mut mode) };
+    result != 0 || mode == KD_GRAPHICS
+}
+
+/// Text consoles someone is logged in on, which login hands to the user,
+/// apart from the one we run on.
+fn logged_in_consoles() -> Vec<(PathBuf, u64)> {
+    let own = fs::metadata("/proc/self/fd/0").map(|meta| meta.rdev()).ok();
+    (1..=MAX_VT)
+        .map(|n| PathBuf::from(format!("/dev/tty{n}")))
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            (meta.uid() != 0 && Some(meta.rdev()) != own && !graphical(&path))
+                .then_some((path, meta.rdev()))
+        })
+        .collect()
+}
+
+fn spawn(path: &PathBuf, args: &[OsString]) -> anyhow::Result<Child> {
+    let tty = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .stdin(Stdio::from(tty.try_clone()?))
+        .stdout(Stdio::from(tty.try_clone()?))
+        .stderr(Stdio::from(tty));
+    // SAFETY: only async-signal-safe calls between fork and exec
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            // Go down with us, however we end
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM);
+            Ok(())
+        })
+    };
+    Ok(command.spawn()?)
+}
+
+/// Lock every other console someone is logged in on for `duration`. The
+/// instances get our own arguments, so they look and unlock the same.
+pub fn lock_others(duration: Duration) -> TtyLocks {
+    let mut args: Vec<OsString> = std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--lock-ttys")
+        .collect();
+    args.push("--tty-child".into());
+    args.push(format!("{}s", duration.as_secs()).into());
+
+    let mut locks = Vec::new();
+    for (path, rdev) in logged_in_consoles() {
+        let child = match spawn(&path, &args) {
+            Ok(child) => child,
+            Err(error) => {
+                warn!(tty = %path.display(), %error, "could not lock console");
+                continue;
+            }
+        };
+        let stopped = foreground_group(rdev);
+        if let Some(pgrp) = stopped {
+            // SAFETY: plain syscall, continued again when dropped
+            unsafe { libc::kill(-pgrp, libc::SIGSTOP) };
+        }
+        info!(tty = %path.display(), ?stopped, "locked console");
+        locks.push(TtyLock {
+            path,
+            child,
+            stopped,
+        });
+    }
+    TtyLocks(locks)
+}
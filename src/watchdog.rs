@@ -0,0 +1,98 @@
+//! `--exec-shield`: the lock runs as a child in a session of its own, so
+//! hangups and job control signals from the terminal's shell don't reach it,
+//! while this process stays behind as its watchdog. If the lock is killed
+//! the watchdog starts it again with the time it had left.
+
+use std::{
+    os::unix::process::{CommandExt, ExitStatusExt},
+    process::Command,
+    sync::atomic::{AtomicI32, Ordering},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Context;
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM, SIGTSTP},
+    iterator::Signals,
+};
+use tracing::{info, warn};
+
+/// Set in the child, which runs the lock instead of watching.
+pub const SHIELDED_ENV: &str = "SCREENLOCK_SHIELDED";
+/// Set when relaunching a killed lock, which then resumes from the state
+/// file.
+pub const RESUME_ENV: &str = "SCREENLOCK_RESUME";
+
+/// Keeps a killed lock from relaunching in a tight loop.
+const RELAUNCH_DELAY: Duration = Duration::from_secs(1);
+
+/// The running lock, 0 while there is none.
+static CHILD: AtomicI32 = AtomicI32::new(0);
+
+pub fn is_shielded() -> bool {
+    std::env::var_os(SHIELDED_ENV).is_some()
+}
+
+pub fn is_resuming() -> bool {
+    std::env::var_os(RESUME_ENV).is_some()
+}
+
+/// Pass SIGTERM on to the lock, which decides what to do with it, and shrug
+/// off the rest: the watchdog only goes away when the lock ends.
+fn handle_signals() -> anyhow::Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP, SIGTSTP])?;
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            let child = CHILD.load(Ordering::Relaxed);
+            if signal == SIGTERM && child != 0 {
+                // SAFETY: plain syscall
+                unsafe { libc::kill(child, SIGTERM) };
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Run the lock as a shielded child until it exits on its own, relaunching
+/// it whenever it was killed. Returns the exit code to leave with.
+pub fn run() -> anyhow::Result<i32> {
+    handle_signals()?;
+    let exe = std::env::current_exe().context("could not find our own executable")?;
+    let mut resume = false;
+    loop {
+        let mut command = Command::new(&exe);
+        command
+            .args(std::env::args_os().skip(1))
+            .env(SHIELDED_ENV, "1");
+        if resume {
+            command.env(RESUME_ENV, "1");
+        }
+        // SAFETY: setsid is async-signal-safe
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = command
+            .spawn()
+            .context("could not start the shielded lock")?;
+        CHILD.store(child.id() as i32, Ordering::Relaxed);
+        let status = child.wait()?;
+        CHILD.store(0, Ordering::Relaxed);
+        match status.signal() {
+            Some(signal) => {
+                warn!(signal, "lock was killed, relaunching");
+                resume = true;
+                thread::sleep(RELAUNCH_DELAY);
+            }
+            None => {
+                info!(%status, "lock exited");
+                return Ok(status.code().unwrap_or(1));
+            }
+        }
+    }
+}